@@ -2,14 +2,16 @@
 mod netpacket;
 
 use netpacket::{
-    const_size::impl_derive_const_size, deserialize::impl_derive_deserialize,
-    serialize::impl_derive_serialize,
+    const_size::impl_derive_const_size, convert::impl_derive_packet_convert,
+    deserialize::impl_derive_deserialize, dyn_size::impl_derive_dyn_size,
+    packet_id::impl_derive_packet_id, serialize::impl_derive_serialize,
 };
 use proc_macro::TokenStream;
 use syn::{DeriveInput, Error, parse_macro_input};
 
 /// Derive macro to evaluate the static size of a serialized struct in bytes
-#[proc_macro_derive(ConstSize)]
+/// Without a context, `SerializeWith` and `DeserializeWith` are also implemented for every context.
+#[proc_macro_derive(ConstSize, attributes(netpacket))]
 pub fn derive_const_size(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     match impl_derive_const_size(&input) {
@@ -18,8 +20,9 @@ pub fn derive_const_size(input: TokenStream) -> TokenStream {
     }
 }
 
-/// Derive macro to serialize a struct into a packet
-#[proc_macro_derive(Serialize)]
+/// Derive macro to serialize a struct into a packet.
+/// With `#[netpacket(context = Type)]` the `SerializeWith<Type>` trait is implemented instead.
+#[proc_macro_derive(Serialize, attributes(netpacket))]
 pub fn derive_serialize(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     match impl_derive_serialize(&input) {
@@ -28,8 +31,9 @@ pub fn derive_serialize(input: TokenStream) -> TokenStream {
     }
 }
 
-/// Derive macro to deserialize a struct from packet
-#[proc_macro_derive(Deserialize)]
+/// Derive macro to deserialize a struct from packet.
+/// With `#[netpacket(context = Type)]` the `DeserializeWith<Type>` trait is implemented instead.
+#[proc_macro_derive(Deserialize, attributes(netpacket))]
 pub fn derive_deserialize(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     match impl_derive_deserialize(&input) {
@@ -37,3 +41,36 @@ pub fn derive_deserialize(input: TokenStream) -> TokenStream {
         Err(err) => Error::from(err).to_compile_error().into(),
     }
 }
+
+/// Derive macro to evaluate the size of a serialized value in bytes,
+/// for enums with payload whose size depends on the variant.
+/// Without a context, `SerializeWith` and `DeserializeWith` are also implemented for every context.
+#[proc_macro_derive(DynSize, attributes(netpacket))]
+pub fn derive_dyn_size(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match impl_derive_dyn_size(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => Error::from(err).to_compile_error().into(),
+    }
+}
+
+/// Derive macro to assign the stable identifier given by `#[netpacket(id = N)]` to a packet type.
+#[proc_macro_derive(PacketId, attributes(netpacket))]
+pub fn derive_packet_id(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match impl_derive_packet_id(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => Error::from(err).to_compile_error().into(),
+    }
+}
+
+/// Derive macro to convert a packet to and from a godot `Dictionary` holding its fields.
+/// Enums are stored as a dictionary holding the name of the `variant` and its `fields`.
+#[proc_macro_derive(PacketConvert, attributes(netpacket))]
+pub fn derive_packet_convert(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match impl_derive_packet_convert(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => Error::from(err).to_compile_error().into(),
+    }
+}
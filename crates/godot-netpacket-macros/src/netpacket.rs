@@ -9,9 +9,25 @@ pub mod serialize;
 /// Implement derive macro for Deserialize trait
 pub mod deserialize;
 
+/// Implement derive macro for DynSize trait
+pub mod dyn_size;
+
+/// Implement derive macro for PacketId trait
+pub mod packet_id;
+
+/// Implement derive macro for godot-rust ToGodot and FromGodot traits
+pub mod convert;
+
+/// Implement PacketSchema trait along with the Serialize derive
+pub mod schema;
+
+/// Parse the options provided to the derive macros
+pub mod attributes;
+
+use attributes::{FieldAttributes, TypeAttributes};
 use proc_macro2::TokenStream;
 use quote::{ToTokens, format_ident, quote};
-use syn::{Field, Fields};
+use syn::{DeriveInput, Field, Fields, Index, Type};
 
 /// Error encountered when implementing traits
 #[derive(thiserror::Error, Debug)]
@@ -24,12 +40,25 @@ pub(crate) enum Error {
 
     #[error("Cannot deduce constant size of enum with payload")]
     NonConstSize,
+
+    #[error("Missing packet identifier, use #[netpacket(id = ...)]")]
+    MissingId,
+
+    #[error("Interned fields require a context and have no constant size")]
+    InternWithoutContext,
+
+    #[error(transparent)]
+    Attribute(#[from] syn::Error),
 }
 
 /// Convert the error into a syn::Error
 impl From<Error> for syn::Error {
     fn from(value: Error) -> Self {
-        syn::Error::new_spanned(TokenStream::new(), value.to_string())
+        match value {
+            // keep the location of the invalid attribute
+            Error::Attribute(err) => err,
+            _ => syn::Error::new_spanned(TokenStream::new(), value.to_string()),
+        }
     }
 }
 
@@ -44,7 +73,7 @@ fn write_structuring(fields: &Fields) -> Vec<TokenStream> {
         let real_name = if let Some(ident) = &field.ident {
             ident.to_token_stream()
         } else {
-            quote! [#index]
+            Index::from(index).to_token_stream()
         };
         let tmp_name = format_ident!("__field_{}", index);
 
@@ -57,12 +86,80 @@ fn write_structuring(fields: &Fields) -> Vec<TokenStream> {
     entries
 }
 
-/// Generate a list of offsets for each of the fields.
-fn write_offsets(fields: &Fields) -> Vec<TokenStream> {
-    let mut offsets = Vec::with_capacity(fields.len());
+/// Fields of a type, split between the flags packed into the leading bitfield
+/// and the values serialized after it. Each field is kept with its index.
+struct Layout<'a> {
+    /// Fields stored as a single bit
+    flags: Vec<(usize, &'a Field)>,
 
-    // for each field get its static size
-    for (index, field) in fields.iter().enumerate() {
+    /// Fields stored after the bitfield
+    values: Vec<(usize, &'a Field)>,
+
+    /// Index of the fields interned using the table provided by the context
+    interned: Vec<usize>,
+}
+
+impl<'a> Layout<'a> {
+    /// Split the fields, `bool` fields and fields marked as flags go into the bitfield
+    fn new(fields: &'a Fields) -> Result<Self, Error> {
+        let mut flags = Vec::new();
+        let mut values = Vec::with_capacity(fields.len());
+        let mut interned = Vec::new();
+
+        for (index, field) in fields.iter().enumerate() {
+            let attributes = FieldAttributes::parse(&field.attrs)?;
+            if attributes.flag || is_bool(&field.ty) {
+                flags.push((index, field));
+            } else {
+                values.push((index, field));
+            }
+
+            if attributes.intern {
+                interned.push(index);
+            }
+        }
+
+        Ok(Self {
+            flags,
+            values,
+            interned,
+        })
+    }
+
+    /// Create the layout of a type without context, rejecting fields needing one
+    fn without_context(fields: &'a Fields) -> Result<Self, Error> {
+        let layout = Self::new(fields)?;
+        if !layout.interned.is_empty() {
+            return Err(Error::InternWithoutContext);
+        }
+        Ok(layout)
+    }
+
+    /// Check if the field at the given index is interned
+    fn is_interned(&self, index: usize) -> bool {
+        self.interned.contains(&index)
+    }
+
+    /// Number of bytes used by the bitfield
+    fn flag_bytes(&self) -> usize {
+        self.flags.len().div_ceil(8)
+    }
+}
+
+/// Check if the type is a plain boolean
+fn is_bool(atype: &Type) -> bool {
+    match atype {
+        Type::Path(path) => path.qself.is_none() && path.path.is_ident("bool"),
+        _ => false,
+    }
+}
+
+/// Generate a list of offsets for each of the values.
+fn write_offsets(layout: &Layout) -> Vec<TokenStream> {
+    let mut offsets = Vec::with_capacity(layout.values.len());
+
+    // for each value get its static size
+    for (index, (_, field)) in layout.values.iter().enumerate() {
         // prepare tokens for the macro
         let size = to_size(field);
         let offset_prev = format_ident!("__OFFSET_{}", index);
@@ -83,6 +180,53 @@ fn to_size(field: &Field) -> TokenStream {
     quote! [ <#atype as __net::ConstSize>::SIZE ]
 }
 
+/// Implement SerializeWith and DeserializeWith for any context by forwarding to Serialize and Deserialize,
+/// for the types knowing their size and not declaring a context.
+/// The bounds are higher-ranked so that they are only checked on use,
+/// types implementing only one of Serialize and Deserialize get only the matching trait.
+fn impl_contextless(input: &DeriveInput) -> Result<TokenStream, Error> {
+    let attributes = TypeAttributes::parse(&input.attrs)?;
+    if attributes.context.is_some() {
+        return Ok(TokenStream::new());
+    }
+
+    let atype = &input.ident;
+    let tokens = quote! [
+        impl<__C> __net::SerializeWith<__C> for #atype
+        where
+            for<'__a> #atype: __net::Serialize,
+        {
+            #[inline]
+            fn serialize_with(
+                &self,
+                _ctx: &mut __C,
+                __buffer: &mut __godot::PackedByteArray,
+                __offset: usize
+            ) -> core::result::Result<usize, ()> {
+                __net::serialize_contextless(self, __buffer, __offset)
+            }
+        }
+
+        impl<__C> __net::DeserializeWith<__C> for #atype
+        where
+            for<'__a> #atype: __net::Deserialize,
+        {
+            #[inline]
+            fn deserialize_with(
+                _ctx: &mut __C,
+                __buffer: &__godot::PackedByteArray,
+                __offset: usize
+            ) -> core::result::Result<(Self, usize), ()>
+            where
+                Self: Sized,
+            {
+                __net::deserialize_contextless(__buffer, __offset)
+            }
+        }
+    ];
+    Ok(encapsulate(&tokens))
+}
+
 /// Encapsulate the token stream into a scope with the necessary modules
 fn encapsulate(tokens: &TokenStream) -> TokenStream {
     quote![
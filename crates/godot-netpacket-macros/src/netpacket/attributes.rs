@@ -0,0 +1,81 @@
+use super::Error;
+use syn::{Attribute, LitInt, Type};
+
+/// Options of a type provided through `#[netpacket(...)]` attributes
+#[derive(Default)]
+pub(crate) struct TypeAttributes {
+    /// Type of the context to use for serialization and deserialization
+    pub(crate) context: Option<Type>,
+
+    /// Stable identifier of the packet type
+    pub(crate) id: Option<u16>,
+}
+
+impl TypeAttributes {
+    /// Parse the options from the attributes of a type
+    pub(crate) fn parse(attrs: &[Attribute]) -> Result<Self, Error> {
+        let mut options = Self::default();
+
+        for attr in attrs {
+            if !attr.path().is_ident("netpacket") {
+                continue;
+            }
+
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("context") {
+                    options.context = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("id") {
+                    let id: LitInt = meta.value()?.parse()?;
+                    options.id = Some(id.base10_parse()?);
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported netpacket attribute"))
+                }
+            })?;
+        }
+
+        Ok(options)
+    }
+}
+
+/// Options of a field provided through `#[netpacket(...)]` attributes
+#[derive(Default)]
+pub(crate) struct FieldAttributes {
+    /// Store the field as a single bit in the leading bitfield
+    pub(crate) flag: bool,
+
+    /// Intern the string using the table provided by the context
+    pub(crate) intern: bool,
+}
+
+impl FieldAttributes {
+    /// Parse the options from the attributes of a field
+    pub(crate) fn parse(attrs: &[Attribute]) -> Result<Self, Error> {
+        let mut options = Self::default();
+
+        for attr in attrs {
+            if !attr.path().is_ident("netpacket") {
+                continue;
+            }
+
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("flag") {
+                    options.flag = true;
+                    Ok(())
+                } else if meta.path.is_ident("intern") {
+                    options.intern = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported netpacket attribute"))
+                }
+            })?;
+
+            if options.flag && options.intern {
+                return Err(syn::Error::new_spanned(attr, "a flag cannot be interned").into());
+            }
+        }
+
+        Ok(options)
+    }
+}
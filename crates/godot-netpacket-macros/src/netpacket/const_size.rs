@@ -1,20 +1,32 @@
-use super::{Error, encapsulate, to_size};
+use super::{Error, Layout, attributes::TypeAttributes, encapsulate, impl_contextless, to_size};
 use proc_macro2::TokenStream;
 use quote::quote;
-use syn::{Data, DeriveInput, Fields};
+use syn::{Data, DeriveInput};
 
-/// Implement ConstSize derive
+/// Implement ConstSize derive, along with the context-free SerializeWith and DeserializeWith
 pub(crate) fn impl_derive_const_size(input: &DeriveInput) -> Result<TokenStream, Error> {
+    let size = impl_const_size(input)?;
+    let contextless = impl_contextless(input)?;
+    Ok(quote! [ #size #contextless ])
+}
+
+/// Implement ConstSize trait
+fn impl_const_size(input: &DeriveInput) -> Result<TokenStream, Error> {
+    // The size does not depend on the context, only check the attributes are valid
+    TypeAttributes::parse(&input.attrs)?;
+
     let atype = &input.ident;
     match &input.data {
         // Implement for struct
         Data::Struct(astruct) => {
-            let sizes = list_sizes(&astruct.fields);
+            let layout = Layout::without_context(&astruct.fields)?;
+            let flag_bytes = layout.flag_bytes();
+            let sizes = list_sizes(&layout);
 
             // write the implementation
             let tokens = quote![
                 impl __net::ConstSize for #atype {
-                    const SIZE: usize = #(#sizes)+*;
+                    const SIZE: usize = #flag_bytes #(+ #sizes)*;
                 }
             ];
             Ok(encapsulate(&tokens))
@@ -47,12 +59,12 @@ pub(crate) fn impl_derive_const_size(input: &DeriveInput) -> Result<TokenStream,
     }
 }
 
-/// Create an array with an expression to evaluate the size of each field.
-fn list_sizes(fields: &Fields) -> Vec<TokenStream> {
-    let mut sizes = Vec::with_capacity(fields.len());
+/// Create an array with an expression to evaluate the size of each value.
+fn list_sizes(layout: &Layout) -> Vec<TokenStream> {
+    let mut sizes = Vec::with_capacity(layout.values.len());
 
-    // for each field get its static size
-    for field in fields {
+    // for each value get its static size, flags are counted in the bitfield
+    for (_, field) in &layout.values {
         sizes.push(to_size(field));
     }
 
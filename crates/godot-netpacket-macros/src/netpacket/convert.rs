@@ -0,0 +1,165 @@
+use super::{Error, Layout, attributes::TypeAttributes, encapsulate, write_structuring};
+use proc_macro2::TokenStream;
+use quote::{ToTokens, format_ident, quote};
+use syn::{Data, DeriveInput, Fields, Index};
+
+/// Implement PacketConvert derive
+pub(crate) fn impl_derive_packet_convert(input: &DeriveInput) -> Result<TokenStream, Error> {
+    // The conversion does not depend on the context, only check the attributes are valid
+    TypeAttributes::parse(&input.attrs)?;
+
+    let atype = &input.ident;
+    let (to_godot, from_godot) = match &input.data {
+        // Implement for struct, the fields are stored in the dictionary
+        Data::Struct(astruct) => {
+            let vars = write_structuring(&astruct.fields);
+            let inserts = write_inserts(&astruct.fields)?;
+            let gets = write_gets(&astruct.fields)?;
+
+            let to_godot = quote! [
+                let Self { #(#vars),* } = self;
+                let mut __data = __godot::VarDictionary::new();
+                #(#inserts)*
+                __data
+            ];
+            let from_godot = quote! [
+                let __data = &via;
+                core::result::Result::Ok(Self { #(#gets),* })
+            ];
+            (to_godot, from_godot)
+        }
+
+        // Implement for enum, the dictionary holds the name of the variant and its fields
+        Data::Enum(anenum) => {
+            let mut to_entries = Vec::with_capacity(anenum.variants.len());
+            let mut from_entries = Vec::with_capacity(anenum.variants.len());
+
+            for variant in &anenum.variants {
+                // Evaluate tokens
+                let name = &variant.ident;
+                let label = name.to_string();
+                let vars = write_structuring(&variant.fields);
+                let inserts = write_inserts(&variant.fields)?;
+                let gets = write_gets(&variant.fields)?;
+
+                to_entries.push(quote! [
+                    Self::#name { #(#vars),* } => {
+                        let mut __data = __godot::VarDictionary::new();
+                        #(#inserts)*
+                        __net::variant_to_godot(#label, __data)
+                    }
+                ]);
+                from_entries.push(quote! [
+                    #label => core::result::Result::Ok(Self::#name { #(#gets),* })
+                ]);
+            }
+
+            let to_godot = quote! [
+                match self {
+                    #(#to_entries),*
+                }
+            ];
+            let from_godot = quote! [
+                let (__variant, __fields) = __net::variant_from_godot(&via)?;
+                let __data = &__fields;
+                match __variant.as_str() {
+                    #(#from_entries,)*
+                    _ => core::result::Result::Err(__net::unknown_variant(&__variant)),
+                }
+            ];
+            (to_godot, from_godot)
+        }
+        _ => return Err(Error::Union),
+    };
+
+    // write the implementation
+    let tokens = quote! [
+        use godot::meta as __meta;
+
+        impl __meta::GodotConvert for #atype {
+            type Via = __godot::VarDictionary;
+        }
+
+        impl __meta::ToGodot for #atype {
+            type Pass = __meta::ByValue;
+
+            #[allow(unused_variables, unused_mut)]
+            fn to_godot(&self) -> __godot::VarDictionary {
+                #to_godot
+            }
+        }
+
+        impl __meta::FromGodot for #atype {
+            #[allow(unused_variables)]
+            fn try_from_godot(
+                via: __godot::VarDictionary,
+            ) -> core::result::Result<Self, __meta::error::ConvertError> {
+                #from_godot
+            }
+        }
+    ];
+    Ok(encapsulate(&tokens))
+}
+
+/// Name of the field used as the key of the dictionary, tuple fields use their index
+fn keys(fields: &Fields) -> Vec<(TokenStream, String)> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| match &field.ident {
+            Some(ident) => (ident.to_token_stream(), ident.to_string()),
+            None => (Index::from(index).to_token_stream(), index.to_string()),
+        })
+        .collect()
+}
+
+/// Generate the statements inserting each field into `__data`, in declaration order
+fn write_inserts(fields: &Fields) -> Result<Vec<TokenStream>, Error> {
+    let layout = Layout::new(fields)?;
+    let keys = keys(fields);
+    let mut inserts = Vec::with_capacity(fields.len());
+
+    for (index, field) in fields.iter().enumerate() {
+        let atype = &field.ty;
+        let label = &keys[index].1;
+        let var = format_ident!("__field_{}", index);
+
+        // flags are stored as booleans
+        let value = if layout.flags.iter().any(|(flag, _)| *flag == index) {
+            quote! [ <#atype as __net::Flag>::to_flag(#var) ]
+        } else {
+            quote! [ __meta::ToGodot::to_variant(#var) ]
+        };
+        inserts.push(quote! [
+            __data.set(#label, #value);
+        ]);
+    }
+
+    Ok(inserts)
+}
+
+/// Generate the `real_name: value` mappings reading each field from `__data`
+fn write_gets(fields: &Fields) -> Result<Vec<TokenStream>, Error> {
+    let layout = Layout::new(fields)?;
+    let keys = keys(fields);
+    let mut gets = Vec::with_capacity(fields.len());
+
+    for (index, field) in fields.iter().enumerate() {
+        let atype = &field.ty;
+        let (real_name, label) = &keys[index];
+
+        // flags are read as booleans
+        let value = if layout.flags.iter().any(|(flag, _)| *flag == index) {
+            quote! [
+                <#atype as __net::Flag>::from_flag(__net::field_from_godot::<bool>(__data, #label)?)
+            ]
+        } else {
+            quote! [ __net::field_from_godot::<#atype>(__data, #label)? ]
+        };
+        gets.push(quote! [
+            #real_name: #value
+        ]);
+    }
+
+    Ok(gets)
+}
@@ -1,18 +1,31 @@
-use super::{Error, encapsulate, write_offsets, write_structuring};
-use proc_macro2::TokenStream;
+use super::{
+    Error, Layout, attributes::TypeAttributes, encapsulate, write_offsets, write_structuring,
+};
+use proc_macro2::{Ident, TokenStream};
 use quote::{format_ident, quote};
-use syn::{Data, DeriveInput, Fields};
+use syn::{Data, DeriveInput, Type};
 
 /// Implement Deserialize derive
 pub(crate) fn impl_derive_deserialize(input: &DeriveInput) -> Result<TokenStream, Error> {
+    // Use the context-aware trait if a context is provided
+    let attributes = TypeAttributes::parse(&input.attrs)?;
+    if let Some(context) = &attributes.context {
+        return impl_derive_deserialize_with(input, context);
+    }
+
     let atype = &input.ident;
     match &input.data {
         // Implement for struct
         Data::Struct(astruct) => {
             // Evaluate tokens
+            let layout = Layout::without_context(&astruct.fields)?;
+            let flag_bytes = layout.flag_bytes();
             let vars = write_structuring(&astruct.fields);
-            let offsets = write_offsets(&astruct.fields);
-            let deserials = write_deserial(&astruct.fields);
+            let names = flag_names(&layout);
+            let positions = 0..flag_bytes;
+            let flags = write_flags(&layout);
+            let offsets = write_offsets(&layout);
+            let deserials = write_deserial(&layout);
 
             // write the implementation
             let tokens = quote! [
@@ -24,7 +37,10 @@ pub(crate) fn impl_derive_deserialize(input: &DeriveInput) -> Result<TokenStream
                         where
                             Self: core::marker::Sized
                     {
-                        const __OFFSET_0: usize = 0;
+                        #(let #names = __buffer.decode_u8(__offset + #positions)?;)*
+                        #(#flags)*
+
+                        const __OFFSET_0: usize = #flag_bytes;
                         #(#offsets)*
                         #(#deserials)*
 
@@ -46,12 +62,21 @@ pub(crate) fn impl_derive_deserialize(input: &DeriveInput) -> Result<TokenStream
                 // Evaluate tokens
                 let index = index as u8;
                 let name = &variant.ident;
+                let layout = Layout::without_context(&variant.fields)?;
+                let flag_bytes = layout.flag_bytes();
                 let vars = write_structuring(&variant.fields);
-                let offsets = write_offsets(&variant.fields);
-                let deserials = write_deserial(&variant.fields);
+                let names = flag_names(&layout);
+                let positions = 1..(1 + flag_bytes);
+                let flags = write_flags(&layout);
+                let offsets = write_offsets(&layout);
+                let deserials = write_deserial(&layout);
 
                 entries.push(quote! [
                     #index => {
+                        #(let #names = __buffer.decode_u8(__offset + #positions)?;)*
+                        #(#flags)*
+
+                        const __OFFSET_0: usize = 1 + #flag_bytes;
                         #(#offsets)*
                         #(#deserials)*
 
@@ -71,7 +96,6 @@ pub(crate) fn impl_derive_deserialize(input: &DeriveInput) -> Result<TokenStream
                             Self: core::marker::Sized
                     {
                         let id = __buffer.decode_u8(__offset)?;
-                        const __OFFSET_0: usize = 1;
 
                         match id {
                             #(#entries),*
@@ -87,17 +111,160 @@ pub(crate) fn impl_derive_deserialize(input: &DeriveInput) -> Result<TokenStream
     }
 }
 
-/// Call deserialize for each field
-fn write_deserial(fields: &Fields) -> Vec<TokenStream> {
+/// Implement DeserializeWith derive for the given context
+fn impl_derive_deserialize_with(input: &DeriveInput, context: &Type) -> Result<TokenStream, Error> {
+    let atype = &input.ident;
+    match &input.data {
+        // Implement for struct
+        Data::Struct(astruct) => {
+            // Evaluate tokens
+            let layout = Layout::new(&astruct.fields)?;
+            let vars = write_structuring(&astruct.fields);
+            let names = flag_names(&layout);
+            let flags = write_flags(&layout);
+            let deserials = write_deserial_with(&layout, context);
+
+            // write the implementation
+            let tokens = quote! [
+                impl __net::DeserializeWith<#context> for #atype {
+                    #[allow(unused_mut)]
+                    fn deserialize_with(
+                        __ctx: &mut #context,
+                        __buffer: &__godot::PackedByteArray,
+                        __offset: usize
+                    ) -> core::result::Result<(Self, usize), ()>
+                        where
+                            Self: core::marker::Sized
+                    {
+                        let mut __cursor = __offset;
+                        #(
+                            let (#names, __size) = <u8 as __net::DeserializeWith<#context>>::deserialize_with(
+                                __ctx, __buffer, __cursor
+                            )?;
+                            __cursor += __size;
+                        )*
+                        #(#flags)*
+                        #(#deserials)*
+
+                        core::result::Result::Ok((Self { #(#vars),* }, __cursor - __offset))
+                    }
+                }
+            ];
+            Ok(encapsulate(&tokens))
+        }
+
+        // Implement for enum
+        Data::Enum(anenum) => {
+            let mut entries = Vec::with_capacity(anenum.variants.len());
+
+            // Implement deserialization for each variant
+            // Just use the raw index of the variant to identify it.
+            for (index, variant) in anenum.variants.iter().enumerate() {
+                // Evaluate tokens
+                let index = index as u8;
+                let name = &variant.ident;
+                let layout = Layout::new(&variant.fields)?;
+                let vars = write_structuring(&variant.fields);
+                let names = flag_names(&layout);
+                let flags = write_flags(&layout);
+                let deserials = write_deserial_with(&layout, context);
+
+                entries.push(quote! [
+                    #index => {
+                        #(
+                            let (#names, __size) = <u8 as __net::DeserializeWith<#context>>::deserialize_with(
+                                __ctx, __buffer, __cursor
+                            )?;
+                            __cursor += __size;
+                        )*
+                        #(#flags)*
+                        #(#deserials)*
+
+                        core::result::Result::Ok((Self::#name { #(#vars),* }, __cursor - __offset))
+                    }
+                ]);
+            }
+
+            // write the implementation
+            let tokens = quote! [
+                impl __net::DeserializeWith<#context> for #atype {
+                    #[allow(unused_mut)]
+                    fn deserialize_with(
+                        __ctx: &mut #context,
+                        __buffer: &__godot::PackedByteArray,
+                        __offset: usize
+                    ) -> core::result::Result<(Self, usize), ()>
+                        where
+                            Self: core::marker::Sized
+                    {
+                        let id = __buffer.decode_u8(__offset)?;
+                        let mut __cursor = __offset + 1;
+
+                        match id {
+                            #(#entries),*
+
+                            _ => core::result::Result::Err(()),
+                        }
+                    }
+                }
+            ];
+            Ok(encapsulate(&tokens))
+        }
+        _ => Err(Error::Union),
+    }
+}
+
+/// Name of each byte holding the flags
+fn flag_names(layout: &Layout) -> Vec<Ident> {
+    (0..layout.flag_bytes())
+        .map(|byte| format_ident!("__flags_{}", byte))
+        .collect()
+}
+
+/// Unpack each flag from its byte, rejecting the unused bits of the last byte when they are set
+fn write_flags(layout: &Layout) -> Vec<TokenStream> {
+    // Store the generated tokens in this list
+    let mut entries = Vec::with_capacity(layout.flags.len());
+
+    for (position, (index, field)) in layout.flags.iter().enumerate() {
+        // prepare tokens for the macro
+        let atype = &field.ty;
+        let name = format_ident!("__field_{}", index);
+        let byte = format_ident!("__flags_{}", position / 8);
+        let bit = (position % 8) as u8;
+
+        // add a new entry to unpack
+        entries.push(quote! [
+            let #name = <#atype as __net::Flag>::from_flag(#byte & (1 << #bit) != 0);
+        ]);
+    }
+
+    // the padding must be zero so that each value has a single encoding
+    let used = layout.flags.len() % 8;
+    if used != 0 {
+        let byte = format_ident!("__flags_{}", layout.flags.len() / 8);
+        let padding = !((1u8 << used) - 1);
+        entries.push(quote! [
+            if #byte & #padding != 0 {
+                return core::result::Result::Err(());
+            }
+        ]);
+    }
+
+    entries
+}
+
+/// Call deserialize for each value
+fn write_deserial(layout: &Layout) -> Vec<TokenStream> {
     // Store the generated tokens in this list
-    let mut entries = Vec::with_capacity(fields.len());
+    let mut entries = Vec::with_capacity(layout.values.len());
 
     // for each field get its static size
-    for (index, field) in fields.iter().enumerate() {
+    for (position, (index, field)) in layout.values.iter().enumerate() {
         // prepare tokens for the macro
         let atype = &field.ty;
         let name = format_ident!("__field_{}", index);
-        let offset = format_ident!("__OFFSET_{}", index);
+        let offset = format_ident!("__OFFSET_{}", position);
 
         // add a new entry to serialize
         entries.push(quote! [
@@ -107,3 +274,35 @@ fn write_deserial(fields: &Fields) -> Vec<TokenStream> {
 
     entries
 }
+
+/// Call deserialize with a context for each value, advancing a cursor
+fn write_deserial_with(layout: &Layout, context: &Type) -> Vec<TokenStream> {
+    // Store the generated tokens in this list
+    let mut entries = Vec::with_capacity(layout.values.len());
+
+    for (index, field) in &layout.values {
+        // prepare tokens for the macro
+        let atype = &field.ty;
+        let name = format_ident!("__field_{}", index);
+
+        // add a new entry to deserialize, interned strings are unwrapped
+        if layout.is_interned(*index) {
+            entries.push(quote! [
+                let (__net::Interned(#name), __size) =
+                    <__net::Interned<#atype> as __net::DeserializeWith<#context>>::deserialize_with(
+                        __ctx, __buffer, __cursor
+                    )?;
+                __cursor += __size;
+            ]);
+        } else {
+            entries.push(quote! [
+                let (#name, __size) = <#atype as __net::DeserializeWith<#context>>::deserialize_with(
+                    __ctx, __buffer, __cursor
+                )?;
+                __cursor += __size;
+            ]);
+        }
+    }
+
+    entries
+}
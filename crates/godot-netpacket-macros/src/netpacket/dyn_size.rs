@@ -0,0 +1,88 @@
+use super::{
+    Error, Layout, attributes::TypeAttributes, encapsulate, impl_contextless, write_structuring,
+};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput};
+
+/// Implement DynSize derive, along with the context-free SerializeWith and DeserializeWith
+pub(crate) fn impl_derive_dyn_size(input: &DeriveInput) -> Result<TokenStream, Error> {
+    let size = impl_dyn_size(input)?;
+    let contextless = impl_contextless(input)?;
+    Ok(quote! [ #size #contextless ])
+}
+
+/// Implement DynSize trait
+fn impl_dyn_size(input: &DeriveInput) -> Result<TokenStream, Error> {
+    // The size does not depend on the context, only check the attributes are valid
+    TypeAttributes::parse(&input.attrs)?;
+
+    let atype = &input.ident;
+    match &input.data {
+        // Implement for struct
+        Data::Struct(astruct) => {
+            let layout = Layout::without_context(&astruct.fields)?;
+            let flag_bytes = layout.flag_bytes();
+            let vars = write_structuring(&astruct.fields);
+            let sizes = list_sizes(&layout);
+
+            // write the implementation
+            let tokens = quote! [
+                impl __net::DynSize for #atype {
+                    #[allow(unused_variables)]
+                    fn size(&self) -> usize {
+                        let Self { #(#vars),* } = self;
+                        #flag_bytes #(+ #sizes)*
+                    }
+                }
+            ];
+            Ok(encapsulate(&tokens))
+        }
+
+        // Implement for enum, the size depends on the variant
+        Data::Enum(anenum) => {
+            let mut entries = Vec::with_capacity(anenum.variants.len());
+
+            for variant in &anenum.variants {
+                // Evaluate tokens
+                let name = &variant.ident;
+                let layout = Layout::without_context(&variant.fields)?;
+                let flag_bytes = layout.flag_bytes();
+                let vars = write_structuring(&variant.fields);
+                let sizes = list_sizes(&layout);
+
+                entries.push(quote! [
+                    Self::#name { #(#vars),* } => 1 + #flag_bytes #(+ #sizes)*
+                ]);
+            }
+
+            // write the implementation
+            let tokens = quote! [
+                impl __net::DynSize for #atype {
+                    #[allow(unused_variables)]
+                    fn size(&self) -> usize {
+                        match self {
+                            #(#entries),*
+                        }
+                    }
+                }
+            ];
+            Ok(encapsulate(&tokens))
+        }
+        _ => Err(Error::Union),
+    }
+}
+
+/// Create an array with an expression to evaluate the size of each value.
+fn list_sizes(layout: &Layout) -> Vec<TokenStream> {
+    let mut sizes = Vec::with_capacity(layout.values.len());
+
+    // for each value get its size, flags are counted in the bitfield
+    for (index, field) in &layout.values {
+        let atype = &field.ty;
+        let name = format_ident!("__field_{}", index);
+        sizes.push(quote! [ <#atype as __net::DynSize>::size(#name) ]);
+    }
+
+    sizes
+}
@@ -0,0 +1,19 @@
+use super::{Error, attributes::TypeAttributes, encapsulate};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::DeriveInput;
+
+/// Implement PacketId derive
+pub(crate) fn impl_derive_packet_id(input: &DeriveInput) -> Result<TokenStream, Error> {
+    let attributes = TypeAttributes::parse(&input.attrs)?;
+    let id = attributes.id.ok_or(Error::MissingId)?;
+
+    // write the implementation
+    let atype = &input.ident;
+    let tokens = quote! [
+        impl __net::PacketId for #atype {
+            const ID: u16 = #id;
+        }
+    ];
+    Ok(encapsulate(&tokens))
+}
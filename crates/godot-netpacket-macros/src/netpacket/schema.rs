@@ -0,0 +1,154 @@
+use super::{Error, Layout, encapsulate};
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Fields};
+
+/// Implement PacketSchema trait, emitted along with the Serialize derive
+pub(crate) fn impl_schema(input: &DeriveInput) -> Result<TokenStream, Error> {
+    let atype = &input.ident;
+    let name = atype.to_string();
+
+    let (consts, size, kind) = match &input.data {
+        // Describe the fields after the bitfield
+        Data::Struct(astruct) => {
+            let layout = Layout::new(&astruct.fields)?;
+            let described = describe_fields(&layout, &astruct.fields, 0, 0);
+            let (consts, entries, end) = (described.consts, described.entries, described.end);
+            let kind = quote! [
+                __net::SchemaKind::Struct { fields: &[#(#entries),*] }
+            ];
+            (consts, quote![#end], kind)
+        }
+
+        // Describe the fields of each variant after its tag
+        Data::Enum(anenum) => {
+            let mut consts = Vec::new();
+            let mut variants = Vec::with_capacity(anenum.variants.len());
+
+            // Just use the raw index of the variant as its tag.
+            for (index, variant) in anenum.variants.iter().enumerate() {
+                let tag = index as u8;
+                let variant_name = variant.ident.to_string();
+                let layout = Layout::new(&variant.fields)?;
+                let described = describe_fields(&layout, &variant.fields, 1, index);
+                let (entries, end) = (described.entries, described.end);
+                consts.extend(described.consts);
+
+                variants.push(quote! [
+                    __net::SchemaVariant {
+                        name: #variant_name,
+                        tag: #tag,
+                        size: #end,
+                        fields: &[#(#entries),*],
+                    }
+                ]);
+            }
+
+            consts.push(quote! [
+                const __VARIANTS: &[__net::SchemaVariant] = &[#(#variants),*];
+            ]);
+            let size = quote![__net::SchemaVariant::common_size(__VARIANTS)];
+            let kind = quote![__net::SchemaKind::Enum {
+                variants: __VARIANTS
+            }];
+            (consts, size, kind)
+        }
+        _ => return Err(Error::Union),
+    };
+
+    // write the implementation
+    let tokens = quote! [
+        impl __net::PacketSchema for #atype {
+            const SCHEMA: &'static __net::Schema = {
+                #(#consts)*
+
+                &__net::Schema {
+                    name: #name,
+                    size: #size,
+                    kind: #kind,
+                }
+            };
+        }
+    ];
+    Ok(encapsulate(&tokens))
+}
+
+/// Description of the fields of a type
+struct Described {
+    /// Constants holding the offset of each value
+    consts: Vec<TokenStream>,
+
+    /// Description of each field, in the order of declaration
+    entries: Vec<TokenStream>,
+
+    /// Constant holding the offset following the last value
+    end: Ident,
+}
+
+/// Describe each field with its offset, starting after `base` bytes.
+/// Offsets of the fields of the variant at `position` use their own constants.
+fn describe_fields(layout: &Layout, fields: &Fields, base: usize, position: usize) -> Described {
+    let mut entries = vec![TokenStream::new(); fields.len()];
+    let mut consts = Vec::with_capacity(layout.values.len() + 1);
+
+    // flags are described as booleans stored in the bitfield
+    for (bit, (index, field)) in layout.flags.iter().enumerate() {
+        let name = field_name(field, *index);
+        let offset = base + bit / 8;
+        let bit = (bit % 8) as u8;
+        entries[*index] = quote! [
+            __net::SchemaField {
+                name: #name,
+                schema: <bool as __net::PacketSchema>::SCHEMA,
+                offset: core::option::Option::Some(#offset),
+                bit: core::option::Option::Some(#bit),
+            }
+        ];
+    }
+
+    // values follow each other after the bitfield, interned values are described as such
+    let start = base + layout.flag_bytes();
+    let first = format_ident!("__OFFSET_{}_0", position);
+    consts.push(quote! [
+        const #first: core::option::Option<usize> = core::option::Option::Some(#start);
+    ]);
+
+    for (value, (index, field)) in layout.values.iter().enumerate() {
+        let name = field_name(field, *index);
+        let atype = &field.ty;
+        let schema = if layout.is_interned(*index) {
+            quote! [ <__net::Interned<#atype> as __net::PacketSchema>::SCHEMA ]
+        } else {
+            quote! [ <#atype as __net::PacketSchema>::SCHEMA ]
+        };
+        let offset_curr = format_ident!("__OFFSET_{}_{}", position, value);
+        let offset_next = format_ident!("__OFFSET_{}_{}", position, value + 1);
+
+        consts.push(quote! [
+            const #offset_next: core::option::Option<usize> = #schema.after(#offset_curr);
+        ]);
+        entries[*index] = quote! [
+            __net::SchemaField {
+                name: #name,
+                schema: #schema,
+                offset: #offset_curr,
+                bit: core::option::Option::None,
+            }
+        ];
+    }
+
+    let end = format_ident!("__OFFSET_{}_{}", position, layout.values.len());
+    Described {
+        consts,
+        entries,
+        end,
+    }
+}
+
+/// Name of the field, or its position for unnamed fields
+fn field_name(field: &syn::Field, index: usize) -> String {
+    match &field.ident {
+        Some(ident) => ident.to_string(),
+        None => index.to_string(),
+    }
+}
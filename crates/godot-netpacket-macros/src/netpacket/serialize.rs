@@ -1,18 +1,38 @@
-use super::{Error, encapsulate, write_offsets, write_structuring};
-use proc_macro2::TokenStream;
+use super::{
+    Error, Layout, attributes::TypeAttributes, encapsulate, schema::impl_schema, write_offsets,
+    write_structuring,
+};
+use proc_macro2::{Ident, TokenStream};
 use quote::{format_ident, quote};
-use syn::{Data, DeriveInput, Fields};
+use syn::{Data, DeriveInput, Type};
 
-/// Implement Serialize derive
+/// Implement Serialize derive, along with the PacketSchema trait
 pub(crate) fn impl_derive_serialize(input: &DeriveInput) -> Result<TokenStream, Error> {
+    let schema = impl_schema(input)?;
+    let serialize = impl_serialize(input)?;
+    Ok(quote! [ #schema #serialize ])
+}
+
+/// Implement Serialize trait
+fn impl_serialize(input: &DeriveInput) -> Result<TokenStream, Error> {
+    // Use the context-aware trait if a context is provided
+    let attributes = TypeAttributes::parse(&input.attrs)?;
+    if let Some(context) = &attributes.context {
+        return impl_derive_serialize_with(input, context);
+    }
+
     let atype = &input.ident;
     match &input.data {
         // Implement for struct
         Data::Struct(astruct) => {
             // Evaluate tokens
+            let layout = Layout::without_context(&astruct.fields)?;
+            let flag_bytes = layout.flag_bytes();
             let vars = write_structuring(&astruct.fields);
-            let offsets = write_offsets(&astruct.fields);
-            let serials = write_serial(&astruct.fields);
+            let (flags, names) = write_flags(&layout);
+            let positions = 0..flag_bytes;
+            let offsets = write_offsets(&layout);
+            let serials = write_serial(&layout);
 
             // write the implementation
             let tokens = quote! [
@@ -24,7 +44,10 @@ pub(crate) fn impl_derive_serialize(input: &DeriveInput) -> Result<TokenStream,
                     ) -> core::result::Result<(), ()> {
                         let Self { #(#vars),* } = self;
 
-                        const __OFFSET_0: usize = 0;
+                        #(#flags)*
+                        #(__buffer.encode_u8(__offset + #positions, #names)?;)*
+
+                        const __OFFSET_0: usize = #flag_bytes;
                         #(#offsets)*
                         #(#serials)*
 
@@ -46,14 +69,22 @@ pub(crate) fn impl_derive_serialize(input: &DeriveInput) -> Result<TokenStream,
                 // Evaluate tokens
                 let index = index as u8;
                 let name = &variant.ident;
+                let layout = Layout::without_context(&variant.fields)?;
+                let flag_bytes = layout.flag_bytes();
                 let vars = write_structuring(&variant.fields);
-                let offsets = write_offsets(&variant.fields);
-                let serials = write_serial(&variant.fields);
+                let (flags, names) = write_flags(&layout);
+                let positions = 1..(1 + flag_bytes);
+                let offsets = write_offsets(&layout);
+                let serials = write_serial(&layout);
 
                 entries.push(quote! [
                     Self::#name { #(#vars),* } => {
                         __buffer.encode_u8(__offset, #index)?;
 
+                        #(#flags)*
+                        #(__buffer.encode_u8(__offset + #positions, #names)?;)*
+
+                        const __OFFSET_0: usize = 1 + #flag_bytes;
                         #(#offsets)*
                         #(#serials)*
                     }
@@ -68,8 +99,6 @@ pub(crate) fn impl_derive_serialize(input: &DeriveInput) -> Result<TokenStream,
                         __buffer: &mut __godot::PackedByteArray,
                         __offset: usize
                     ) -> core::result::Result<(), ()> {
-                        const __OFFSET_0: usize = 1;
-
                         match self {
                             #(#entries)*,
                         }
@@ -84,17 +113,142 @@ pub(crate) fn impl_derive_serialize(input: &DeriveInput) -> Result<TokenStream,
     }
 }
 
-/// Call serialize for each field
-fn write_serial(fields: &Fields) -> Vec<TokenStream> {
+/// Implement SerializeWith derive for the given context
+fn impl_derive_serialize_with(input: &DeriveInput, context: &Type) -> Result<TokenStream, Error> {
+    let atype = &input.ident;
+    match &input.data {
+        // Implement for struct
+        Data::Struct(astruct) => {
+            // Evaluate tokens
+            let layout = Layout::new(&astruct.fields)?;
+            let vars = write_structuring(&astruct.fields);
+            let (flags, names) = write_flags(&layout);
+            let serials = write_serial_with(&layout, context);
+
+            // write the implementation
+            let tokens = quote! [
+                impl __net::SerializeWith<#context> for #atype {
+                    #[allow(unused_mut)]
+                    fn serialize_with(
+                        &self,
+                        __ctx: &mut #context,
+                        __buffer: &mut __godot::PackedByteArray,
+                        __offset: usize
+                    ) -> core::result::Result<usize, ()> {
+                        let Self { #(#vars),* } = self;
+
+                        let mut __cursor = __offset;
+                        #(#flags)*
+                        #(
+                            __cursor += <u8 as __net::SerializeWith<#context>>::serialize_with(
+                                &#names, __ctx, __buffer, __cursor
+                            )?;
+                        )*
+                        #(#serials)*
+
+                        core::result::Result::Ok(__cursor - __offset)
+                    }
+                }
+            ];
+            Ok(encapsulate(&tokens))
+        }
+
+        // Implement for enum
+        Data::Enum(anenum) => {
+            let mut entries = Vec::with_capacity(anenum.variants.len());
+
+            // Implement serialization for each variant.
+            // Just use the raw index of the variant to identify it.
+            for (index, variant) in anenum.variants.iter().enumerate() {
+                // Evaluate tokens
+                let index = index as u8;
+                let name = &variant.ident;
+                let layout = Layout::new(&variant.fields)?;
+                let vars = write_structuring(&variant.fields);
+                let (flags, names) = write_flags(&layout);
+                let serials = write_serial_with(&layout, context);
+
+                entries.push(quote! [
+                    Self::#name { #(#vars),* } => {
+                        __cursor += <u8 as __net::SerializeWith<#context>>::serialize_with(
+                            &#index, __ctx, __buffer, __cursor
+                        )?;
+
+                        #(#flags)*
+                        #(
+                            __cursor += <u8 as __net::SerializeWith<#context>>::serialize_with(
+                                &#names, __ctx, __buffer, __cursor
+                            )?;
+                        )*
+                        #(#serials)*
+                    }
+                ]);
+            }
+
+            // write the implementation
+            let tokens = quote! [
+                impl __net::SerializeWith<#context> for #atype {
+                    fn serialize_with(
+                        &self,
+                        __ctx: &mut #context,
+                        __buffer: &mut __godot::PackedByteArray,
+                        __offset: usize
+                    ) -> core::result::Result<usize, ()> {
+                        let mut __cursor = __offset;
+
+                        match self {
+                            #(#entries)*,
+                        }
+
+                        core::result::Result::Ok(__cursor - __offset)
+                    }
+                }
+            ];
+            Ok(encapsulate(&tokens))
+        }
+        _ => Err(Error::Union),
+    }
+}
+
+/// Pack the flags into bytes, returns the declarations and the name of each byte
+fn write_flags(layout: &Layout) -> (Vec<TokenStream>, Vec<Ident>) {
+    let mut entries = Vec::with_capacity(layout.flag_bytes());
+    let mut names = Vec::with_capacity(layout.flag_bytes());
+
+    // each byte stores up to eight flags
+    for (byte, chunk) in layout.flags.chunks(8).enumerate() {
+        let name = format_ident!("__flags_{}", byte);
+
+        // set the bit of each flag
+        let bits = chunk.iter().enumerate().map(|(bit, (index, field))| {
+            let atype = &field.ty;
+            let field = format_ident!("__field_{}", index);
+            let bit = bit as u8;
+            quote! [
+                ((<#atype as __net::Flag>::to_flag(#field) as u8) << #bit)
+            ]
+        });
+
+        entries.push(quote! [
+            let #name: u8 = #(#bits)|*;
+        ]);
+        names.push(name);
+    }
+
+    (entries, names)
+}
+
+/// Call serialize for each value
+fn write_serial(layout: &Layout) -> Vec<TokenStream> {
     // Store the generated tokens in this list
-    let mut entries = Vec::with_capacity(fields.len());
+    let mut entries = Vec::with_capacity(layout.values.len());
 
     // for each field get its static size
-    for (index, field) in fields.iter().enumerate() {
+    for (position, (index, field)) in layout.values.iter().enumerate() {
         // prepare tokens for the macro
         let atype = &field.ty;
         let name = format_ident!("__field_{}", index);
-        let offset = format_ident!("__OFFSET_{}", index);
+        let offset = format_ident!("__OFFSET_{}", position);
 
         // add a new entry to serialize
         entries.push(quote! [
@@ -104,3 +258,32 @@ fn write_serial(fields: &Fields) -> Vec<TokenStream> {
 
     entries
 }
+
+/// Call serialize with a context for each value, advancing a cursor
+fn write_serial_with(layout: &Layout, context: &Type) -> Vec<TokenStream> {
+    // Store the generated tokens in this list
+    let mut entries = Vec::with_capacity(layout.values.len());
+
+    for (index, field) in &layout.values {
+        // prepare tokens for the macro
+        let atype = &field.ty;
+        let name = format_ident!("__field_{}", index);
+
+        // add a new entry to serialize, interned strings are wrapped
+        if layout.is_interned(*index) {
+            entries.push(quote! [
+                __cursor += <__net::Interned<#atype> as __net::SerializeWith<#context>>::serialize_with(
+                    &__net::Interned(core::clone::Clone::clone(#name)), __ctx, __buffer, __cursor
+                )?;
+            ]);
+        } else {
+            entries.push(quote! [
+                __cursor += <#atype as __net::SerializeWith<#context>>::serialize_with(
+                    #name, __ctx, __buffer, __cursor
+                )?;
+            ]);
+        }
+    }
+
+    entries
+}
@@ -3,12 +3,36 @@
 /// Module for network packets
 pub mod netpacket;
 
+// allow the derive macros to be used inside this crate
+extern crate self as godot_netpacket;
+
 // re-export elements
-pub use netpacket::{ConstSize, Deserialize, Half, Serialize, Unit};
+pub use netpacket::{
+    Angle, BatchError, BatchMessage, BatchReader, CSharpGenerator, Cell, CodecError, CodegenError,
+    ConstSize, DEFAULT_MAX_FRAME_SIZE, DEFAULT_MAX_INTERNED, DEFAULT_MAX_STRINGS, DEFAULT_MTU,
+    DEFAULT_REASSEMBLY_MEMORY, DEFAULT_REASSEMBLY_TIMEOUT, DEFAULT_RESEND_TIMEOUT, Deserialize,
+    DeserializeWith, DynSize, DynamicSchema, FRAGMENT_HEADER_SIZE, Flag, Flags, FragmentError,
+    Fragmenter, FrameDecoder, FrameEncoder, FrameError, Full, GdScriptGenerator, Half, Handshake,
+    InternTable, Interned, MultiplayerPeerExt, NetPacketCodec, NetRef, NetRefError, NetworkId,
+    NetworkObjectRegistry, PacketBatcher, PacketError, PacketId, PacketPeerExt, PacketRegistry,
+    PacketSchema, PeerError, RELIABLE_HEADER_SIZE, RELIABLE_WINDOW, Reassembler, Relative,
+    ReliableChannel, ReliableError, ResourceRef, Rgb, Rgb565, Rgba4444, Rigid2D, SNorm16, Schema,
+    SchemaField, SchemaHash, SchemaKind, SchemaVariant, Serialize, SerializeWith, StringTable, Trs,
+    UNorm8, UNorm16, Unit, Wrapper, combine_hashes, hash_str,
+};
+
+// helpers used by the derive macros
+#[doc(hidden)]
+pub use netpacket::{
+    deserialize_contextless, field_from_godot, serialize_contextless, unknown_variant,
+    variant_from_godot, variant_to_godot,
+};
 
 // re-export derive macros
 pub mod macros {
-    pub use godot_netpacket_macros::{ConstSize, Deserialize, Serialize};
+    pub use godot_netpacket_macros::{
+        ConstSize, Deserialize, DynSize, PacketConvert, PacketId, Serialize,
+    };
 }
 
 #[allow(unused_imports)]
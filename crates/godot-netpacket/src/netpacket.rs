@@ -20,7 +20,91 @@ mod transform;
 /// Packet traits implementation for composite types
 mod composite;
 
-use godot::builtin::*;
+/// Packet traits implementation for godot engine enums and bitfields
+mod engine;
+
+/// Packet traits implementation for string types
+mod string;
+
+/// Packet traits implementation for input events
+mod input;
+
+/// Network identifiers of objects shared between peers
+mod object;
+
+/// References to resources by UID or path
+mod resource;
+
+/// Interning of strings repeated across packets
+mod intern;
+
+/// Registry of packet types
+mod registry;
+
+/// Description and fingerprint of the wire format of the types
+mod schema;
+
+/// Generate codecs in other languages from the packet schemas
+mod codegen;
+
+/// Encode and decode packets from godot values
+mod codec;
+
+/// Convert packets and wrappers to and from godot values
+mod convert;
+
+/// Packet types described at runtime
+mod dynamic;
+
+/// Send and receive packets through the peers of the engine
+mod peer;
+
+/// Split streams into length-prefixed frames
+mod frame;
+
+/// Pack many messages into a single datagram
+mod batch;
+
+/// Split payloads larger than the MTU into fragments
+mod fragment;
+
+/// Reliable and ordered delivery over unreliable transports
+mod reliable;
+
+pub use batch::{BatchError, BatchMessage, BatchReader, DEFAULT_MTU, PacketBatcher};
+pub use codec::{CodecError, NetPacketCodec};
+pub use codegen::{CSharpGenerator, CodegenError, GdScriptGenerator};
+#[doc(hidden)]
+pub use convert::{field_from_godot, unknown_variant, variant_from_godot, variant_to_godot};
+pub use dynamic::DynamicSchema;
+pub use fragment::{
+    DEFAULT_REASSEMBLY_MEMORY, DEFAULT_REASSEMBLY_TIMEOUT, FRAGMENT_HEADER_SIZE, FragmentError,
+    Fragmenter, Reassembler,
+};
+pub use frame::{DEFAULT_MAX_FRAME_SIZE, FrameDecoder, FrameEncoder, FrameError};
+pub use intern::{DEFAULT_MAX_INTERNED, InternTable, Interned};
+pub use object::{NetRef, NetRefError, NetworkId, NetworkObjectRegistry};
+pub use peer::{MultiplayerPeerExt, PacketPeerExt, PeerError};
+pub use registry::{Handshake, PacketError, PacketId, PacketRegistry};
+pub use reliable::{
+    DEFAULT_RESEND_TIMEOUT, RELIABLE_HEADER_SIZE, RELIABLE_WINDOW, ReliableChannel, ReliableError,
+};
+pub use resource::ResourceRef;
+pub use schema::{
+    PacketSchema, Schema, SchemaField, SchemaHash, SchemaKind, SchemaVariant, combine_hashes,
+    hash_str,
+};
+pub use string::{DEFAULT_MAX_STRINGS, StringTable};
+
+use godot::{
+    builtin::*,
+    global::Error,
+    prelude::real_consts::{PI, TAU},
+};
+use std::{
+    marker::PhantomData,
+    ops::{Add, Sub},
+};
 
 /// Size of the data to serialize or deserialize
 pub trait ConstSize {
@@ -29,17 +113,155 @@ pub trait ConstSize {
 
 /// Serialize to a godot byte buffer
 pub trait Serialize {
+    #[allow(clippy::result_unit_err)]
     fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()>;
 }
 
 /// Deserialize from a godot byte buffer
 pub trait Deserialize {
+    #[allow(clippy::result_unit_err)]
     fn deserialize(buffer: &PackedByteArray, offset: usize) -> Result<Self, ()>
     where
         Self: Sized;
 }
 
-/// Encapsulate vector types to be stored using unit numbers [0.0, 1.0]
+/// Size of the serialized data, known once the value is available.
+/// Types with a dynamic size such as strings implement this trait instead of `ConstSize`.
+pub trait DynSize {
+    fn size(&self) -> usize;
+}
+
+impl<T> DynSize for T
+where
+    T: ConstSize,
+{
+    #[inline]
+    fn size(&self) -> usize {
+        T::SIZE
+    }
+}
+
+/// Value stored as a single bit in the bitfield of derived types
+pub trait Flag {
+    fn to_flag(&self) -> bool;
+    fn from_flag(flag: bool) -> Self;
+}
+
+impl Flag for bool {
+    #[inline]
+    fn to_flag(&self) -> bool {
+        *self
+    }
+
+    #[inline]
+    fn from_flag(flag: bool) -> Self {
+        flag
+    }
+}
+
+/// Serialize to a godot byte buffer using a context provided by the caller.
+/// The buffer grows as needed and the number of bytes written is returned.
+pub trait SerializeWith<C> {
+    #[allow(clippy::result_unit_err)]
+    fn serialize_with(
+        &self,
+        ctx: &mut C,
+        buffer: &mut PackedByteArray,
+        offset: usize,
+    ) -> Result<usize, ()>;
+}
+
+/// Deserialize from a godot byte buffer using a context provided by the caller.
+/// The value is returned along with the number of bytes read.
+pub trait DeserializeWith<C> {
+    #[allow(clippy::result_unit_err)]
+    fn deserialize_with(
+        ctx: &mut C,
+        buffer: &PackedByteArray,
+        offset: usize,
+    ) -> Result<(Self, usize), ()>
+    where
+        Self: Sized;
+}
+
+/// Serialize a value not needing any context, returns the number of bytes written
+#[doc(hidden)]
+#[inline]
+#[allow(clippy::result_unit_err)]
+pub fn serialize_contextless<T: Serialize + DynSize>(
+    value: &T,
+    buffer: &mut PackedByteArray,
+    offset: usize,
+) -> Result<usize, ()> {
+    let size = value.size();
+    reserve(buffer, offset + size);
+    value.serialize(buffer, offset)?;
+    Ok(size)
+}
+
+/// Deserialize a value not needing any context, returns it along with the number of bytes read
+#[doc(hidden)]
+#[inline]
+#[allow(clippy::result_unit_err)]
+pub fn deserialize_contextless<T: Deserialize + DynSize>(
+    buffer: &PackedByteArray,
+    offset: usize,
+) -> Result<(T, usize), ()> {
+    let value = T::deserialize(buffer, offset)?;
+    let size = value.size();
+    Ok((value, size))
+}
+
+/// Implement `SerializeWith` and `DeserializeWith` for any context, forwarding to `Serialize` and `Deserialize`.
+/// The generic parameters of each type are given between brackets, such as `impl_contextless![[T] Wrapper<T>]`.
+/// Types reading their context, such as interned strings, implement the traits themselves instead.
+#[macro_export]
+macro_rules! impl_contextless {
+    ( $( [ $( $generics:tt )* ] $type:ty ),+ $(,)? ) => {
+        $(
+            impl<__C, $( $generics )*> $crate::SerializeWith<__C> for $type
+            where
+                $type: $crate::Serialize + $crate::DynSize,
+            {
+                #[inline]
+                fn serialize_with(
+                    &self,
+                    _ctx: &mut __C,
+                    buffer: &mut ::godot::builtin::PackedByteArray,
+                    offset: usize,
+                ) -> ::core::result::Result<usize, ()> {
+                    $crate::serialize_contextless(self, buffer, offset)
+                }
+            }
+            impl<__C, $( $generics )*> $crate::DeserializeWith<__C> for $type
+            where
+                $type: $crate::Deserialize + $crate::DynSize,
+            {
+                #[inline]
+                fn deserialize_with(
+                    _ctx: &mut __C,
+                    buffer: &::godot::builtin::PackedByteArray,
+                    offset: usize,
+                ) -> ::core::result::Result<(Self, usize), ()>
+                where
+                    Self: Sized,
+                {
+                    $crate::deserialize_contextless(buffer, offset)
+                }
+            }
+        )+
+    };
+}
+
+/// Grow the buffer so that it contains at least `end` bytes.
+#[inline]
+pub fn reserve(buffer: &mut PackedByteArray, end: usize) {
+    if buffer.len() < end {
+        buffer.resize(end);
+    }
+}
+
+/// Encapsulate vector types to be stored using unit numbers [-1.0, 1.0]
 #[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
 pub struct Unit<T>(pub T);
 
@@ -47,9 +269,265 @@ pub struct Unit<T>(pub T);
 #[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
 pub struct Half<T>(pub T);
 
+/// Encapsulate numbers in the range [0.0, 1.0] to be stored on 8 bits.
+/// If `STRICT` is set, serializing a value out of range fails instead of clamping it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
+pub struct UNorm8<T, const STRICT: bool = false>(pub T);
+
+/// Encapsulate numbers in the range [0.0, 1.0] to be stored on 16 bits.
+/// If `STRICT` is set, serializing a value out of range fails instead of clamping it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
+pub struct UNorm16<T, const STRICT: bool = false>(pub T);
+
+/// Encapsulate numbers in the range [-1.0, 1.0] to be stored on 16 bits.
+/// If `STRICT` is set, serializing a value out of range fails instead of clamping it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
+pub struct SNorm16<T, const STRICT: bool = false>(pub T);
+
+/// Encapsulate transformation types to be stored as rotation, scale and translation
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Trs<T>(pub T);
+
+/// Encapsulate types to be stored using full precision floating point numbers
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Full<T>(pub T);
+
+/// Encapsulate colors to be stored using their RGB channels on 8 bits, alpha is always 1.0
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Rgb<T>(pub T);
+
+/// Encapsulate colors to be packed on 16 bits (5 bits red, 6 bits green, 5 bits blue), alpha is always 1.0
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Rgb565<T>(pub T);
+
+/// Encapsulate colors to be packed on 16 bits (4 bits per channel)
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Rgba4444<T>(pub T);
+
+/// Encapsulate positions to be stored as an integer cell of `CELL_SIZE` units on 32 bits per axis
+/// and a local offset within that cell quantized on 16 bits per axis.
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Cell<T, const CELL_SIZE: u32>(pub T);
+
+/// Encapsulate positions to be stored relative to an origin using half floating point numbers.
+/// The wrapped value is the offset from the origin.
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Relative<T>(pub T);
+
+impl<T> Relative<T>
+where
+    T: Sub<Output = T>,
+{
+    /// Express a position relative to the given origin
+    #[inline]
+    pub fn new(position: T, origin: T) -> Self {
+        Self(position - origin)
+    }
+}
+
+impl<T> Relative<T>
+where
+    T: Add<Output = T>,
+{
+    /// Recover the position given the origin it is relative to
+    #[inline]
+    pub fn resolve(self, origin: T) -> T {
+        self.0 + origin
+    }
+}
+
+/// Encapsulate an angle in radians to be stored using 8, 12 or 16 bits.
+/// Angles using 12 bits still occupy two bytes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Angle<const BITS: u32>(pub real);
+
+/// Encapsulate 2D transforms to be stored as a rotation angle of type `A` and an origin of type `O`.
+/// Any scale or skew of the transform is lost.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Rigid2D<A = Angle<16>, O = Vector2>(pub Transform2D, PhantomData<(A, O)>);
+
+impl<A, O> Rigid2D<A, O> {
+    /// Wrap a 2D transform
+    #[inline]
+    pub fn new(transform: Transform2D) -> Self {
+        Self(transform, PhantomData)
+    }
+}
+
+/// Encapsulate an unsigned integer used as a set of bits, such as collision layers
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Flags<T>(pub T);
+
+/// Implement bit manipulation for the supported integers
+macro_rules! impl_flags {
+    ( $( $num:ty ),+ ) => {
+        $(
+            impl Flags<$num> {
+                /// Number of bits available
+                pub const BITS: u32 = <$num>::BITS;
+
+                /// Check whether the bit at the given index is set
+                #[inline]
+                pub fn get(self, index: u32) -> bool {
+                    index < Self::BITS && self.0 & (1 << index) != 0
+                }
+
+                /// Set or clear the bit at the given index, out of range indices are ignored
+                #[inline]
+                pub fn set(&mut self, index: u32, value: bool) {
+                    if index < Self::BITS {
+                        if value {
+                            self.0 |= 1 << index;
+                        } else {
+                            self.0 &= !(1 << index);
+                        }
+                    }
+                }
+
+                /// Check whether all the bits of the mask are set
+                #[inline]
+                pub fn contains(self, mask: $num) -> bool {
+                    self.0 & mask == mask
+                }
+            }
+        )+
+    };
+}
+
+impl_flags![u8, u16, u32, u64];
+
+/// Conversion between a value and one of its wrapped encodings
+pub trait Wrapper<T> {
+    /// Wrap the value
+    fn wrap(value: T) -> Self;
+
+    /// Extract the wrapped value
+    fn into_inner(self) -> T;
+}
+
+/// A value is its own encoding
+impl<T> Wrapper<T> for T {
+    #[inline]
+    fn wrap(value: T) -> Self {
+        value
+    }
+
+    #[inline]
+    fn into_inner(self) -> T {
+        self
+    }
+}
+
+/// Implement the wrapper trait for encapsulating types
+macro_rules! impl_wrapper {
+    ( $( $wrap:ident < T $( , const $param:ident : $ptype:ty )? > ),+ ) => {
+        $(
+            impl<T $( , const $param: $ptype )?> Wrapper<T> for $wrap<T $( , $param )?> {
+                #[inline]
+                fn wrap(value: T) -> Self {
+                    Self(value)
+                }
+
+                #[inline]
+                fn into_inner(self) -> T {
+                    self.0
+                }
+            }
+        )+
+    };
+}
+
+impl_wrapper![
+    Unit<T>,
+    Half<T>,
+    UNorm8<T, const STRICT: bool>,
+    UNorm16<T, const STRICT: bool>,
+    SNorm16<T, const STRICT: bool>,
+    Trs<T>,
+    Full<T>,
+    Rgb<T>,
+    Rgb565<T>,
+    Rgba4444<T>,
+    Cell<T, const CELL_SIZE: u32>,
+    Flags<T>
+];
+
+// wrappers only change how their value is stored
+crate::impl_contextless![
+    [T] Unit<T>,
+    [T] Half<T>,
+    [T, const STRICT: bool] UNorm8<T, STRICT>,
+    [T, const STRICT: bool] UNorm16<T, STRICT>,
+    [T, const STRICT: bool] SNorm16<T, STRICT>,
+    [T] Trs<T>,
+    [T] Full<T>,
+    [T] Rgb<T>,
+    [T] Rgb565<T>,
+    [T] Rgba4444<T>,
+    [T, const CELL_SIZE: u32] Cell<T, CELL_SIZE>,
+    [T] Relative<T>,
+    [T] Flags<T>,
+    [const BITS: u32] Angle<BITS>,
+    [A, O] Rigid2D<A, O>,
+];
+
+impl<const BITS: u32> Wrapper<real> for Angle<BITS> {
+    #[inline]
+    fn wrap(value: real) -> Self {
+        Self(value)
+    }
+
+    #[inline]
+    fn into_inner(self) -> real {
+        self.0
+    }
+}
+
 /// Size of an unit
 const UNIT_SIZE: usize = 1;
 
+/// Number of bytes used to encode an unsigned integer 7 bits at a time
+#[inline]
+fn varint_size(value: u64) -> usize {
+    (64 - (value | 1).leading_zeros()).div_ceil(7) as usize
+}
+
+/// Encode an unsigned integer 7 bits at a time, returns the number of bytes written
+fn encode_varint(buffer: &mut PackedByteArray, offset: usize, value: u64) -> Result<usize, ()> {
+    let mut value = value;
+    let mut index = offset;
+    while value >= 0x80 {
+        buffer.encode_u8(index, (value as u8) | 0x80)?;
+        value >>= 7;
+        index += 1;
+    }
+    buffer.encode_u8(index, value as u8)?;
+    Ok(index + 1 - offset)
+}
+
+/// Decode an unsigned integer 7 bits at a time, returns the value and the number of bytes read
+fn decode_varint(buffer: &PackedByteArray, offset: usize) -> Result<(u64, usize), ()> {
+    let mut value = 0;
+    for index in 0..varint_size(u64::MAX) {
+        let byte = buffer.decode_u8(offset + index)?;
+        value |= ((byte & 0x7F) as u64) << (7 * index);
+        if byte & 0x80 == 0 {
+            return Ok((value, index + 1));
+        }
+    }
+    Err(())
+}
+
+/// Convert the error code of the engine
+#[inline]
+fn check(error: Error) -> Result<(), Error> {
+    if error == Error::OK {
+        Ok(())
+    } else {
+        Err(error)
+    }
+}
+
 /// Size of an half
 const HALF_SIZE: usize = 2;
 
@@ -67,12 +545,122 @@ fn from_unit(byte: i8) -> real {
     byte as real * FACTOR
 }
 
+/// Convert an angle in radians into a number of steps out of `2^bits`.
+#[inline]
+fn to_angle(angle: real, bits: u32) -> u32 {
+    let steps = (1u32 << bits) as real;
+    ((angle / TAU * steps).round().rem_euclid(steps)) as u32
+}
+
+/// Convert a number of steps out of `2^bits` into an angle in the range ]-PI, PI].
+#[inline]
+fn from_angle(step: u32, bits: u32) -> real {
+    let angle = step as real * TAU / (1u32 << bits) as real;
+    if angle > PI { angle - TAU } else { angle }
+}
+
+/// Convert a color channel in the range [0.0, 1.0] into a level out of `max`.
+#[inline]
+fn to_channel(value: f32, max: u16) -> u16 {
+    (value.clamp(0.0, 1.0) * max as f32).round() as u16
+}
+
+/// Convert a level out of `max` into a color channel in the range [0.0, 1.0]
+#[inline]
+fn from_channel(level: u16, max: u16) -> f32 {
+    level as f32 / max as f32
+}
+
+/// Scale a real number in the range [min, 1.0] by the given factor and round it.
+/// Out of range values are clamped, or rejected in strict mode.
+#[inline]
+fn quantize(num: real, min: real, factor: real, strict: bool) -> Result<real, ()> {
+    if strict && !(min..=1.0).contains(&num) {
+        return Err(());
+    }
+    Ok((num.clamp(min, 1.0) * factor).round())
+}
+
+/// Convert a real number in the range [0.0, 1.0] into a byte.
+#[inline]
+fn to_unorm8(num: real, strict: bool) -> Result<u8, ()> {
+    quantize(num, 0.0, u8::MAX as real, strict).map(|num| num as u8)
+}
+
+/// Convert a byte into a real in the range [0.0, 1.0]
+#[inline]
+fn from_unorm8(byte: u8) -> real {
+    byte as real / u8::MAX as real
+}
+
+/// Convert a real number in the range [0.0, 1.0] into a 16 bits integer.
+#[inline]
+fn to_unorm16(num: real, strict: bool) -> Result<u16, ()> {
+    quantize(num, 0.0, u16::MAX as real, strict).map(|num| num as u16)
+}
+
+/// Convert a 16 bits integer into a real in the range [0.0, 1.0]
+#[inline]
+fn from_unorm16(word: u16) -> real {
+    word as real / u16::MAX as real
+}
+
+/// Convert a real number in the range [-1.0, 1.0] into a 16 bits integer.
+#[inline]
+fn to_snorm16(num: real, strict: bool) -> Result<i16, ()> {
+    quantize(num, -1.0, i16::MAX as real, strict).map(|num| num as i16)
+}
+
+/// Convert a 16 bits integer into a real in the range [-1.0, 1.0]
+#[inline]
+fn from_snorm16(word: i16) -> real {
+    // both -32768 and -32767 map to -1.0
+    (word as real / i16::MAX as real).max(-1.0)
+}
+
+/// Convert the floored coordinate of a cell into a 32 bits integer, failing when out of range.
+#[inline]
+fn to_cell(cell: real) -> Result<i32, ()> {
+    // i32::MAX is not representable as a real, compare against the next power of two instead
+    if cell >= i32::MIN as real && cell < -(i32::MIN as real) {
+        Ok(cell as i32)
+    } else {
+        Err(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{ConstSize, Deserialize, Serialize};
+    use super::{
+        Angle, BatchError, BatchReader, CSharpGenerator, Cell, CodecError, CodegenError, ConstSize,
+        Deserialize, DeserializeWith, DynSize, DynamicSchema, FRAGMENT_HEADER_SIZE, Flag, Flags,
+        FragmentError, Fragmenter, FrameDecoder, FrameEncoder, FrameError, GdScriptGenerator, Half,
+        Handshake, InternTable, MultiplayerPeerExt, NetPacketCodec, NetRef, NetRefError, NetworkId,
+        NetworkObjectRegistry, PI, PacketBatcher, PacketError, PacketPeerExt, PacketRegistry,
+        PacketSchema, PeerError, RELIABLE_HEADER_SIZE, RELIABLE_WINDOW, Reassembler, Relative,
+        ReliableChannel, ReliableError, ResourceRef, Rgb, SchemaHash, SchemaKind, Serialize,
+        SerializeWith, StringTable, TAU, Trs, Unit, combine_hashes, from_angle, from_channel,
+        from_snorm16, from_unorm8, from_unorm16, hash_str, to_angle, to_cell, to_channel,
+        to_snorm16, to_unorm8, to_unorm16, varint_size,
+    };
     //use gd_rehearse::itest::gditest;
-    use godot::builtin::{PackedByteArray, Vector2i};
-    use godot_netpacket_macros::{ConstSize, Deserialize, Serialize};
+    use godot::{
+        builtin::{
+            Basis, Color, EulerOrder, GString, NodePath, PackedByteArray, StringName, Transform3D,
+            VarDictionary, Vector2, Vector2i, Vector3,
+        },
+        classes::{
+            InputEvent, InputEventKey, InputEventMouseMotion, Node, OfflineMultiplayerPeer,
+            PacketPeerStream, Resource, StreamPeerBuffer, multiplayer_peer::TransferMode,
+        },
+        global::Key,
+        meta::{FromGodot, ToGodot},
+        obj::{EngineEnum, Gd, NewAlloc, NewGd},
+        tools::load,
+    };
+    use godot_netpacket_macros::{
+        ConstSize, Deserialize, DynSize, PacketConvert, PacketId, Serialize,
+    };
     extern crate self as godot_netpacket;
 
     #[derive(Debug, Serialize, ConstSize, Deserialize, PartialEq, Eq)]
@@ -92,6 +680,86 @@ mod tests {
         B { foo: i64, bar: bool },
     }
 
+    #[derive(Debug, Serialize, ConstSize, Deserialize, PacketConvert, PartialEq, Eq)]
+    struct FPacket {
+        jump: bool,
+        pos: Vector2i,
+        crouch: bool,
+        #[netpacket(flag)]
+        sprint: Toggle,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Toggle {
+        Off,
+        On,
+    }
+
+    impl Flag for Toggle {
+        fn to_flag(&self) -> bool {
+            *self == Toggle::On
+        }
+
+        fn from_flag(flag: bool) -> Self {
+            if flag { Toggle::On } else { Toggle::Off }
+        }
+    }
+
+    /// Context shared by the packets of a session
+    struct Session;
+
+    /// Position stored in full without context, or relative to the origin given as context
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Waypoint(Vector3);
+
+    impl ConstSize for Waypoint {
+        const SIZE: usize = Vector3::SIZE;
+    }
+    impl Serialize for Waypoint {
+        fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
+            self.0.serialize(buffer, offset)
+        }
+    }
+    impl Deserialize for Waypoint {
+        fn deserialize(buffer: &PackedByteArray, offset: usize) -> Result<Self, ()> {
+            Vector3::deserialize(buffer, offset).map(Self)
+        }
+    }
+    impl SerializeWith<Vector3> for Waypoint {
+        fn serialize_with(
+            &self,
+            origin: &mut Vector3,
+            buffer: &mut PackedByteArray,
+            offset: usize,
+        ) -> Result<usize, ()> {
+            Relative::new(self.0, *origin).serialize_with(&mut (), buffer, offset)
+        }
+    }
+    impl DeserializeWith<Vector3> for Waypoint {
+        fn deserialize_with(
+            origin: &mut Vector3,
+            buffer: &PackedByteArray,
+            offset: usize,
+        ) -> Result<(Self, usize), ()> {
+            let (relative, size) = <Relative<Vector3>>::deserialize_with(&mut (), buffer, offset)?;
+            Ok((Self(relative.resolve(*origin)), size))
+        }
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+    #[netpacket(context = Session)]
+    struct DPacket {
+        id: u16,
+        inner: APacket,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+    #[netpacket(context = Session)]
+    enum EPacket {
+        A,
+        B(DPacket),
+    }
+
     #[doc(hidden)]
     #[allow(dead_code)]
     fn test_struct() {
@@ -139,7 +807,7 @@ mod tests {
 
         // Allocate a buffer
         let mut buffer = PackedByteArray::new();
-        buffer.resize(i64::SIZE + bool::SIZE);
+        buffer.resize(1 + 1 + i64::SIZE);
 
         // serialize and deserialize
         c.serialize(&mut buffer, 0).unwrap();
@@ -147,4 +815,1370 @@ mod tests {
 
         assert_eq!(c, c2);
     }
+
+    #[doc(hidden)]
+    #[allow(dead_code)]
+    fn test_flags() {
+        // all the flags share a single leading byte
+        assert_eq!(FPacket::SIZE, 1 + Vector2i::SIZE);
+
+        // initial payload
+        let f = FPacket {
+            jump: true,
+            pos: Vector2i::new(12, 34),
+            crouch: false,
+            sprint: Toggle::On,
+        };
+
+        // Allocate a buffer
+        let mut buffer = PackedByteArray::new();
+        buffer.resize(FPacket::SIZE);
+
+        // serialize and deserialize
+        f.serialize(&mut buffer, 0).unwrap();
+        let f2 = FPacket::deserialize(&buffer, 0).unwrap();
+
+        assert_eq!(buffer.get(0), Some(0b101));
+        assert_eq!(f, f2);
+
+        // the unused bits of the flags must be zero
+        buffer.encode_u8(0, 0b1101).unwrap();
+        assert_eq!(FPacket::deserialize(&buffer, 0), Err(()));
+        assert_eq!(FPacket::deserialize_with(&mut (), &buffer, 0), Err(()));
+    }
+
+    #[doc(hidden)]
+    #[allow(dead_code)]
+    fn test_trs() {
+        // an axis scaled to zero keeps the rotation of the other axes
+        let rotation = Basis::from_euler(EulerOrder::YXZ, Vector3::new(0.0, 1.0, 0.0));
+        let transform = Transform3D {
+            basis: rotation * Basis::from_scale(Vector3::new(0.0, 2.0, 1.0)),
+            origin: Vector3::new(1.0, 2.0, 3.0),
+        };
+        let mut buffer = PackedByteArray::new();
+        buffer.resize(<Trs<Transform3D>>::SIZE);
+        Trs(transform).serialize(&mut buffer, 0).unwrap();
+        let decoded = <Trs<Transform3D>>::deserialize(&buffer, 0).unwrap().0;
+        assert!((decoded.basis.col_b() - transform.basis.col_b()).length() < 0.01);
+        assert!((decoded.basis.col_c() - transform.basis.col_c()).length() < 0.01);
+        assert!(decoded.basis.col_a().is_zero_approx());
+
+        // a basis scaled to zero on every axis does not panic either
+        let hidden = Basis::from_scale(Vector3::ZERO);
+        Trs(hidden).serialize(&mut buffer, 0).unwrap();
+        Unit(hidden).serialize(&mut buffer, 0).unwrap();
+    }
+
+    #[doc(hidden)]
+    #[allow(dead_code)]
+    fn test_input_event() {
+        // initial payload
+        let mut key = InputEventKey::new_gd();
+        key.set_keycode(Key::A);
+        // labels of other layouts are code points without a named constant
+        key.set_key_label(Key::from_ord(0x416));
+        key.set_pressed(true);
+        let event = key.upcast::<InputEvent>();
+
+        // Allocate a buffer
+        let mut buffer = PackedByteArray::new();
+        buffer.resize(event.size());
+
+        // serialize and deserialize
+        event.serialize(&mut buffer, 0).unwrap();
+        let event2 = Gd::<InputEvent>::deserialize(&buffer, 0).unwrap();
+
+        let key2 = event2.try_cast::<InputEventKey>().unwrap();
+        assert_eq!(key2.get_keycode(), Key::A);
+        assert_eq!(key2.get_key_label(), Key::from_ord(0x416));
+        assert!(key2.is_pressed());
+
+        // tilts are not normalized, each axis covers [-1, 1]
+        let mut motion = InputEventMouseMotion::new_gd();
+        motion.set_tilt(Vector2::new(1.0, 1.0));
+        motion.set_pressure(0.5);
+        let event = motion.upcast::<InputEvent>();
+        buffer.resize(event.size());
+        event.serialize(&mut buffer, 0).unwrap();
+        let motion2 = Gd::<InputEvent>::deserialize(&buffer, 0)
+            .unwrap()
+            .try_cast::<InputEventMouseMotion>()
+            .unwrap();
+        assert_eq!(motion2.get_tilt(), Vector2::new(1.0, 1.0));
+        assert!((motion2.get_pressure() - 0.5).abs() < 0.01);
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    #[netpacket(context = NetworkObjectRegistry)]
+    struct OPacket {
+        target: NetRef<Node>,
+    }
+
+    #[doc(hidden)]
+    #[allow(dead_code)]
+    fn test_net_ref() {
+        let node = Node::new_alloc();
+        let mut local = NetworkObjectRegistry::new();
+        let mut remote = NetworkObjectRegistry::new();
+
+        // the sender registers the object when serializing it
+        let mut buffer = PackedByteArray::new();
+        let written = NetRef(node.clone())
+            .serialize_with(&mut local, &mut buffer, 0)
+            .unwrap();
+        assert_eq!(written, NetRef::<Node>::SIZE);
+        let id = local.id_of(node.instance_id()).unwrap();
+
+        // the receiver resolves the identifier it bound to the object
+        remote.insert(id, node.instance_id());
+        let (reference, read) = NetRef::<Node>::deserialize_with(&mut remote, &buffer, 0).unwrap();
+        assert_eq!(read, written);
+        assert_eq!(reference, NetRef(node.clone()));
+
+        // packets holding references of engine classes can derive the common traits
+        let packet = OPacket { target: reference };
+        assert_eq!(packet.clone(), packet);
+
+        // unknown and freed objects fail to deserialize
+        let mut empty = NetworkObjectRegistry::new();
+        assert!(NetRef::<Node>::deserialize_with(&mut empty, &buffer, 0).is_err());
+        node.free();
+        assert!(NetRef::<Node>::deserialize_with(&mut remote, &buffer, 0).is_err());
+        assert_eq!(remote.resolve::<Node>(id), Err(NetRefError::Freed(id)));
+        assert_eq!(
+            remote.resolve::<Node>(NetworkId(1234)),
+            Err(NetRefError::Unknown(NetworkId(1234)))
+        );
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq)]
+    #[netpacket(context = StringTable)]
+    struct GPacket {
+        slot: u8,
+        item: ResourceRef<Resource, true>,
+    }
+
+    #[doc(hidden)]
+    #[allow(dead_code)]
+    fn test_resource_table() {
+        // initial payload
+        let item = ResourceRef(load::<Resource>("res://item.tres"));
+        let g = GPacket { slot: 1, item };
+
+        // each direction uses its own table
+        let mut sender = StringTable::new();
+        let mut receiver = StringTable::new();
+        let mut buffer = PackedByteArray::new();
+
+        // the path is sent only once
+        let first = g.serialize_with(&mut sender, &mut buffer, 0).unwrap();
+        let second = g.serialize_with(&mut sender, &mut buffer, first).unwrap();
+        assert!(second < first);
+
+        let (g1, read) = GPacket::deserialize_with(&mut receiver, &buffer, 0).unwrap();
+        let (g2, _) = GPacket::deserialize_with(&mut receiver, &buffer, read).unwrap();
+        assert!(g == g1 && g == g2);
+
+        // paths outside of the project are rejected before being loaded or added to the table
+        let path = b"user://save.tres";
+        let mut hostile = PackedByteArray::from(&[1, 3, path.len() as u8][..]);
+        hostile.extend(path.iter().copied());
+        let mut receiver = StringTable::new();
+        assert!(GPacket::deserialize_with(&mut receiver, &hostile, 0).is_err());
+        assert!(receiver.is_empty());
+
+        // full tables send the path inline, and reject new entries
+        let mut sender = StringTable::new().max_strings(0);
+        let mut receiver = StringTable::new().max_strings(0);
+        let size = g.serialize_with(&mut sender, &mut buffer, 0).unwrap();
+        let (g3, read) = GPacket::deserialize_with(&mut receiver, &buffer, 0).unwrap();
+        assert!(g == g3 && read == size && sender.is_empty() && receiver.is_empty());
+
+        let path = b"res://item.tres";
+        let mut entry = PackedByteArray::from(&[1, 3, path.len() as u8][..]);
+        entry.extend(path.iter().copied());
+        assert!(GPacket::deserialize_with(&mut receiver, &entry, 0).is_err());
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    #[netpacket(context = InternTable)]
+    struct HPacket {
+        #[netpacket(intern)]
+        animation: StringName,
+        #[netpacket(intern)]
+        target: NodePath,
+        speed: f32,
+    }
+
+    #[doc(hidden)]
+    #[allow(dead_code)]
+    fn test_intern() {
+        // initial payload
+        let h = HPacket {
+            animation: StringName::from("run"),
+            target: NodePath::from("Player/Skeleton"),
+            speed: 1.5,
+        };
+
+        // each direction uses its own table
+        let mut sender = InternTable::new();
+        let mut receiver = InternTable::new();
+        let mut buffer = PackedByteArray::new();
+
+        // strings are sent inline until acknowledged
+        let first = h.serialize_with(&mut sender, &mut buffer, 0).unwrap();
+        let (h1, _) = HPacket::deserialize_with(&mut receiver, &buffer, 0).unwrap();
+        for (epoch, id) in receiver.take_acks() {
+            sender.acknowledge(epoch, id);
+        }
+
+        // then only their identifier is sent
+        let second = h.serialize_with(&mut sender, &mut buffer, 0).unwrap();
+        let (h2, _) = HPacket::deserialize_with(&mut receiver, &buffer, 0).unwrap();
+
+        assert_eq!(second, 1 + 1 + f32::SIZE);
+        assert!(second < first);
+        assert_eq!(h, h1);
+        assert_eq!(h, h2);
+
+        // after a reset, late references of the previous epoch are rejected instead of resolving to new strings
+        let stale = buffer.clone();
+        sender.reset();
+        let jump = HPacket {
+            animation: StringName::from("jump"),
+            target: NodePath::from("Enemy"),
+            speed: 2.0,
+        };
+        let inline = jump.serialize_with(&mut sender, &mut buffer, 0).unwrap();
+        let (h3, _) = HPacket::deserialize_with(&mut receiver, &buffer, 0).unwrap();
+        assert_eq!(h3, jump);
+        assert!(HPacket::deserialize_with(&mut receiver, &stale, 0).is_err());
+
+        // acknowledgements of the previous epoch are ignored
+        sender.acknowledge(0, 0);
+        assert_eq!(
+            jump.serialize_with(&mut sender, &mut buffer, 0).unwrap(),
+            inline
+        );
+
+        // tables hold a limited number of strings
+        let mut small = InternTable::new().max_strings(1);
+        assert!(h.serialize_with(&mut small, &mut buffer, 0).is_err());
+    }
+
+    #[derive(Debug, Serialize, ConstSize, Deserialize, PacketId, PartialEq, Eq)]
+    #[netpacket(id = 65535)]
+    struct ZPacket {}
+
+    #[derive(Debug, Serialize, ConstSize, Deserialize, PacketId, PartialEq, Eq)]
+    #[netpacket(id = 42)]
+    struct JPacket {
+        score: u32,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, DynSize, PacketId, PacketConvert, PartialEq, Eq)]
+    #[netpacket(id = 43)]
+    enum KPacket {
+        Join { team: u8 },
+        Leave,
+    }
+
+    #[doc(hidden)]
+    #[allow(dead_code)]
+    fn test_registry() {
+        // count the received packets
+        let mut registry = PacketRegistry::<u32>::new();
+        registry
+            .register(|count, packet: JPacket| *count += packet.score)
+            .unwrap();
+        registry
+            .register(|count, _: KPacket| *count += 1000)
+            .unwrap();
+        assert_eq!(
+            registry.register(|_, _: JPacket| ()),
+            Err(PacketError::Duplicate(42))
+        );
+        assert_eq!(
+            registry.register(|_, _: ZPacket| ()),
+            Err(PacketError::Reserved(65535))
+        );
+
+        // the header identifies the type of the packet
+        let mut count = 0;
+        let j = PacketRegistry::encode(&mut count, &JPacket { score: 7 }).unwrap();
+        let k = PacketRegistry::encode(&mut count, &KPacket::Join { team: 1 }).unwrap();
+        assert_eq!(PacketRegistry::<u32>::peek_id(&j), Ok(42));
+
+        registry.dispatch(&mut count, &j).unwrap();
+        registry.dispatch(&mut count, &k).unwrap();
+        assert_eq!(count, 1007);
+
+        // unknown identifiers are reported
+        let mut unknown = PackedByteArray::new();
+        unknown.resize(2);
+        unknown.encode_u16(0, 7).unwrap();
+        assert_eq!(
+            registry.dispatch(&mut count, &unknown),
+            Err(PacketError::Unknown(7))
+        );
+
+        // peers registering different packets are detected by the handshake
+        let mut remote = PacketRegistry::<u32>::new();
+        remote.register(|_, _: JPacket| ()).unwrap();
+        assert_eq!(
+            registry.verify(&remote.handshake()),
+            Err(PacketError::ProtocolMismatch(43))
+        );
+        assert_eq!(registry.mismatches(&remote.handshake()), [43]);
+        assert_ne!(registry.protocol_hash(), remote.protocol_hash());
+        remote.register(|_, _: KPacket| ()).unwrap();
+        assert_eq!(registry.verify(&remote.handshake()), Ok(()));
+
+        // the handshake lists the schema hash of each packet type
+        let handshake = PacketRegistry::encode(&mut count, &registry.handshake()).unwrap();
+        assert_eq!(PacketRegistry::<u32>::peek_id(&handshake), Ok(65535));
+        let (received, _) =
+            Handshake::deserialize_with(&mut count, &handshake, PacketRegistry::<u32>::HEADER_SIZE)
+                .unwrap();
+        assert_eq!(received, remote.handshake());
+        assert_eq!(received.protocol_hash(), registry.protocol_hash());
+    }
+
+    #[doc(hidden)]
+    #[allow(dead_code)]
+    fn test_codec() {
+        NetPacketCodec::register::<FPacket>().unwrap();
+        NetPacketCodec::register::<KPacket>().unwrap();
+
+        // dictionaries produce the same bytes as the rust types
+        let mut data = VarDictionary::new();
+        data.set("jump", true);
+        data.set("pos", Vector2i::new(12, 34));
+        data.set("crouch", false);
+        data.set("sprint", true);
+        let bytes = NetPacketCodec::encode_packet("FPacket", &data).unwrap();
+
+        let mut buffer = PackedByteArray::new();
+        buffer.resize(FPacket::SIZE);
+        let f = FPacket {
+            jump: true,
+            pos: Vector2i::new(12, 34),
+            crouch: false,
+            sprint: Toggle::On,
+        };
+        f.serialize(&mut buffer, 0).unwrap();
+        assert_eq!(bytes, buffer);
+        assert_eq!(NetPacketCodec::decode_packet("FPacket", &bytes), Ok(data));
+
+        // enums hold the name of the variant and its fields
+        let mut fields = VarDictionary::new();
+        fields.set("team", 3);
+        let mut data = VarDictionary::new();
+        data.set("variant", "Join");
+        data.set("fields", fields);
+        let bytes = NetPacketCodec::encode_packet("KPacket", &data).unwrap();
+        let (k, _) = KPacket::deserialize_with(&mut (), &bytes, 0).unwrap();
+        assert_eq!(k, KPacket::Join { team: 3 });
+
+        // missing values and unknown types are reported
+        assert_eq!(
+            NetPacketCodec::encode_packet("FPacket", &VarDictionary::new()),
+            Err(CodecError::Missing("FPacket.jump".into()))
+        );
+        assert_eq!(
+            NetPacketCodec::encode_packet("Unknown", &data),
+            Err(CodecError::UnknownPacket("Unknown".into()))
+        );
+    }
+
+    #[doc(hidden)]
+    #[allow(dead_code)]
+    fn test_convert() {
+        NetPacketCodec::register::<FPacket>().unwrap();
+        NetPacketCodec::register::<KPacket>().unwrap();
+
+        // structs match the dictionaries of the codec
+        let f = FPacket {
+            jump: true,
+            pos: Vector2i::new(12, 34),
+            crouch: false,
+            sprint: Toggle::On,
+        };
+        let mut buffer = PackedByteArray::new();
+        buffer.resize(FPacket::SIZE);
+        f.serialize(&mut buffer, 0).unwrap();
+        let data = f.to_godot();
+        assert_eq!(
+            NetPacketCodec::decode_packet("FPacket", &buffer),
+            Ok(data.clone())
+        );
+        assert_eq!(FPacket::try_from_godot(data).unwrap(), f);
+
+        // enums hold the name of the variant and its fields
+        let k = KPacket::Join { team: 3 };
+        let bytes = NetPacketCodec::encode_packet("KPacket", &k.to_godot()).unwrap();
+        let (decoded, _) = KPacket::deserialize_with(&mut (), &bytes, 0).unwrap();
+        assert_eq!(decoded, k);
+        assert_eq!(KPacket::from_variant(&k.to_variant()), k);
+
+        // unit variants do not need their fields
+        let mut data = VarDictionary::new();
+        data.set("variant", "Leave");
+        assert_eq!(KPacket::try_from_godot(data).unwrap(), KPacket::Leave);
+
+        // wrappers hold the plain value
+        let l = LPacket {
+            aim: Unit(Vector3::UP),
+            velocity: Half(Vector3::new(1.0, 2.0, 3.0)),
+        };
+        let data = l.to_godot();
+        assert_eq!(data.get("aim"), Some(Vector3::UP.to_variant()));
+        assert_eq!(LPacket::try_from_godot(data).unwrap(), l);
+
+        // unknown variants and missing fields are rejected
+        let mut data = VarDictionary::new();
+        data.set("variant", "Kick");
+        assert!(KPacket::try_from_godot(data).is_err());
+        assert!(FPacket::try_from_godot(VarDictionary::new()).is_err());
+    }
+
+    #[doc(hidden)]
+    #[allow(dead_code)]
+    fn test_context() {
+        // initial payload
+        let e = EPacket::B(DPacket {
+            id: 42,
+            inner: APacket {
+                pos: Vector2i::new(12, 34),
+            },
+        });
+
+        // The buffer grows as needed
+        let mut session = Session;
+        let mut buffer = PackedByteArray::new();
+
+        // serialize and deserialize
+        let written = e.serialize_with(&mut session, &mut buffer, 0).unwrap();
+        let (e2, read) = EPacket::deserialize_with(&mut session, &buffer, 0).unwrap();
+
+        assert_eq!(written, 1 + u16::SIZE + APacket::SIZE);
+        assert_eq!(written, read);
+        assert_eq!(e, e2);
+
+        // types serializable without context can still read one
+        let mut origin = Vector3::new(100.0, 0.0, -50.0);
+        let waypoint = Waypoint(Vector3::new(101.0, 2.0, -48.0));
+        let mut buffer = PackedByteArray::new();
+        let size = <Relative<Vector3>>::SIZE;
+        assert_eq!(
+            waypoint.serialize_with(&mut origin, &mut buffer, 0),
+            Ok(size)
+        );
+        assert_eq!(
+            Waypoint::deserialize_with(&mut origin, &buffer, 0),
+            Ok((waypoint, size))
+        );
+        assert_eq!(Waypoint::deserialize(&buffer, 0), Err(()));
+    }
+
+    #[test]
+    fn test_normalized() {
+        // values are rounded to the nearest step
+        assert_eq!(to_unorm8(0.5, false), Ok(128));
+        assert_eq!(to_unorm16(1.0, false), Ok(u16::MAX));
+        assert_eq!(to_snorm16(-1.0, false), Ok(-i16::MAX));
+
+        // out of range values are clamped, or rejected in strict mode
+        assert_eq!(to_unorm8(1.5, false), Ok(u8::MAX));
+        assert_eq!(to_unorm8(-0.5, false), Ok(0));
+        assert_eq!(to_unorm8(1.5, true), Err(()));
+        assert_eq!(to_snorm16(-1.5, true), Err(()));
+        assert_eq!(from_snorm16(i16::MIN), -1.0);
+
+        // every step survives a round-trip
+        for byte in 0..=u8::MAX {
+            assert_eq!(to_unorm8(from_unorm8(byte), true), Ok(byte));
+        }
+        for word in [0, 1, 12345, u16::MAX] {
+            assert_eq!(to_unorm16(from_unorm16(word), true), Ok(word));
+        }
+    }
+
+    #[test]
+    fn test_flags_bits() {
+        let mut flags = Flags(0u16);
+        flags.set(0, true);
+        flags.set(9, true);
+        flags.set(16, true);
+        assert_eq!(flags, Flags(0b10_0000_0001));
+        assert!(flags.get(9) && !flags.get(1) && !flags.get(16));
+        assert!(flags.contains(0b10_0000_0000));
+
+        flags.set(0, false);
+        assert_eq!(flags, Flags(0b10_0000_0000));
+    }
+
+    #[test]
+    fn test_schema_hash() {
+        // the hash is stable and depends on the order
+        assert_eq!(hash_str(""), 0xcbf2_9ce4_8422_2325);
+        assert_ne!(combine_hashes(&[1, 2]), combine_hashes(&[2, 1]));
+
+        // the names, the types and the attributes of the fields are covered
+        assert_ne!(APacket::SCHEMA_HASH, DPacket::SCHEMA_HASH);
+        assert_ne!(
+            FPacket::SCHEMA_HASH,
+            <(bool, Vector2i, bool, bool)>::SCHEMA_HASH
+        );
+        assert_ne!(BPacket::SCHEMA_HASH, <u8>::SCHEMA_HASH);
+        assert_eq!(<Unit<Vector3>>::SCHEMA_HASH, <Unit<Vector3>>::SCHEMA_HASH);
+        assert_ne!(<Unit<Vector3>>::SCHEMA_HASH, <Half<Vector3>>::SCHEMA_HASH);
+    }
+
+    #[test]
+    fn test_schema() {
+        // flags share the leading byte, values follow
+        let SchemaKind::Struct { fields } = FPacket::SCHEMA.kind else {
+            panic!("expected a struct");
+        };
+        let layout: Vec<_> = fields
+            .iter()
+            .map(|field| (field.name, field.schema.name, field.offset, field.bit))
+            .collect();
+        assert_eq!(
+            layout,
+            [
+                ("jump", "bool", Some(0), Some(0)),
+                ("pos", "Vector2i", Some(1), None),
+                ("crouch", "bool", Some(0), Some(1)),
+                ("sprint", "bool", Some(0), Some(2)),
+            ]
+        );
+        assert_eq!(FPacket::SCHEMA.size, Some(FPacket::SIZE));
+
+        // each variant has its own size, the enum has none
+        let SchemaKind::Enum { variants } = CPacket::SCHEMA.kind else {
+            panic!("expected an enum");
+        };
+        let sizes: Vec<_> = variants.iter().map(|v| (v.name, v.tag, v.size)).collect();
+        assert_eq!(sizes, [("A", 0, Some(5)), ("B", 1, Some(10))]);
+        assert_eq!(CPacket::SCHEMA.size, None);
+        assert_eq!(BPacket::SCHEMA.size, Some(1));
+
+        // wrappers keep the wrapped type
+        assert_eq!(
+            <[Unit<Vector3>; 2]>::SCHEMA.to_string(),
+            "[Unit<Vector3>; 2] (6 bytes)"
+        );
+        assert_eq!(
+            APacket::SCHEMA.to_string(),
+            "APacket (8 bytes)\n  pos @0: Vector2i (8 bytes)"
+        );
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    #[netpacket(context = ())]
+    struct MPacket {
+        id: u32,
+        alive: bool,
+        position: Half<Vector3>,
+        aim: Unit<Vector3>,
+        transform: Trs<Transform3D>,
+        tint: Rgb<Color>,
+        heading: Angle<12>,
+        name: GString,
+        hidden: bool,
+    }
+
+    /// Fields of MPacket as written in a definition
+    const M_FIELDS: [(&str, &str); 9] = [
+        ("id", "u32"),
+        ("alive", "bool"),
+        ("position", "Half<Vector3>"),
+        ("aim", "Unit<Vector3>"),
+        ("transform", "Trs<Transform3D>"),
+        ("tint", "Rgb<Color>"),
+        ("heading", "Angle<12>"),
+        ("name", "GString"),
+        ("hidden", "bool"),
+    ];
+
+    #[test]
+    fn test_dynamic_schema() {
+        // definitions describe the same layout as the derived types
+        let m = DynamicSchema::new_struct("MPacket", &M_FIELDS).unwrap();
+        assert_eq!(m.schema(), MPacket::SCHEMA);
+        assert_eq!(m.schema().hash(), MPacket::SCHEMA_HASH);
+
+        let team: &[(&str, &str)] = &[("team", "u8")];
+        let k = DynamicSchema::new_enum("KPacket", &[("Join", team), ("Leave", &[])]).unwrap();
+        assert_eq!(k.schema(), KPacket::SCHEMA);
+
+        // types are written as displayed by the schemas
+        let types = [
+            "[Unit<Vector3>; 2]",
+            "Cell<Vector3, 4>",
+            "Angle<12>",
+            "Rigid2D<Angle<12>, Half<Vector2>>",
+        ];
+        for atype in types {
+            let schema = DynamicSchema::new_struct("T", &[("value", atype)]).unwrap();
+            let SchemaKind::Struct { fields } = schema.schema().kind else {
+                panic!("expected a struct");
+            };
+            assert_eq!(fields[0].schema.type_name(), atype);
+        }
+
+        // invalid definitions are reported with the path of the field
+        assert_eq!(
+            DynamicSchema::new_struct("T", &[("value", "Half<GString>")]),
+            Err(CodecError::Definition(
+                "T.value: unsupported wrapper Half".into()
+            ))
+        );
+        assert_eq!(
+            DynamicSchema::new_struct("T", &[("a", "u8"), ("a", "u8")]),
+            Err(CodecError::Definition("T.a: duplicate field".into()))
+        );
+        assert_eq!(
+            DynamicSchema::new_struct("T", &[("a", "Vector9")]),
+            Err(CodecError::Definition("T.a: unknown type Vector9".into()))
+        );
+
+        // sizes overflowing the address space and empty cells are rejected
+        assert_eq!(
+            DynamicSchema::new_struct("T", &[("a", "[[u64; 4294967295]; 4294967295]")]),
+            Err(CodecError::Definition(
+                "T.a: array of 4294967295 items is too large".into()
+            ))
+        );
+        assert_eq!(
+            DynamicSchema::new_struct("T", &[("a", "Cell<Vector3, 0>")]),
+            Err(CodecError::Definition(
+                "T.a: cells cannot have a size of zero".into()
+            ))
+        );
+    }
+
+    #[doc(hidden)]
+    #[allow(dead_code)]
+    fn test_dynamic() {
+        // definitions from JSON encode the same bytes as the derived type
+        let fields: Vec<String> = M_FIELDS
+            .iter()
+            .map(|(name, atype)| format!(r#"{{"name": "{name}", "type": "{atype}"}}"#))
+            .collect();
+        let json = format!(
+            r#"{{"name": "MPacket", "fields": [{}]}}"#,
+            fields.join(", ")
+        );
+        let dynamic = DynamicSchema::from_json(&json).unwrap();
+        assert_eq!(dynamic.schema(), MPacket::SCHEMA);
+
+        let m = MPacket {
+            id: 7,
+            alive: true,
+            position: Half(Vector3::new(1.0, 2.0, 3.0)),
+            aim: Unit(Vector3::UP),
+            transform: Trs(Transform3D::IDENTITY),
+            tint: Rgb(Color::from_rgb(1.0, 0.0, 0.0)),
+            heading: Angle(1.0),
+            name: "player".into(),
+            hidden: false,
+        };
+        let mut buffer = PackedByteArray::new();
+        m.serialize_with(&mut (), &mut buffer, 0).unwrap();
+
+        let mut data = VarDictionary::new();
+        data.set("id", 7);
+        data.set("alive", true);
+        data.set("position", Vector3::new(1.0, 2.0, 3.0));
+        data.set("aim", Vector3::UP);
+        data.set("transform", Transform3D::IDENTITY);
+        data.set("tint", Color::from_rgb(1.0, 0.0, 0.0));
+        data.set("heading", 1.0);
+        data.set("name", "player");
+        data.set("hidden", false);
+        assert_eq!(dynamic.encode(&data), Ok(buffer.clone()));
+
+        // decoding matches the derived type
+        let decoded = dynamic.decode(&buffer).unwrap();
+        let bytes = dynamic.encode(&decoded).unwrap();
+        assert_eq!(
+            MPacket::deserialize_with(&mut (), &bytes, 0),
+            MPacket::deserialize_with(&mut (), &buffer, 0)
+        );
+
+        // registered definitions are reachable from scripts
+        dynamic.register().unwrap();
+        assert_eq!(
+            NetPacketCodec::decode_packet("MPacket", &buffer),
+            Ok(decoded)
+        );
+    }
+
+    #[doc(hidden)]
+    #[allow(dead_code)]
+    fn test_peer() {
+        // a stream buffer carries the packets of the peer
+        let mut buffer = StreamPeerBuffer::new_gd();
+        let mut peer = PacketPeerStream::new_gd();
+        peer.set_stream_peer(&buffer);
+
+        let j = JPacket { score: 7 };
+        let k = KPacket::Join { team: 3 };
+        peer.send(&j).unwrap();
+        peer.send(&k).unwrap();
+        buffer.seek(0);
+        assert_eq!(peer.recv::<JPacket>(), Ok(j));
+        assert_eq!(peer.recv::<KPacket>(), Ok(k));
+        assert_eq!(peer.recv::<JPacket>(), Err(PeerError::Empty));
+
+        // packets are read whole
+        let position = buffer.get_position();
+        peer.send(&JPacket { score: 0 }).unwrap();
+        buffer.seek(position);
+        assert_eq!(peer.recv::<KPacket>(), Err(PeerError::TrailingBytes(2)));
+
+        // multiplayer peers report the sender
+        let mut multiplayer = OfflineMultiplayerPeer::new_gd();
+        assert_eq!(multiplayer.recv_from::<JPacket>(), Err(PeerError::Empty));
+        assert!(
+            multiplayer
+                .send_to(0, 0, TransferMode::RELIABLE, &JPacket { score: 7 })
+                .is_err()
+        );
+    }
+
+    #[doc(hidden)]
+    #[allow(dead_code)]
+    fn test_frame() {
+        let encoder = FrameEncoder::new();
+        let j = encoder.encode(&JPacket { score: 7 }).unwrap();
+        let k = encoder.encode_packet(&KPacket::Join { team: 3 }).unwrap();
+        assert_eq!(j.as_slice(), [4, 7, 0, 0, 0]);
+        assert_eq!(k.as_slice(), [4, 43, 0, 0, 3]);
+
+        // partial reads are accumulated until the frame is complete
+        let mut decoder = FrameDecoder::new();
+        for byte in &j.as_slice()[..j.len() - 1] {
+            decoder.push(&[*byte]);
+            assert_eq!(decoder.next_frame(), Ok(None));
+        }
+        decoder.push(&j.as_slice()[j.len() - 1..]);
+        decoder.push(k.as_slice());
+        assert_eq!(decoder.next_value(), Ok(Some(JPacket { score: 7 })));
+        assert_eq!(decoder.pending(), k.len());
+        assert_eq!(decoder.next_packet(), Ok(Some(KPacket::Join { team: 3 })));
+        assert_eq!(decoder.next_frame(), Ok(None));
+
+        // packets of another type are rejected
+        decoder.push(k.as_slice());
+        assert_eq!(
+            decoder.next_packet::<JPacket>(),
+            Err(FrameError::UnexpectedId {
+                expected: 42,
+                found: 43
+            })
+        );
+
+        // lengths above the limit are rejected before the frame is received
+        let mut decoder = FrameDecoder::new().max_size(3);
+        decoder.push(&[0xFF, 0xFF, 0xFF, 0xFF, 0x0F]);
+        assert_eq!(
+            decoder.next_frame(),
+            Err(FrameError::TooLarge {
+                size: u32::MAX as u64,
+                max: 3
+            })
+        );
+        let mut decoder = FrameDecoder::new();
+        decoder.push(&[0xFF; 10]);
+        assert_eq!(decoder.next_frame(), Err(FrameError::InvalidLength));
+        assert_eq!(
+            FrameEncoder::new()
+                .max_size(3)
+                .encode(&JPacket { score: 7 }),
+            Err(FrameError::TooLarge { size: 4, max: 3 })
+        );
+
+        // frames go through streams
+        let mut stream = StreamPeerBuffer::new_gd();
+        encoder.write(&mut stream, &j).unwrap();
+        encoder.write(&mut stream, &k).unwrap();
+        stream.seek(0);
+        let mut decoder = FrameDecoder::new();
+        assert_eq!(decoder.read_from(&mut stream), Ok(j.len() + k.len()));
+        assert_eq!(decoder.next_value(), Ok(Some(JPacket { score: 7 })));
+        assert_eq!(decoder.next_packet(), Ok(Some(KPacket::Join { team: 3 })));
+    }
+
+    #[doc(hidden)]
+    #[allow(dead_code)]
+    fn test_batch() {
+        // messages are appended until the next one does not fit
+        let mut batcher = PacketBatcher::new().mtu(12);
+        assert_eq!(batcher.push(&JPacket { score: 7 }), Ok(None));
+        assert_eq!(batcher.push(&KPacket::Join { team: 3 }), Ok(None));
+        assert_eq!(batcher.len(), 12);
+        let first = batcher.push(&JPacket { score: 8 }).unwrap().unwrap();
+        assert_eq!(first.as_slice(), [42, 0, 4, 7, 0, 0, 0, 43, 0, 2, 0, 3]);
+        assert_eq!(batcher.len(), 7);
+
+        // the rest is flushed at the end of the tick
+        let second = batcher.flush().unwrap();
+        assert_eq!(second.as_slice(), [42, 0, 4, 8, 0, 0, 0]);
+        assert!(batcher.is_empty());
+        assert_eq!(batcher.flush(), None);
+        assert_eq!(
+            batcher.push_raw(7, &PackedByteArray::from(&[0; 10][..])),
+            Err(BatchError::TooLarge { size: 13, mtu: 12 })
+        );
+
+        // unknown messages are skipped using their length
+        let mut batcher = PacketBatcher::new();
+        batcher.push(&JPacket { score: 7 }).unwrap();
+        batcher
+            .push_raw(7, &PackedByteArray::from(&[1, 2, 3][..]))
+            .unwrap();
+        batcher.push(&KPacket::Join { team: 3 }).unwrap();
+        let batch = batcher.flush().unwrap();
+        let messages = BatchReader::new(&batch)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            messages
+                .iter()
+                .map(|message| message.id)
+                .collect::<Vec<_>>(),
+            [42, 7, 43]
+        );
+        assert_eq!(messages[0].read_packet(), Ok(JPacket { score: 7 }));
+        assert_eq!(messages[1].payload(), [1, 2, 3]);
+        assert_eq!(messages[2].read_packet(), Ok(KPacket::Join { team: 3 }));
+        assert_eq!(
+            messages[2].read_packet::<JPacket>(),
+            Err(BatchError::UnexpectedId {
+                expected: 42,
+                found: 43
+            })
+        );
+
+        // messages can be dispatched by a registry
+        let mut registry = PacketRegistry::<u32>::new();
+        registry
+            .register(|count, packet: JPacket| *count += packet.score)
+            .unwrap();
+        registry
+            .register(|count, _: KPacket| *count += 1000)
+            .unwrap();
+        let mut count = 0;
+        for message in BatchReader::new(&batch) {
+            let message = message.unwrap();
+            if registry.contains(message.id) {
+                registry.dispatch(&mut count, &message.to_packet()).unwrap();
+            }
+        }
+        assert_eq!(count, 1007);
+
+        // the iteration stops at a truncated message
+        let truncated = PackedByteArray::from(&batch.as_slice()[..batch.len() - 1]);
+        let mut reader = BatchReader::new(&truncated);
+        assert!(reader.next().unwrap().is_ok());
+        assert!(reader.next().unwrap().is_ok());
+        assert_eq!(
+            reader.next().unwrap().err(),
+            Some(BatchError::Truncated(13))
+        );
+        assert!(reader.next().is_none());
+    }
+
+    #[derive(Debug, Serialize, ConstSize, Deserialize, PacketConvert, PartialEq)]
+    struct LPacket {
+        aim: Unit<Vector3>,
+        velocity: Half<Vector3>,
+    }
+
+    #[test]
+    fn test_gdscript() {
+        let script = GdScriptGenerator::new()
+            .add::<APacket>()
+            .add::<FPacket>()
+            .add::<LPacket>()
+            .add::<KPacket>()
+            .generate()
+            .unwrap();
+        assert!(script.contains(
+            "class APacket:\n\
+            \tvar pos: Vector2i\n\
+            \n\
+            \tfunc write(w: Writer) -> void:\n\
+            \t\tw.s32(pos.x)\n\
+            \t\tw.s32(pos.y)\n\
+            \n\
+            \tstatic func read(r: Reader) -> APacket:\n\
+            \t\tvar value := APacket.new()\n\
+            \t\tvalue.pos = Vector2i(r.s32(), r.s32())\n\
+            \t\treturn value\n"
+        ));
+        assert!(script.contains("static func from_bytes(bytes: PackedByteArray) -> APacket:"));
+
+        // flags share the leading byte
+        assert!(script.contains("w.u8(int(jump) | int(crouch) << 1 | int(sprint) << 2)"));
+        assert!(script.contains("value.crouch = ((_t0 >> 1) & 1) == 1"));
+
+        // wrappers use the quantization of the rust implementation
+        assert!(script.contains("w.unit(aim.z)"));
+        assert!(
+            script.contains("value.aim = Vector3(r.unit(), r.unit(), r.unit()).limit_length(1.0)")
+        );
+        assert!(script.contains("w.half(velocity.x)"));
+
+        // enums hold the variant and its fields
+        assert!(script.contains("enum Variant { JOIN = 0, LEAVE = 1 }"));
+        assert!(script.contains("value.data = Join.read(r)"));
+
+        // interned values depend on the state of the connection
+        let error = GdScriptGenerator::new().add::<HPacket>().generate();
+        assert!(matches!(error, Err(CodegenError::Unsupported(_))));
+    }
+
+    #[test]
+    fn test_csharp() {
+        let code = CSharpGenerator::new()
+            .namespace("Game.Net")
+            .add::<APacket>()
+            .add::<FPacket>()
+            .add::<LPacket>()
+            .add::<KPacket>()
+            .generate()
+            .unwrap();
+        assert!(code.contains("namespace Game.Net;"));
+        assert!(code.contains(
+            "    public void Write(ref Writer w)\n\
+            \x20   {\n\
+            \x20       w.S32(Pos.X);\n\
+            \x20       w.S32(Pos.Y);\n\
+            \x20   }\n"
+        ));
+        assert!(code.contains("public const int SerializedSize = 8;"));
+        assert!(code.contains("value.Pos = new Vector2I(r.S32(), r.S32());"));
+
+        // flags share the leading byte
+        assert!(
+            code.contains("w.U8((byte)((Jump ? 1 : 0) | (Crouch ? 2 : 0) | (Sprint ? 4 : 0)));")
+        );
+        assert!(code.contains("value.Crouch = (_t0 & 2) != 0;"));
+
+        // wrappers use the quantization of the rust implementation
+        assert!(code.contains("w.Unit(Aim.Z);"));
+        assert!(
+            code.contains("value.Aim = new Vector3(r.Unit(), r.Unit(), r.Unit()).LimitLength(1f);")
+        );
+        assert!(code.contains("w.Half(Velocity.X);"));
+
+        // enums hold the variant and the fields of each variant
+        assert!(code.contains("public enum Kind : byte { Join = 0, Leave = 1 }"));
+        assert!(code.contains("public JoinFields Join;"));
+        assert!(code.contains("value.Join = JoinFields.Read(ref r);"));
+
+        // interned values depend on the state of the connection
+        let error = CSharpGenerator::new().add::<HPacket>().generate();
+        assert!(matches!(error, Err(CodegenError::Unsupported(_))));
+    }
+
+    #[test]
+    fn test_varint_size() {
+        assert_eq!(varint_size(0), 1);
+        assert_eq!(varint_size(0x7F), 1);
+        assert_eq!(varint_size(0x80), 2);
+        assert_eq!(varint_size(0x3FFF), 2);
+        assert_eq!(varint_size(0x4000), 3);
+        assert_eq!(varint_size(u64::MAX), 10);
+    }
+
+    #[test]
+    fn test_string_table() {
+        let mut table = StringTable::new().max_strings(2);
+        assert_eq!(table.insert("res://a.tscn"), Some(0));
+        assert_eq!(table.insert("res://b.tres"), Some(1));
+        assert_eq!(table.insert("res://a.tscn"), Some(0));
+
+        // the table stops growing once full
+        assert!(table.is_full());
+        assert_eq!(table.insert("res://c.tres"), None);
+        assert_eq!(table.insert("res://b.tres"), Some(1));
+
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.index_of("res://b.tres"), Some(1));
+        assert_eq!(table.get(0), Some("res://a.tscn"));
+        assert_eq!(table.get(2), None);
+
+        table.clear();
+        assert!(table.is_empty());
+    }
+
+    #[test]
+    fn test_angle() {
+        // angles wrap around a full turn
+        assert_eq!(to_angle(0.0, 8), 0);
+        assert_eq!(to_angle(TAU, 8), 0);
+        assert_eq!(to_angle(-PI / 2.0, 16), 3 << 14);
+
+        // decoded angles are in the range ]-PI, PI]
+        assert_eq!(from_angle(1 << 11, 12), PI);
+        assert!(from_angle((1 << 11) + 1, 12) < 0.0);
+
+        // every step survives a round-trip
+        for step in 0..(1 << 12) {
+            assert_eq!(to_angle(from_angle(step, 12), 12), step);
+        }
+    }
+
+    #[test]
+    fn test_cell() {
+        // cells take 32 bits per axis
+        assert_eq!(<Cell<Vector3, 4>>::SIZE, 18);
+        assert_eq!(<Cell<Vector2, 1>>::SIZE, 12);
+
+        // cells out of range are rejected instead of saturated
+        assert_eq!(to_cell(-2147483648.0), Ok(i32::MIN));
+        assert_eq!(to_cell(32768.0), Ok(32768));
+        assert_eq!(to_cell(-32769.0), Ok(-32769));
+        assert_eq!(to_cell(2147483648.0), Err(()));
+        assert_eq!(to_cell(-4294967296.0), Err(()));
+        assert_eq!(to_cell(f32::NAN as _), Err(()));
+    }
+
+    #[test]
+    fn test_channel() {
+        // values are rounded to the nearest level
+        assert_eq!(to_channel(0.5, 255), 128);
+        assert_eq!(to_channel(0.999, 255), 255);
+        assert_eq!(to_channel(2.0, 31), 31);
+
+        // a channel survives repeated round-trips unchanged
+        for level in 0..=255 {
+            let mut value = from_channel(level, 255);
+            for _ in 0..4 {
+                value = from_channel(to_channel(value, 255), 255);
+            }
+            assert_eq!(to_channel(value, 255), level);
+        }
+    }
+
+    /// Deterministic stand-in for an unreliable transport, dropping, duplicating and reordering the packets
+    struct LossyChannel {
+        state: u32,
+
+        /// Percentage of the packets dropped
+        loss: u32,
+
+        in_flight: Vec<Vec<u8>>,
+    }
+
+    impl LossyChannel {
+        fn new(seed: u32, loss: u32) -> Self {
+            Self {
+                state: seed.max(1),
+                loss,
+                in_flight: Vec::new(),
+            }
+        }
+
+        fn random(&mut self) -> u32 {
+            self.state ^= self.state << 13;
+            self.state ^= self.state >> 17;
+            self.state ^= self.state << 5;
+            self.state
+        }
+
+        fn send(&mut self, packet: &[u8]) {
+            let roll = self.random() % 100;
+            if roll < self.loss {
+                return;
+            }
+            self.in_flight.push(packet.to_vec());
+            if roll >= 90 {
+                self.in_flight.push(packet.to_vec());
+            }
+        }
+
+        /// Take the packets in flight, shuffled
+        fn deliver(&mut self) -> Vec<Vec<u8>> {
+            let mut packets = std::mem::take(&mut self.in_flight);
+            for index in (1..packets.len()).rev() {
+                let other = self.random() as usize % (index + 1);
+                packets.swap(index, other);
+            }
+            packets
+        }
+    }
+
+    #[test]
+    fn test_fragment() {
+        // payloads are split into numbered fragments no larger than the MTU
+        let payload = (0..3000).map(|byte| byte as u8).collect::<Vec<_>>();
+        let mut fragmenter = Fragmenter::new();
+        let fragments = fragmenter.split(&payload).unwrap();
+        assert_eq!(
+            fragments.iter().map(Vec::len).collect::<Vec<_>>(),
+            [1200, 1200, 618]
+        );
+        assert_eq!(fragments[2][..FRAGMENT_HEADER_SIZE], [0, 0, 2, 0, 3, 0]);
+        assert_eq!(fragmenter.split(&[]).unwrap(), [vec![1, 0, 0, 0, 1, 0]]);
+        assert_eq!(
+            Fragmenter::new().mtu(FRAGMENT_HEADER_SIZE).split(&payload),
+            Err(FragmentError::MtuTooSmall(FRAGMENT_HEADER_SIZE))
+        );
+
+        // fragments are reassembled in any order, duplicates are ignored
+        let mut reassembler = Reassembler::new();
+        assert_eq!(reassembler.receive(&fragments[2], 0), Ok(None));
+        assert_eq!(reassembler.receive(&fragments[2], 0), Ok(None));
+        assert_eq!(reassembler.receive(&fragments[0], 0), Ok(None));
+        assert_eq!(
+            reassembler.memory(),
+            1194 + 612 + 3 * size_of::<Option<Vec<u8>>>()
+        );
+        assert_eq!(
+            reassembler.receive(&fragments[1], 0),
+            Ok(Some(payload.clone()))
+        );
+        assert_eq!(reassembler.receive(&fragments[1], 0), Ok(None));
+        assert_eq!((reassembler.pending(), reassembler.memory()), (0, 0));
+
+        // incomplete messages are dropped after the timeout
+        let mut reassembler = Reassembler::new().timeout(100);
+        reassembler.receive(&fragments[0], 0).unwrap();
+        assert_eq!(reassembler.expire(99), 0);
+        assert_eq!(reassembler.expire(100), 1);
+        assert_eq!((reassembler.pending(), reassembler.memory()), (0, 0));
+        assert_eq!(reassembler.receive(&fragments[1], 100), Ok(None));
+        assert_eq!(reassembler.receive(&fragments[2], 250), Ok(None));
+        assert_eq!(reassembler.pending(), 1);
+
+        // the oldest messages are dropped to respect the memory limit
+        let other = fragmenter.split(&payload).unwrap();
+        let mut reassembler = Reassembler::new().max_memory(3000);
+        reassembler.receive(&fragments[0], 0).unwrap();
+        reassembler.receive(&other[0], 1).unwrap();
+        reassembler.receive(&other[1], 2).unwrap();
+        assert_eq!(reassembler.pending(), 1);
+        assert_eq!(
+            reassembler.receive(&other[2], 3),
+            Err(FragmentError::MemoryLimit {
+                size: 3072,
+                max: 3000
+            })
+        );
+        assert_eq!((reassembler.pending(), reassembler.memory()), (0, 0));
+
+        // malformed fragments are rejected
+        let mut reassembler = Reassembler::new();
+        assert_eq!(
+            reassembler.receive(&[0, 0, 1], 0),
+            Err(FragmentError::MissingHeader)
+        );
+        assert_eq!(
+            reassembler.receive(&[0, 0, 3, 0, 3, 0], 0),
+            Err(FragmentError::InvalidIndex { index: 3, count: 3 })
+        );
+        reassembler.receive(&fragments[0], 0).unwrap();
+        assert_eq!(
+            reassembler.receive(&[0, 0, 1, 0, 2, 0], 0),
+            Err(FragmentError::CountMismatch(0))
+        );
+    }
+
+    #[test]
+    fn test_fragment_lossy() {
+        let mut fragmenter = Fragmenter::new().mtu(100);
+        let mut reassembler = Reassembler::new().mtu(100).timeout(50).max_memory(4096);
+        let messages = (0..200)
+            .map(|index| vec![index as u8; 10 + index * 7])
+            .collect::<Vec<_>>();
+
+        // messages are sent in bursts and delivered shuffled, with duplicates and losses
+        let mut channel = LossyChannel::new(7, 10);
+        let mut received = Vec::new();
+        for (tick, burst) in messages.chunks(4).enumerate() {
+            for message in burst {
+                for fragment in fragmenter.split(message).unwrap() {
+                    channel.send(&fragment);
+                }
+            }
+            for fragment in channel.deliver() {
+                if let Some(message) = reassembler.receive(&fragment, tick as u64 * 10).unwrap() {
+                    received.push(message);
+                }
+            }
+            assert!(reassembler.memory() <= 4096);
+        }
+
+        // every message is either delivered once and intact, or lost
+        assert!(received.len() > 20 && received.len() < messages.len());
+        let mut indices = received
+            .iter()
+            .map(|message| message[0])
+            .collect::<Vec<_>>();
+        indices.dedup();
+        assert_eq!(indices.len(), received.len());
+        for message in &received {
+            assert_eq!(message, &messages[message[0] as usize]);
+        }
+
+        // the fragments of lost messages are eventually released
+        reassembler.expire(10_000);
+        assert_eq!((reassembler.pending(), reassembler.memory()), (0, 0));
+
+        // hostile fragments announcing many fragments without sending them cannot bypass the memory limit
+        let mut reassembler = Reassembler::new().max_memory(4096);
+        assert_eq!(reassembler.max_fragments(), 4);
+        for id in 0..=u16::MAX {
+            let [low, high] = id.to_le_bytes();
+            assert_eq!(
+                reassembler.receive(&[low, high, 0, 0, 0xFF, 0xFF], 0),
+                Err(FragmentError::EmptyFragment { id, index: 0 })
+            );
+            assert_eq!(
+                reassembler.receive(&[low, high, 0xFE, 0xFF, 0xFF, 0xFF], 0),
+                Err(FragmentError::TooManyFragments {
+                    count: u16::MAX,
+                    max: 4
+                })
+            );
+            assert_eq!(reassembler.receive(&[low, high, 3, 0, 4, 0], 0), Ok(None));
+            assert!(reassembler.memory() <= 4096);
+        }
+        assert_eq!(
+            reassembler.pending(),
+            4096 / (4 * size_of::<Option<Vec<u8>>>())
+        );
+
+        // without losses every message is delivered
+        let mut channel = LossyChannel::new(11, 0);
+        let mut reassembler = Reassembler::new();
+        let mut received = 0;
+        for message in &messages {
+            for fragment in fragmenter.split(message).unwrap() {
+                channel.send(&fragment);
+            }
+        }
+        for fragment in channel.deliver() {
+            if let Some(message) = reassembler.receive(&fragment, 0).unwrap() {
+                assert_eq!(message, messages[message[0] as usize]);
+                received += 1;
+            }
+        }
+        assert_eq!(received, messages.len());
+    }
+
+    #[test]
+    fn test_reliable() {
+        // messages are acknowledged by the packets going the other way
+        let (mut a, mut b) = (ReliableChannel::new(), ReliableChannel::new());
+        let packet = a.send(b"hello", 0).unwrap();
+        assert_eq!(packet.len(), RELIABLE_HEADER_SIZE + 5);
+        b.receive(&packet, 10).unwrap();
+        assert_eq!(b.recv(), Some(b"hello".to_vec()));
+        assert_eq!(b.recv(), None);
+        let acks = b.update(20);
+        assert_eq!(acks.len(), 1);
+        assert!(b.update(30).is_empty());
+        assert_eq!(a.rtt(), None);
+        a.receive(&acks[0], 40).unwrap();
+        assert_eq!((a.in_flight(), a.rtt()), (0, Some(40.0)));
+        assert_eq!(a.resend_timeout(), 120);
+
+        // messages are delivered in order, duplicates are dropped
+        let packets = (0..3)
+            .map(|index| a.send(&[index], 50).unwrap())
+            .collect::<Vec<_>>();
+        b.receive(&packets[2], 60).unwrap();
+        b.receive(&packets[1], 60).unwrap();
+        assert_eq!(b.recv(), None);
+        b.receive(&packets[0], 60).unwrap();
+        b.receive(&packets[1], 60).unwrap();
+        assert_eq!(
+            std::iter::from_fn(|| b.recv()).collect::<Vec<_>>(),
+            [[0], [1], [2]]
+        );
+
+        // lost packets are resent after the timeout
+        assert!(a.update(169).is_empty());
+        let resent = a.update(170);
+        assert_eq!(resent, packets);
+        assert!(a.update(170 + 239).is_empty());
+        assert_eq!(a.update(170 + 240).len(), 3);
+        for ack in b.update(500) {
+            a.receive(&ack, 500).unwrap();
+        }
+        assert_eq!(a.in_flight(), 0);
+
+        // the number of packets in flight is bounded by the acknowledgment bitfield
+        for _ in 0..RELIABLE_WINDOW {
+            a.send(&[], 600).unwrap();
+        }
+        assert!(!a.can_send());
+        assert_eq!(a.send(&[], 600), Err(ReliableError::WindowFull));
+
+        // malformed packets are rejected
+        assert_eq!(b.receive(&[0; 4], 600), Err(ReliableError::MissingHeader));
+        assert_eq!(
+            b.receive(&[7; RELIABLE_HEADER_SIZE], 600),
+            Err(ReliableError::InvalidKind(7))
+        );
+        assert_eq!(
+            ReliableChannel::new().receive(&[0, 0, 1, 0, 0, 0, 0, 0, 0], 0),
+            Err(ReliableError::OutOfWindow(256))
+        );
+    }
+
+    #[test]
+    fn test_reliable_lossy() {
+        let (mut a, mut b) = (ReliableChannel::new(), ReliableChannel::new());
+        let (mut to_b, mut to_a) = (LossyChannel::new(3, 20), LossyChannel::new(5, 20));
+        let messages = (0..500u32)
+            .map(|index| index.to_le_bytes().to_vec())
+            .collect::<Vec<_>>();
+
+        // both peers send every message through transports dropping, duplicating and reordering the packets
+        let (mut sent_a, mut sent_b) = (0, 0);
+        let (mut received_a, mut received_b) = (Vec::new(), Vec::new());
+        for tick in 0..2000 {
+            let now = tick * 10;
+            while sent_a < messages.len() && a.can_send() {
+                to_b.send(&a.send(&messages[sent_a], now).unwrap());
+                sent_a += 1;
+            }
+            while sent_b < messages.len() && b.can_send() {
+                to_a.send(&b.send(&messages[sent_b], now).unwrap());
+                sent_b += 1;
+            }
+            for packet in to_b.deliver() {
+                b.receive(&packet, now).unwrap();
+            }
+            for packet in to_a.deliver() {
+                a.receive(&packet, now).unwrap();
+            }
+            received_a.extend(std::iter::from_fn(|| a.recv()));
+            received_b.extend(std::iter::from_fn(|| b.recv()));
+            for packet in a.update(now) {
+                to_b.send(&packet);
+            }
+            for packet in b.update(now) {
+                to_a.send(&packet);
+            }
+            if received_a.len() == messages.len() && received_b.len() == messages.len() {
+                break;
+            }
+        }
+
+        // every message is delivered once and in order
+        assert_eq!(received_a, messages);
+        assert_eq!(received_b, messages);
+        assert!(a.rtt().is_some());
+    }
 }
@@ -0,0 +1,259 @@
+//!
+//! Pack many small messages into a single datagram, each one preceded by its type identifier and its length.
+//!
+
+use super::{
+    DeserializeWith, PacketId, PacketRegistry, SerializeWith, decode_varint, encode_varint,
+    reserve, varint_size,
+};
+use godot::builtin::*;
+
+/// Largest datagram written by default, in bytes, small enough to avoid fragmentation on most networks
+pub const DEFAULT_MTU: usize = 1200;
+
+/// Size of the type identifier preceding each message
+const ID_SIZE: usize = 2;
+
+/// Error encountered when writing or reading a batch of messages
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchError {
+    #[error("Message of {size} bytes exceeds the MTU of {mtu} bytes")]
+    TooLarge { size: usize, mtu: usize },
+
+    #[error("Failed to serialize the message with identifier {0}")]
+    Serialize(u16),
+
+    #[error("The batch is truncated at offset {0}")]
+    Truncated(usize),
+
+    #[error("Failed to deserialize the message with identifier {0}")]
+    Deserialize(u16),
+
+    #[error("Expected message identifier {expected}, found {found}")]
+    UnexpectedId { expected: u16, found: u16 },
+}
+
+/// Append the messages of a tick into datagrams no larger than the MTU.
+/// Each message is stored as its identifier, the length of its payload as a varint, then the payload.
+#[derive(Debug, Clone)]
+pub struct PacketBatcher {
+    mtu: usize,
+
+    /// Datagram being filled
+    current: PackedByteArray,
+}
+
+impl Default for PacketBatcher {
+    fn default() -> Self {
+        Self {
+            mtu: DEFAULT_MTU,
+            current: PackedByteArray::new(),
+        }
+    }
+}
+
+impl PacketBatcher {
+    /// Create a batcher writing datagrams up to `DEFAULT_MTU` bytes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the largest datagram the batcher writes
+    pub fn mtu(mut self, mtu: usize) -> Self {
+        self.mtu = mtu;
+        self
+    }
+
+    /// Number of bytes in the datagram being filled
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.current.len()
+    }
+
+    /// Check if no message is waiting to be flushed
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.current.is_empty()
+    }
+
+    /// Append the packet, returns the full datagram to send if the packet does not fit in it
+    pub fn push<T>(&mut self, packet: &T) -> Result<Option<PackedByteArray>, BatchError>
+    where
+        T: PacketId + SerializeWith<()>,
+    {
+        self.push_with(&mut (), packet)
+    }
+
+    /// Append the packet using the context, returns the full datagram to send if the packet does not fit in it
+    pub fn push_with<C, T>(
+        &mut self,
+        ctx: &mut C,
+        packet: &T,
+    ) -> Result<Option<PackedByteArray>, BatchError>
+    where
+        T: PacketId + SerializeWith<C>,
+    {
+        let mut payload = PackedByteArray::new();
+        packet
+            .serialize_with(ctx, &mut payload, 0)
+            .map_err(|_| BatchError::Serialize(T::ID))?;
+        self.push_raw(T::ID, &payload)
+    }
+
+    /// Append the payload of a message already serialized,
+    /// returns the full datagram to send if the message does not fit in it
+    pub fn push_raw(
+        &mut self,
+        id: u16,
+        payload: &PackedByteArray,
+    ) -> Result<Option<PackedByteArray>, BatchError> {
+        let length = payload.len() as u64;
+        let size = ID_SIZE + varint_size(length) + payload.len();
+        if size > self.mtu {
+            return Err(BatchError::TooLarge {
+                size,
+                mtu: self.mtu,
+            });
+        }
+
+        // start a new datagram when the message does not fit
+        let full = if self.current.len() + size > self.mtu {
+            self.flush()
+        } else {
+            None
+        };
+
+        let start = self.current.len();
+        reserve(&mut self.current, start + ID_SIZE + varint_size(length));
+        let written = self
+            .current
+            .encode_u16(start, id)
+            .and_then(|_| encode_varint(&mut self.current, start + ID_SIZE, length));
+        written.map_err(|_| BatchError::Serialize(id))?;
+        self.current.extend_array(payload);
+        Ok(full)
+    }
+
+    /// Take the datagram being filled, if it holds any message, to send it at the end of the tick
+    pub fn flush(&mut self) -> Option<PackedByteArray> {
+        if self.current.is_empty() {
+            None
+        } else {
+            Some(std::mem::take(&mut self.current))
+        }
+    }
+}
+
+/// Message read from a batch
+#[derive(Debug, Clone, Copy)]
+pub struct BatchMessage<'a> {
+    /// Type identifier of the message
+    pub id: u16,
+
+    buffer: &'a PackedByteArray,
+
+    /// Offset of the payload in the batch
+    offset: usize,
+
+    /// Size of the payload
+    size: usize,
+}
+
+impl BatchMessage<'_> {
+    /// Bytes of the payload
+    #[inline]
+    pub fn payload(&self) -> &[u8] {
+        &self.buffer.as_slice()[self.offset..self.offset + self.size]
+    }
+
+    /// Deserialize the payload
+    pub fn read<T: DeserializeWith<()>>(&self) -> Result<T, BatchError> {
+        self.read_with(&mut ())
+    }
+
+    /// Deserialize the payload using the context
+    pub fn read_with<C, T: DeserializeWith<C>>(&self, ctx: &mut C) -> Result<T, BatchError> {
+        match T::deserialize_with(ctx, self.buffer, self.offset) {
+            Ok((value, size)) if size == self.size => Ok(value),
+            _ => Err(BatchError::Deserialize(self.id)),
+        }
+    }
+
+    /// Deserialize the payload, checking the message holds a packet of type `T`
+    pub fn read_packet<T>(&self) -> Result<T, BatchError>
+    where
+        T: PacketId + DeserializeWith<()>,
+    {
+        if self.id != T::ID {
+            return Err(BatchError::UnexpectedId {
+                expected: T::ID,
+                found: self.id,
+            });
+        }
+        self.read()
+    }
+
+    /// Copy the message into a packet starting with the header expected by `PacketRegistry::dispatch`
+    pub fn to_packet(&self) -> PackedByteArray {
+        let header = PacketRegistry::<()>::HEADER_SIZE;
+        let mut packet = PackedByteArray::new();
+        packet.resize(header);
+        // the buffer was just resized to hold the header
+        let _ = packet.encode_u16(0, self.id);
+        packet.extend(self.payload().iter().copied());
+        packet
+    }
+}
+
+/// Iterate the messages of a batch.
+/// Messages of unknown types can be skipped, their length is known without deserializing them.
+/// The iteration stops after the first error since the following messages cannot be located.
+#[derive(Debug, Clone)]
+pub struct BatchReader<'a> {
+    buffer: &'a PackedByteArray,
+
+    /// Offset of the next message
+    offset: usize,
+}
+
+impl<'a> BatchReader<'a> {
+    /// Read the messages of the datagram
+    pub fn new(buffer: &'a PackedByteArray) -> Self {
+        Self { buffer, offset: 0 }
+    }
+
+    /// Locate the message at the current offset
+    fn locate(&self) -> Result<BatchMessage<'a>, BatchError> {
+        let truncated = BatchError::Truncated(self.offset);
+        let id = self.buffer.decode_u16(self.offset).map_err(|_| truncated)?;
+        let (size, prefix) =
+            decode_varint(self.buffer, self.offset + ID_SIZE).map_err(|_| truncated)?;
+        let offset = self.offset + ID_SIZE + prefix;
+        let size = usize::try_from(size).map_err(|_| truncated)?;
+        if self.buffer.len() - offset < size {
+            return Err(truncated);
+        }
+        Ok(BatchMessage {
+            id,
+            buffer: self.buffer,
+            offset,
+            size,
+        })
+    }
+}
+
+impl<'a> Iterator for BatchReader<'a> {
+    type Item = Result<BatchMessage<'a>, BatchError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.buffer.len() {
+            return None;
+        }
+        let message = self.locate();
+        self.offset = match &message {
+            Ok(message) => message.offset + message.size,
+            Err(_) => self.buffer.len(),
+        };
+        Some(message)
+    }
+}
@@ -62,5 +62,7 @@ impl_box!(Rect2i[Vector2i]);
 impl_box!(Rect2[Vector2]);
 impl_box!(Aabb[Vector3]);
 
+crate::impl_contextless![[] Rect2i, [] Rect2, [] Aabb];
+
 impl_box!(Rect2[Vector2] as Half);
 impl_box!(Aabb[Vector3] as Half);
@@ -0,0 +1,750 @@
+//!
+//! Encode and decode packets from godot values using their schema, so that scripts share the wire format.
+//!
+
+use super::{
+    Angle, DynSize, DynamicSchema, Full, Half, PacketSchema, Rgb, Rgb565, Rgba4444, SNorm16,
+    Schema, SchemaField, SchemaKind, Trs, UNorm8, UNorm16, Unit, Wrapper, reserve, to_cell,
+};
+use godot::{
+    builtin::*,
+    classes::InputEvent,
+    global::{JoyAxis, JoyButton, Key, KeyModifierMask, MouseButton, MouseButtonMask, godot_error},
+    meta::{FromGodot, ToGodot},
+    obj::Gd,
+    prelude::{GodotClass, godot_api},
+};
+use std::{
+    collections::BTreeMap,
+    sync::{Mutex, PoisonError},
+};
+
+/// Key holding the name of the variant of an enum
+pub(crate) const VARIANT_KEY: &str = "variant";
+
+/// Key holding the fields of the variant of an enum
+pub(crate) const FIELDS_KEY: &str = "fields";
+
+/// Error encountered when encoding or decoding a packet from godot values
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum CodecError {
+    #[error("Unknown packet type {0}")]
+    UnknownPacket(String),
+
+    #[error("Different packet types are named {0}")]
+    Conflict(&'static str),
+
+    #[error("Missing value for {0}")]
+    Missing(String),
+
+    #[error("Value of {path} cannot be converted into {expected}")]
+    InvalidValue { path: String, expected: String },
+
+    #[error("Unknown variant {variant} for {path}")]
+    UnknownVariant { path: String, variant: String },
+
+    #[error("Type {0} depends on the state of the connection")]
+    Unsupported(String),
+
+    #[error("Failed to encode the value of {0}")]
+    Encode(String),
+
+    #[error("Failed to decode the value of {0}")]
+    Decode(String),
+
+    #[error("Invalid packet definition: {0}")]
+    Definition(String),
+}
+
+/// Packet types reachable by name
+static PACKETS: Mutex<BTreeMap<&'static str, &'static Schema>> = Mutex::new(BTreeMap::new());
+
+/// Encode and decode the registered packet types from scripts.
+/// Structs are represented as dictionaries holding the fields,
+/// enums as dictionaries holding the name of the `variant` and its `fields`.
+#[derive(GodotClass)]
+#[class(init, base = RefCounted)]
+pub struct NetPacketCodec {}
+
+impl NetPacketCodec {
+    /// Make the packet type reachable by its name
+    pub fn register<T: PacketSchema + ?Sized>() -> Result<(), CodecError> {
+        Self::register_schema(T::SCHEMA)
+    }
+
+    /// Make the packet type described by the schema reachable by its name
+    pub fn register_schema(schema: &'static Schema) -> Result<(), CodecError> {
+        let mut packets = PACKETS.lock().unwrap_or_else(PoisonError::into_inner);
+        match packets.get(schema.name) {
+            Some(known) if *known != schema => Err(CodecError::Conflict(schema.name)),
+            Some(_) => Ok(()),
+            None => {
+                packets.insert(schema.name, schema);
+                Ok(())
+            }
+        }
+    }
+
+    /// Register the schema returned by `describe`, given the schema already registered with the name.
+    /// The registry stays locked meanwhile, so the schema is only created when it is actually registered.
+    pub(super) fn register_with(
+        name: &str,
+        describe: impl FnOnce(Option<&'static Schema>) -> Result<&'static Schema, CodecError>,
+    ) -> Result<&'static Schema, CodecError> {
+        let mut packets = PACKETS.lock().unwrap_or_else(PoisonError::into_inner);
+        let known = packets.get(name).copied();
+        let schema = describe(known)?;
+        if known.is_none() {
+            packets.insert(schema.name, schema);
+        }
+        Ok(schema)
+    }
+
+    /// Get the schema of the packet type registered with the name
+    pub fn schema(packet: &str) -> Result<&'static Schema, CodecError> {
+        let packets = PACKETS.lock().unwrap_or_else(PoisonError::into_inner);
+        packets
+            .get(packet)
+            .copied()
+            .ok_or_else(|| CodecError::UnknownPacket(packet.to_owned()))
+    }
+
+    /// Encode the packet from the dictionary holding its fields
+    pub fn encode_packet(
+        packet: &str,
+        data: &VarDictionary,
+    ) -> Result<PackedByteArray, CodecError> {
+        encode_dictionary(Self::schema(packet)?, data)
+    }
+
+    /// Decode the packet into a dictionary holding its fields
+    pub fn decode_packet(
+        packet: &str,
+        buffer: &PackedByteArray,
+    ) -> Result<VarDictionary, CodecError> {
+        decode_dictionary(Self::schema(packet)?, buffer)
+    }
+}
+
+#[godot_api]
+impl NetPacketCodec {
+    /// Encode the packet type with the given name from the dictionary, returns an empty buffer on failure
+    #[func]
+    fn encode(packet: StringName, data: VarDictionary) -> PackedByteArray {
+        Self::encode_packet(&packet.to_string(), &data).unwrap_or_else(|error| {
+            godot_error!("{}", error);
+            PackedByteArray::new()
+        })
+    }
+
+    /// Decode the packet type with the given name into a dictionary, returns an empty dictionary on failure
+    #[func]
+    fn decode(packet: StringName, bytes: PackedByteArray) -> VarDictionary {
+        Self::decode_packet(&packet.to_string(), &bytes).unwrap_or_else(|error| {
+            godot_error!("{}", error);
+            VarDictionary::new()
+        })
+    }
+
+    /// Register the packet type described by the dictionary, see `DynamicSchema`.
+    /// Returns false if the definition is invalid or conflicts with a registered packet type.
+    #[func]
+    fn register_definition(definition: VarDictionary) -> bool {
+        DynamicSchema::register_definition(&definition)
+            .map_err(|error| godot_error!("{}", error))
+            .is_ok()
+    }
+
+    /// Check if a packet type is registered with the given name
+    #[func]
+    fn has_packet(packet: StringName) -> bool {
+        Self::schema(&packet.to_string()).is_ok()
+    }
+
+    /// List the names of the registered packet types
+    #[func]
+    fn get_packet_names() -> PackedStringArray {
+        let packets = PACKETS.lock().unwrap_or_else(PoisonError::into_inner);
+        packets.keys().map(|name| GString::from(*name)).collect()
+    }
+}
+
+/// Encode the packet described by the schema from the dictionary holding its fields
+pub(crate) fn encode_dictionary(
+    schema: &Schema,
+    data: &VarDictionary,
+) -> Result<PackedByteArray, CodecError> {
+    let mut buffer = PackedByteArray::new();
+    encode_value(schema, &data.to_variant(), &mut buffer, 0, schema.name)?;
+    Ok(buffer)
+}
+
+/// Decode the packet described by the schema into a dictionary holding its fields
+pub(crate) fn decode_dictionary(
+    schema: &Schema,
+    buffer: &PackedByteArray,
+) -> Result<VarDictionary, CodecError> {
+    let (value, _) = decode_value(schema, buffer, 0, schema.name)?;
+    value
+        .try_to()
+        .map_err(|_| CodecError::Decode(schema.name.to_owned()))
+}
+
+/// Path of a value nested in another one
+pub(crate) fn child(path: &str, name: &str) -> String {
+    format!("{path}.{name}")
+}
+
+/// Convert the godot value into the type expected by the schema
+fn convert<T: FromGodot>(value: &Variant, schema: &Schema, path: &str) -> Result<T, CodecError> {
+    value
+        .try_to_relaxed::<T>()
+        .map_err(|_| CodecError::InvalidValue {
+            path: path.to_owned(),
+            expected: schema.type_name(),
+        })
+}
+
+/// Write the value using its Rust implementation, returns the offset following it
+fn put<T: super::Serialize + DynSize>(
+    value: T,
+    buffer: &mut PackedByteArray,
+    offset: usize,
+    path: &str,
+) -> Result<usize, CodecError> {
+    let end = offset + value.size();
+    reserve(buffer, end);
+    value
+        .serialize(buffer, offset)
+        .map_err(|_| CodecError::Encode(path.to_owned()))?;
+    Ok(end)
+}
+
+/// Read the value using its Rust implementation, returns the offset following it
+fn get<T: super::Deserialize + DynSize>(
+    buffer: &PackedByteArray,
+    offset: usize,
+    path: &str,
+) -> Result<(T, usize), CodecError> {
+    let value = T::deserialize(buffer, offset).map_err(|_| CodecError::Decode(path.to_owned()))?;
+    let end = offset + value.size();
+    Ok((value, end))
+}
+
+/// Call the macro with the Rust type of the built-in schema, angles are passed to their own macro when given
+macro_rules! for_builtin {
+    ( $name:expr, $body:ident, $angle:ident, $fallback:expr ) => {
+        for_builtin!($name, $body, {
+            match $name {
+                "Angle<8>" => $angle!(Angle<8>),
+                "Angle<12>" => $angle!(Angle<12>),
+                "Angle<16>" => $angle!(Angle<16>),
+                _ => $fallback,
+            }
+        })
+    };
+    ( $name:expr, $body:ident, $fallback:expr ) => {
+        match $name {
+            "u8" => $body!(u8),
+            "u16" => $body!(u16),
+            "u32" => $body!(u32),
+            "i8" => $body!(i8),
+            "i16" => $body!(i16),
+            "i32" => $body!(i32),
+            "i64" => $body!(i64),
+            "f32" => $body!(f32),
+            "f64" => $body!(f64),
+            "bool" => $body!(bool),
+            "Vector2" => $body!(Vector2),
+            "Vector3" => $body!(Vector3),
+            "Vector4" => $body!(Vector4),
+            "Vector2i" => $body!(Vector2i),
+            "Vector3i" => $body!(Vector3i),
+            "Vector4i" => $body!(Vector4i),
+            "Quaternion" => $body!(Quaternion),
+            "Basis" => $body!(Basis),
+            "Transform2D" => $body!(Transform2D),
+            "Transform3D" => $body!(Transform3D),
+            "Plane" => $body!(Plane),
+            "Color" => $body!(Color),
+            "Rect2" => $body!(Rect2),
+            "Rect2i" => $body!(Rect2i),
+            "Aabb" => $body!(Aabb),
+            "GString" => $body!(GString),
+            "StringName" => $body!(StringName),
+            "NodePath" => $body!(NodePath),
+            "Key" => $body!(Key),
+            "MouseButton" => $body!(MouseButton),
+            "JoyButton" => $body!(JoyButton),
+            "JoyAxis" => $body!(JoyAxis),
+            "KeyModifierMask" => $body!(KeyModifierMask),
+            "MouseButtonMask" => $body!(MouseButtonMask),
+            "InputEvent" => $body!(Gd<InputEvent>),
+            _ => $fallback,
+        }
+    };
+}
+
+/// Call the macro with the Rust wrapper and wrapped type of the schema
+macro_rules! for_wrapper {
+    ( $wrapper:expr, $inner:expr, $param:expr, $body:ident, $fallback:expr ) => {
+        match ($wrapper, $inner, $param) {
+            ("Unit", "f32", _) => $body!(Unit, f32),
+            ("Unit", "Vector2", _) => $body!(Unit, Vector2),
+            ("Unit", "Vector3", _) => $body!(Unit, Vector3),
+            ("Unit", "Vector4", _) => $body!(Unit, Vector4),
+            ("Unit", "Quaternion", _) => $body!(Unit, Quaternion),
+            ("Unit", "Basis", _) => $body!(Unit, Basis),
+            ("Unit", "Transform2D", _) => $body!(Unit, Transform2D),
+            ("Unit", "Transform3D", _) => $body!(Unit, Transform3D),
+            ("Unit", "Plane", _) => $body!(Unit, Plane),
+            ("Half" | "Relative", "f32", _) => $body!(Half, f32),
+            ("Half" | "Relative", "Vector2", _) => $body!(Half, Vector2),
+            ("Half" | "Relative", "Vector3", _) => $body!(Half, Vector3),
+            ("Half" | "Relative", "Vector4", _) => $body!(Half, Vector4),
+            ("Half", "Quaternion", _) => $body!(Half, Quaternion),
+            ("Half", "Basis", _) => $body!(Half, Basis),
+            ("Half", "Transform2D", _) => $body!(Half, Transform2D),
+            ("Half", "Transform3D", _) => $body!(Half, Transform3D),
+            ("Half", "Plane", _) => $body!(Half, Plane),
+            ("Half", "Color", _) => $body!(Half, Color),
+            ("Half", "Rect2", _) => $body!(Half, Rect2),
+            ("Half", "Aabb", _) => $body!(Half, Aabb),
+            ("Full", "Color", _) => $body!(Full, Color),
+            ("Rgb", "Color", _) => $body!(Rgb, Color),
+            ("Rgb565", "Color", _) => $body!(Rgb565, Color),
+            ("Rgba4444", "Color", _) => $body!(Rgba4444, Color),
+            ("UNorm8", "f32", _) => $body!(UNorm8, f32),
+            ("UNorm8", "Vector2", _) => $body!(UNorm8, Vector2),
+            ("UNorm8", "Vector3", _) => $body!(UNorm8, Vector3),
+            ("UNorm8", "Vector4", _) => $body!(UNorm8, Vector4),
+            ("UNorm16", "f32", _) => $body!(UNorm16, f32),
+            ("UNorm16", "Vector2", _) => $body!(UNorm16, Vector2),
+            ("UNorm16", "Vector3", _) => $body!(UNorm16, Vector3),
+            ("UNorm16", "Vector4", _) => $body!(UNorm16, Vector4),
+            ("SNorm16", "f32", _) => $body!(SNorm16, f32),
+            ("SNorm16", "Vector2", _) => $body!(SNorm16, Vector2),
+            ("SNorm16", "Vector3", _) => $body!(SNorm16, Vector3),
+            ("SNorm16", "Vector4", _) => $body!(SNorm16, Vector4),
+            ("SNorm16", "Quaternion", _) => $body!(SNorm16, Quaternion),
+            ("Trs", "Basis", _) => $body!(Trs, Basis),
+            ("Trs", "Transform2D", _) => $body!(Trs, Transform2D),
+            ("Trs", "Transform3D", _) => $body!(Trs, Transform3D),
+            _ => $fallback,
+        }
+    };
+}
+
+pub(crate) use {for_builtin, for_wrapper};
+
+/// Write the value described by the schema, returns the offset following it
+pub(crate) fn encode_value(
+    schema: &Schema,
+    value: &Variant,
+    buffer: &mut PackedByteArray,
+    offset: usize,
+    path: &str,
+) -> Result<usize, CodecError> {
+    match schema.kind {
+        SchemaKind::Builtin => {
+            macro_rules! builtin {
+                ($atype:ty) => {
+                    put(
+                        convert::<$atype>(value, schema, path)?,
+                        buffer,
+                        offset,
+                        path,
+                    )
+                };
+            }
+            macro_rules! angle {
+                ($atype:ty) => {
+                    put(
+                        <$atype as Wrapper<real>>::wrap(convert(value, schema, path)?),
+                        buffer,
+                        offset,
+                        path,
+                    )
+                };
+            }
+            for_builtin!(schema.name, builtin, angle, {
+                match schema.name {
+                    // identifiers of objects are plain numbers
+                    "u64" | "NetworkId" => {
+                        let number = convert::<i64>(value, schema, path)?;
+                        if schema.name == "u64" {
+                            put(number as u64, buffer, offset, path)
+                        } else {
+                            let number =
+                                u32::try_from(number).map_err(|_| CodecError::InvalidValue {
+                                    path: path.to_owned(),
+                                    expected: schema.type_name(),
+                                })?;
+                            put(number, buffer, offset, path)
+                        }
+                    }
+                    _ => Err(CodecError::Unsupported(schema.type_name())),
+                }
+            })
+        }
+
+        SchemaKind::Wrapper { inner, param } => {
+            macro_rules! wrapper {
+                ($wrap:ident, $atype:ty) => {
+                    put(
+                        $wrap::<$atype>(convert::<$atype>(value, inner, path)?),
+                        buffer,
+                        offset,
+                        path,
+                    )
+                };
+            }
+            for_wrapper!(schema.name, inner.name, param, wrapper, {
+                match (schema.name, inner.name) {
+                    ("Flags", _) => encode_value(inner, value, buffer, offset, path),
+                    ("Cell", "Vector2" | "Vector3") => {
+                        encode_cell(inner, param.unwrap_or(1), value, buffer, offset, path)
+                    }
+                    ("Rigid2D", _) => encode_rigid(inner, value, buffer, offset, path),
+                    _ => Err(CodecError::Unsupported(schema.type_name())),
+                }
+            })
+        }
+
+        SchemaKind::Struct { fields } => {
+            let data: VarDictionary = convert(value, schema, path)?;
+            encode_fields(fields, &data, buffer, offset, path)
+        }
+
+        SchemaKind::Enum { variants } => {
+            let data: VarDictionary = convert(value, schema, path)?;
+            let name = lookup(&data, VARIANT_KEY)
+                .ok_or_else(|| CodecError::Missing(child(path, VARIANT_KEY)))?
+                .stringify()
+                .to_string();
+            let variant = variants
+                .iter()
+                .find(|variant| variant.name == name)
+                .ok_or_else(|| CodecError::UnknownVariant {
+                    path: path.to_owned(),
+                    variant: name.clone(),
+                })?;
+
+            // the tag is followed by the fields of the variant
+            let end = put(variant.tag, buffer, offset, path)?;
+            let fields = match lookup(&data, FIELDS_KEY) {
+                Some(fields) => convert(&fields, schema, &child(path, FIELDS_KEY))?,
+                None => VarDictionary::new(),
+            };
+            encode_fields(variant.fields, &fields, buffer, end, &child(path, &name))
+        }
+
+        SchemaKind::Array { item, len } => {
+            let items: VarArray = convert(value, schema, path)?;
+            if items.len() != len {
+                return Err(CodecError::InvalidValue {
+                    path: path.to_owned(),
+                    expected: schema.type_name(),
+                });
+            }
+            items
+                .iter_shared()
+                .enumerate()
+                .try_fold(offset, |end, (index, value)| {
+                    encode_value(item, &value, buffer, end, &format!("{path}[{index}]"))
+                })
+        }
+
+        SchemaKind::Tuple { items } => {
+            let values: VarArray = convert(value, schema, path)?;
+            if values.len() != items.len() {
+                return Err(CodecError::InvalidValue {
+                    path: path.to_owned(),
+                    expected: schema.type_name(),
+                });
+            }
+            items.iter().zip(values.iter_shared()).enumerate().try_fold(
+                offset,
+                |end, (index, (item, value))| {
+                    encode_value(item, &value, buffer, end, &format!("{path}[{index}]"))
+                },
+            )
+        }
+    }
+}
+
+/// Read the value described by the schema, returns the offset following it
+pub(crate) fn decode_value(
+    schema: &Schema,
+    buffer: &PackedByteArray,
+    offset: usize,
+    path: &str,
+) -> Result<(Variant, usize), CodecError> {
+    match schema.kind {
+        SchemaKind::Builtin => {
+            macro_rules! builtin {
+                ($atype:ty) => {
+                    get::<$atype>(buffer, offset, path)
+                        .map(|(value, end)| (value.to_variant(), end))
+                };
+            }
+            macro_rules! angle {
+                ($atype:ty) => {
+                    get::<$atype>(buffer, offset, path)
+                        .map(|(value, end)| (Wrapper::<real>::into_inner(value).to_variant(), end))
+                };
+            }
+            for_builtin!(schema.name, builtin, angle, {
+                match schema.name {
+                    "u64" => get::<u64>(buffer, offset, path)
+                        .map(|(value, end)| ((value as i64).to_variant(), end)),
+                    "NetworkId" => get::<u32>(buffer, offset, path)
+                        .map(|(value, end)| (value.to_variant(), end)),
+                    _ => Err(CodecError::Unsupported(schema.type_name())),
+                }
+            })
+        }
+
+        SchemaKind::Wrapper { inner, param } => {
+            macro_rules! wrapper {
+                ($wrap:ident, $atype:ty) => {
+                    get::<$wrap<$atype>>(buffer, offset, path)
+                        .map(|(value, end)| (value.0.to_variant(), end))
+                };
+            }
+            for_wrapper!(schema.name, inner.name, param, wrapper, {
+                match (schema.name, inner.name) {
+                    ("Flags", _) => decode_value(inner, buffer, offset, path),
+                    ("Cell", "Vector2" | "Vector3") => {
+                        decode_cell(inner, param.unwrap_or(1), buffer, offset, path)
+                    }
+                    ("Rigid2D", _) => decode_rigid(inner, buffer, offset, path),
+                    _ => Err(CodecError::Unsupported(schema.type_name())),
+                }
+            })
+        }
+
+        SchemaKind::Struct { fields } => {
+            decode_fields(fields, buffer, offset, path).map(|(data, end)| (data.to_variant(), end))
+        }
+
+        SchemaKind::Enum { variants } => {
+            let (tag, end) = get::<u8>(buffer, offset, path)?;
+            let variant = variants
+                .iter()
+                .find(|variant| variant.tag == tag)
+                .ok_or_else(|| CodecError::Decode(path.to_owned()))?;
+            let (fields, end) =
+                decode_fields(variant.fields, buffer, end, &child(path, variant.name))?;
+
+            let mut data = VarDictionary::new();
+            data.set(VARIANT_KEY, variant.name);
+            data.set(FIELDS_KEY, fields);
+            Ok((data.to_variant(), end))
+        }
+
+        SchemaKind::Array { item, len } => {
+            let mut items = VarArray::new();
+            let mut end = offset;
+            for index in 0..len {
+                let (value, next) = decode_value(item, buffer, end, &format!("{path}[{index}]"))?;
+                items.push(&value);
+                end = next;
+            }
+            Ok((items.to_variant(), end))
+        }
+
+        SchemaKind::Tuple { items } => {
+            let mut values = VarArray::new();
+            let mut end = offset;
+            for (index, item) in items.iter().enumerate() {
+                let (value, next) = decode_value(item, buffer, end, &format!("{path}[{index}]"))?;
+                values.push(&value);
+                end = next;
+            }
+            Ok((values.to_variant(), end))
+        }
+    }
+}
+
+/// Get the value of the key, accepting both strings and string names as keys
+pub(crate) fn lookup(data: &VarDictionary, key: &str) -> Option<Variant> {
+    data.get(key).or_else(|| data.get(StringName::from(key)))
+}
+
+/// Write the bytes holding the flags followed by the values, returns the offset following them
+fn encode_fields(
+    fields: &[SchemaField],
+    data: &VarDictionary,
+    buffer: &mut PackedByteArray,
+    offset: usize,
+    path: &str,
+) -> Result<usize, CodecError> {
+    let field = |field: &SchemaField| {
+        let path = child(path, field.name);
+        lookup(data, field.name)
+            .map(|value| (value, path.clone()))
+            .ok_or(CodecError::Missing(path))
+    };
+
+    // flags are stored in the leading bytes, in the order of declaration
+    let flags: Vec<&SchemaField> = fields.iter().filter(|field| field.bit.is_some()).collect();
+    let mut bits = vec![0u8; flags.len().div_ceil(8)];
+    for (index, flag) in flags.iter().enumerate() {
+        let (value, path) = field(flag)?;
+        if convert::<bool>(&value, flag.schema, &path)? {
+            bits[index / 8] |= 1 << flag.bit.unwrap_or_default();
+        }
+    }
+    let mut end = offset + bits.len();
+    reserve(buffer, end);
+    buffer.as_mut_slice()[offset..end].copy_from_slice(&bits);
+
+    for value in fields.iter().filter(|field| field.bit.is_none()) {
+        let (data, path) = field(value)?;
+        end = encode_value(value.schema, &data, buffer, end, &path)?;
+    }
+    Ok(end)
+}
+
+/// Read the bytes holding the flags followed by the values, returns the offset following them
+fn decode_fields(
+    fields: &[SchemaField],
+    buffer: &PackedByteArray,
+    offset: usize,
+    path: &str,
+) -> Result<(VarDictionary, usize), CodecError> {
+    let flags: Vec<&SchemaField> = fields.iter().filter(|field| field.bit.is_some()).collect();
+    let mut end = offset + flags.len().div_ceil(8);
+
+    let mut values = Vec::with_capacity(fields.len());
+    for (index, chunk) in flags.chunks(8).enumerate() {
+        // the unused bits of the last byte must be zero, like the derived types
+        let (byte, _) = get::<u8>(buffer, offset + index, path)?;
+        if u16::from(byte) >> chunk.len() != 0 {
+            return Err(CodecError::Decode(path.to_owned()));
+        }
+        for flag in chunk {
+            let bit = (byte >> flag.bit.unwrap_or_default()) & 1 == 1;
+            values.push((flag.name, bit.to_variant()));
+        }
+    }
+    for field in fields.iter().filter(|field| field.bit.is_none()) {
+        let (value, next) = decode_value(field.schema, buffer, end, &child(path, field.name))?;
+        values.push((field.name, value));
+        end = next;
+    }
+
+    // keep the order of declaration
+    let mut data = VarDictionary::new();
+    for field in fields {
+        if let Some((_, value)) = values.iter().find(|(name, _)| *name == field.name) {
+            data.set(field.name, value.clone());
+        }
+    }
+    Ok((data, end))
+}
+
+/// Write a position as a cell of 32 bits per axis followed by the position within the cell
+fn encode_cell(
+    inner: &Schema,
+    size: u32,
+    value: &Variant,
+    buffer: &mut PackedByteArray,
+    offset: usize,
+    path: &str,
+) -> Result<usize, CodecError> {
+    let size = size.max(1) as real;
+    let axes = |cell: &[real]| -> Result<Vec<i32>, CodecError> {
+        cell.iter()
+            .map(|axis| to_cell(*axis).map_err(|_| CodecError::Encode(path.to_owned())))
+            .collect()
+    };
+    let mut end = offset;
+    if inner.name == "Vector2" {
+        let position = convert::<Vector2>(value, inner, path)? / size;
+        let cell = position.floor();
+        for axis in axes(&[cell.x, cell.y])? {
+            end = put(axis, buffer, end, path)?;
+        }
+        put(UNorm16::<Vector2>(position - cell), buffer, end, path)
+    } else {
+        let position = convert::<Vector3>(value, inner, path)? / size;
+        let cell = position.floor();
+        for axis in axes(&[cell.x, cell.y, cell.z])? {
+            end = put(axis, buffer, end, path)?;
+        }
+        put(UNorm16::<Vector3>(position - cell), buffer, end, path)
+    }
+}
+
+/// Read a position stored as a cell of 32 bits per axis followed by the position within the cell
+fn decode_cell(
+    inner: &Schema,
+    size: u32,
+    buffer: &PackedByteArray,
+    offset: usize,
+    path: &str,
+) -> Result<(Variant, usize), CodecError> {
+    let size = size.max(1) as real;
+    let (x, end) = get::<i32>(buffer, offset, path)?;
+    let (y, end) = get::<i32>(buffer, end, path)?;
+    if inner.name == "Vector2" {
+        let (local, end) = get::<UNorm16<Vector2>>(buffer, end, path)?;
+        let cell = Vector2::new(x as real, y as real);
+        Ok((((cell + local.0) * size).to_variant(), end))
+    } else {
+        let (z, end) = get::<i32>(buffer, end, path)?;
+        let (local, end) = get::<UNorm16<Vector3>>(buffer, end, path)?;
+        let cell = Vector3::new(x as real, y as real, z as real);
+        Ok((((cell + local.0) * size).to_variant(), end))
+    }
+}
+
+/// Write a 2D transform as its rotation followed by its origin
+fn encode_rigid(
+    inner: &Schema,
+    value: &Variant,
+    buffer: &mut PackedByteArray,
+    offset: usize,
+    path: &str,
+) -> Result<usize, CodecError> {
+    let SchemaKind::Tuple {
+        items: [rotation, origin],
+    } = inner.kind
+    else {
+        return Err(CodecError::Unsupported(inner.type_name()));
+    };
+    let transform = convert::<Transform2D>(value, inner, path)?;
+    let end = encode_value(
+        rotation,
+        &transform.rotation().to_variant(),
+        buffer,
+        offset,
+        path,
+    )?;
+    encode_value(origin, &transform.origin.to_variant(), buffer, end, path)
+}
+
+/// Read a 2D transform stored as its rotation followed by its origin
+fn decode_rigid(
+    inner: &Schema,
+    buffer: &PackedByteArray,
+    offset: usize,
+    path: &str,
+) -> Result<(Variant, usize), CodecError> {
+    let SchemaKind::Tuple {
+        items: [rotation, origin],
+    } = inner.kind
+    else {
+        return Err(CodecError::Unsupported(inner.type_name()));
+    };
+    let (angle, end) = decode_value(rotation, buffer, offset, path)?;
+    let (position, end) = decode_value(origin, buffer, end, path)?;
+    let angle = convert::<real>(&angle, rotation, path)?;
+    let position = convert::<Vector2>(&position, origin, path)?;
+    Ok((
+        Transform2D::from_angle_origin(angle, position).to_variant(),
+        end,
+    ))
+}
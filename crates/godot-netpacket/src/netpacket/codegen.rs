@@ -0,0 +1,138 @@
+//!
+//! Generate codecs in other languages from the schemas of the packets.
+//!
+
+/// Generate GDScript classes
+mod gdscript;
+
+/// Generate C# structs
+mod csharp;
+
+pub use csharp::CSharpGenerator;
+pub use gdscript::GdScriptGenerator;
+
+use super::{Schema, SchemaField, SchemaKind};
+
+/// Error encountered when generating a codec
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum CodegenError {
+    #[error("Type {0} has no equivalent in the generated code")]
+    Unsupported(String),
+
+    #[error("Different types are named {0}")]
+    Conflict(&'static str),
+}
+
+/// Collect the derived types reachable from the schema, dependencies first
+fn collect(schema: &'static Schema, types: &mut Vec<&'static Schema>) -> Result<(), CodegenError> {
+    match schema.kind {
+        SchemaKind::Struct { fields } => collect_fields(fields, types)?,
+        SchemaKind::Enum { variants } => {
+            for variant in variants {
+                collect_fields(variant.fields, types)?;
+            }
+        }
+        SchemaKind::Wrapper { inner, .. } => return collect(inner, types),
+        SchemaKind::Array { item, .. } => return collect(item, types),
+        SchemaKind::Tuple { items } => {
+            for item in items {
+                collect(item, types)?;
+            }
+            return Ok(());
+        }
+        SchemaKind::Builtin => return Ok(()),
+    }
+
+    // keep a single class per name
+    match types.iter().find(|known| known.name == schema.name) {
+        Some(known) if *known != schema => Err(CodegenError::Conflict(schema.name)),
+        Some(_) => Ok(()),
+        None => {
+            types.push(schema);
+            Ok(())
+        }
+    }
+}
+
+/// Collect the derived types used by the fields
+fn collect_fields(
+    fields: &'static [SchemaField],
+    types: &mut Vec<&'static Schema>,
+) -> Result<(), CodegenError> {
+    for field in fields {
+        collect(field.schema, types)?;
+    }
+    Ok(())
+}
+
+/// Error for a type without equivalent
+fn unsupported(schema: &Schema) -> CodegenError {
+    CodegenError::Unsupported(schema.type_name())
+}
+
+/// Convert a name in camel case into upper snake case
+fn to_upper_snake(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    for (index, c) in name.char_indices() {
+        if index > 0 && c.is_uppercase() {
+            snake.push('_');
+        }
+        snake.push(c.to_ascii_uppercase());
+    }
+    snake
+}
+
+/// Text of the generated code, indented as it is written
+struct Code {
+    text: String,
+    unit: &'static str,
+    depth: usize,
+    temps: usize,
+}
+
+impl Code {
+    /// Start the code, indenting each level with the given unit
+    fn new(unit: &'static str) -> Self {
+        Self {
+            text: String::new(),
+            unit,
+            depth: 0,
+            temps: 0,
+        }
+    }
+
+    /// Write an indented line
+    fn line(&mut self, line: impl AsRef<str>) {
+        for _ in 0..self.depth {
+            self.text.push_str(self.unit);
+        }
+        self.text.push_str(line.as_ref());
+        self.text.push('\n');
+    }
+
+    /// Write an empty line
+    fn blank(&mut self) {
+        self.text.push('\n');
+    }
+
+    /// Indent the following lines
+    fn indent(&mut self) {
+        self.depth += 1;
+    }
+
+    /// Stop indenting the following lines
+    fn dedent(&mut self) {
+        self.depth -= 1;
+    }
+
+    /// Name of a new temporary variable, unique within the function
+    fn temp(&mut self) -> String {
+        self.temps += 1;
+        format!("_t{}", self.temps - 1)
+    }
+
+    /// Start a new function
+    fn reset_temps(&mut self) {
+        self.temps = 0;
+    }
+}
@@ -0,0 +1,211 @@
+
+/// <summary>Error raised when a buffer is too small or holds an invalid value</summary>
+public class NetPacketException : Exception
+{
+    public NetPacketException(string message) : base(message) { }
+}
+
+/// <summary>Write the values into a buffer, starting at an offset</summary>
+public struct Writer
+{
+    public byte[] Buffer;
+    public int Offset;
+
+    public Writer(byte[] buffer, int offset)
+    {
+        Buffer = buffer;
+        Offset = offset;
+    }
+
+    private Span<byte> Take(int size)
+    {
+        if (Offset < 0 || Buffer.Length - Offset < size)
+            throw new NetPacketException("The buffer is too small for the packet");
+        var span = Buffer.AsSpan(Offset, size);
+        Offset += size;
+        return span;
+    }
+
+    public void U8(byte value) => Take(1)[0] = value;
+    public void U16(ushort value) => BinaryPrimitives.WriteUInt16LittleEndian(Take(2), value);
+    public void U32(uint value) => BinaryPrimitives.WriteUInt32LittleEndian(Take(4), value);
+    public void U64(ulong value) => BinaryPrimitives.WriteUInt64LittleEndian(Take(8), value);
+    public void S8(sbyte value) => Take(1)[0] = (byte)value;
+    public void S16(short value) => BinaryPrimitives.WriteInt16LittleEndian(Take(2), value);
+    public void S32(int value) => BinaryPrimitives.WriteInt32LittleEndian(Take(4), value);
+    public void S64(long value) => BinaryPrimitives.WriteInt64LittleEndian(Take(8), value);
+    public void F32(float value) => BinaryPrimitives.WriteSingleLittleEndian(Take(4), value);
+    public void F64(double value) => BinaryPrimitives.WriteDoubleLittleEndian(Take(8), value);
+    public void Half(float value) => BinaryPrimitives.WriteHalfLittleEndian(Take(2), (System.Half)value);
+
+    /// <summary>Truncate toward zero and saturate, like a cast in Rust</summary>
+    private static sbyte Truncate(float value) =>
+        float.IsNaN(value) ? (sbyte)0 : (sbyte)Math.Clamp(value, -128f, 127f);
+
+    /// <summary>Number in the range [low, 1.0] scaled and rounded half away from zero, like Rust</summary>
+    private static float Quantize(float value, float low, float factor) =>
+        float.IsNaN(value) ? 0f : MathF.Round(Math.Clamp(value, low, 1f) * factor, MidpointRounding.AwayFromZero);
+
+    /// <summary>Number in the range [-1.0, 1.0] scaled by 127 and truncated</summary>
+    public void Unit(float value) => S8(Truncate(value * 127f));
+
+    /// <summary>Angle in radians scaled by 127 / PI and truncated</summary>
+    public void UnitAngle(float value) => S8(Truncate(value * (127f / MathF.PI)));
+
+    public void UNorm8(float value) => U8((byte)Quantize(value, 0f, 255f));
+    public void UNorm16(float value) => U16((ushort)Quantize(value, 0f, 65535f));
+    public void SNorm16(float value) => S16((short)Quantize(value, -1f, 32767f));
+
+    /// <summary>Color channel in the range [0.0, 1.0] as a level out of the given number of levels</summary>
+    public static int Channel(float value, int levels) => (int)Quantize(value, 0f, levels);
+
+    /// <summary>Floored coordinate of a cell on 32 bits, failing when out of range</summary>
+    public void Cell(float value)
+    {
+        // int.MaxValue rounds up as a float, compare against the next power of two instead
+        if (!(value >= -2147483648f && value < 2147483648f))
+            throw new NetPacketException("The cell is out of range");
+        S32((int)value);
+    }
+
+    /// <summary>Angle in radians as a number of steps out of 2^bits</summary>
+    public void Angle(float value, int bits)
+    {
+        float steps = 1 << bits;
+        var step = MathF.Round(value / MathF.Tau * steps, MidpointRounding.AwayFromZero) % steps;
+        if (step < 0f)
+            step += steps;
+        var level = float.IsNaN(step) ? 0u : (uint)step;
+        if (bits > 8)
+            U16((ushort)level);
+        else
+            U8((byte)level);
+    }
+
+    /// <summary>Unsigned integer stored 7 bits at a time</summary>
+    public void VarInt(ulong value)
+    {
+        while (value >= 0x80)
+        {
+            U8((byte)(value | 0x80));
+            value >>= 7;
+        }
+        U8((byte)value);
+    }
+
+    /// <summary>Length of the text in bytes followed by its UTF-8 content</summary>
+    public void Text(string value)
+    {
+        var bytes = Encoding.UTF8.GetBytes(value);
+        VarInt((ulong)bytes.Length);
+        bytes.CopyTo(Take(bytes.Length));
+    }
+}
+
+/// <summary>Read the values from a buffer, starting at an offset</summary>
+public struct Reader
+{
+    private static readonly UTF8Encoding Utf8 = new UTF8Encoding(false, true);
+
+    public byte[] Buffer;
+    public int Offset;
+
+    public Reader(byte[] buffer, int offset)
+    {
+        Buffer = buffer;
+        Offset = offset;
+    }
+
+    private ReadOnlySpan<byte> Take(int size)
+    {
+        if (size < 0 || Offset < 0 || Buffer.Length - Offset < size)
+            throw new NetPacketException("The buffer ends before the packet");
+        var span = new ReadOnlySpan<byte>(Buffer, Offset, size);
+        Offset += size;
+        return span;
+    }
+
+    public byte U8() => Take(1)[0];
+    public ushort U16() => BinaryPrimitives.ReadUInt16LittleEndian(Take(2));
+    public uint U32() => BinaryPrimitives.ReadUInt32LittleEndian(Take(4));
+    public ulong U64() => BinaryPrimitives.ReadUInt64LittleEndian(Take(8));
+    public sbyte S8() => (sbyte)Take(1)[0];
+    public short S16() => BinaryPrimitives.ReadInt16LittleEndian(Take(2));
+    public int S32() => BinaryPrimitives.ReadInt32LittleEndian(Take(4));
+    public long S64() => BinaryPrimitives.ReadInt64LittleEndian(Take(8));
+    public float F32() => BinaryPrimitives.ReadSingleLittleEndian(Take(4));
+    public double F64() => BinaryPrimitives.ReadDoubleLittleEndian(Take(8));
+    public float Half() => (float)BinaryPrimitives.ReadHalfLittleEndian(Take(2));
+
+    public float Unit() => S8() * (1f / 127f);
+    public float UnitAngle() => S8() * (MathF.PI / 127f);
+    public float UNorm8() => U8() / 255f;
+    public float UNorm16() => U16() / 65535f;
+
+    /// <summary>Both -32768 and -32767 map to -1.0</summary>
+    public float SNorm16() => MathF.Max(S16() / 32767f, -1f);
+
+    /// <summary>Byte holding the given number of flags, the unused bits must be zero</summary>
+    public byte Flags(int count)
+    {
+        byte flags = U8();
+        if ((flags >> count) != 0)
+            throw new NetPacketException("The flags have unused bits set");
+        return flags;
+    }
+
+    /// <summary>Angle in radians in the range ]-PI, PI] from a number of steps out of 2^bits</summary>
+    public float Angle(int bits)
+    {
+        uint steps = 1u << bits;
+        uint step = bits > 8 ? U16() : U8();
+        if ((step >> bits) != 0)
+            throw new NetPacketException("The angle is out of range");
+        var angle = step * MathF.Tau / steps;
+        return angle > MathF.PI ? angle - MathF.Tau : angle;
+    }
+
+    public ulong VarInt()
+    {
+        ulong value = 0;
+        for (var index = 0; index < 10; index++)
+        {
+            var b = U8();
+            value |= (ulong)(b & 0x7F) << (7 * index);
+            if ((b & 0x80) == 0)
+                return value;
+        }
+        throw new NetPacketException("The integer is too long");
+    }
+
+    public string Text()
+    {
+        var size = VarInt();
+        if (size > int.MaxValue)
+            throw new NetPacketException("The text is too long");
+        var bytes = Take((int)size);
+        try
+        {
+            return Utf8.GetString(bytes);
+        }
+        catch (ArgumentException)
+        {
+            throw new NetPacketException("The text is not valid UTF-8");
+        }
+    }
+
+    /// <summary>Prevent the vector to be longer than 1.0</summary>
+    public static Vector4 Limit(Vector4 value)
+    {
+        var lengthSquared = value.LengthSquared();
+        return lengthSquared > 1f ? value / MathF.Sqrt(lengthSquared) : value;
+    }
+
+    /// <summary>Normalize the quaternion, failing if it is null</summary>
+    public static Quaternion Normalized(Quaternion value)
+    {
+        if (Mathf.IsZeroApprox(value.LengthSquared))
+            throw new NetPacketException("The rotation is null");
+        return value.Normalized();
+    }
+}
@@ -0,0 +1,861 @@
+//!
+//! Generate C# structs reading and writing the same bytes as the Rust implementation.
+//!
+
+use super::{Code, CodegenError, collect, unsupported};
+use crate::netpacket::{PacketSchema, Schema, SchemaField, SchemaKind, SchemaVariant};
+
+/// Writer and reader used by the generated structs
+const RUNTIME: &str = include_str!("csharp.cs");
+
+/// Generate a C# file holding a struct for each packet type, along with the writer and reader they use.
+/// The structs use the types of the GodotSharp assembly and encode the same bytes as the Rust implementation,
+/// assuming the engine stores vectors using single precision floats.
+#[derive(Debug, Default, Clone)]
+pub struct CSharpGenerator {
+    namespace: Option<String>,
+    schemas: Vec<&'static Schema>,
+}
+
+impl CSharpGenerator {
+    /// Create a generator without any packet type
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare the structs in the given namespace
+    pub fn namespace(&mut self, name: &str) -> &mut Self {
+        self.namespace = Some(name.to_owned());
+        self
+    }
+
+    /// Generate a struct for the packet type and for the types of its fields
+    pub fn add<T: PacketSchema + ?Sized>(&mut self) -> &mut Self {
+        self.schemas.push(T::SCHEMA);
+        self
+    }
+
+    /// Generate the text of the file
+    pub fn generate(&self) -> Result<String, CodegenError> {
+        let mut types = Vec::new();
+        for schema in &self.schemas {
+            collect(schema, &mut types)?;
+        }
+
+        let mut code = Code::new("    ");
+        code.line("// Generated by godot-netpacket from the packet schemas, do not edit.");
+        code.line("using System;");
+        code.line("using System.Buffers.Binary;");
+        code.line("using System.Text;");
+        code.line("using Godot;");
+        if let Some(name) = &self.namespace {
+            code.blank();
+            code.line(format!("namespace {name};"));
+        }
+        code.text.push_str(RUNTIME);
+
+        for schema in types {
+            code.blank();
+            match schema.kind {
+                SchemaKind::Struct { fields } => write_struct(&mut code, schema, fields)?,
+                SchemaKind::Enum { variants } => write_enum(&mut code, schema, variants)?,
+                _ => return Err(unsupported(schema)),
+            }
+        }
+
+        Ok(code.text)
+    }
+}
+
+/// Name of the field as a public C# member
+fn member(name: &str) -> String {
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        return format!("Item{name}");
+    }
+
+    // convert from snake case into pascal case
+    let mut pascal = String::with_capacity(name.len());
+    let mut upper = true;
+    for c in name.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            pascal.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            pascal.push(c);
+        }
+    }
+    pascal
+}
+
+/// Open a block
+fn open(code: &mut Code, line: impl AsRef<str>) {
+    code.line(line);
+    code.line("{");
+    code.indent();
+}
+
+/// Close a block
+fn close(code: &mut Code) {
+    code.dedent();
+    code.line("}");
+}
+
+/// Write a struct holding the fields
+fn write_struct(
+    code: &mut Code,
+    schema: &Schema,
+    fields: &[SchemaField],
+) -> Result<(), CodegenError> {
+    let name = schema.name;
+    open(code, format!("public struct {name}"));
+    write_size(code, schema);
+    write_members(code, fields)?;
+    write_bytes(code, name);
+    write_functions(code, name, fields)?;
+    close(code);
+    Ok(())
+}
+
+/// Write a struct holding the variant, and the fields of each variant in a nested struct
+fn write_enum(
+    code: &mut Code,
+    schema: &Schema,
+    variants: &[SchemaVariant],
+) -> Result<(), CodegenError> {
+    let name = schema.name;
+    if variants.is_empty() {
+        return Err(unsupported(schema));
+    }
+    let with_fields = || variants.iter().filter(|variant| !variant.fields.is_empty());
+
+    open(code, format!("public struct {name}"));
+    write_size(code, schema);
+    let tags: Vec<String> = variants
+        .iter()
+        .map(|variant| format!("{} = {}", variant.name, variant.tag))
+        .collect();
+    code.line(format!("public enum Kind : byte {{ {} }}", tags.join(", ")));
+
+    for variant in with_fields() {
+        code.blank();
+        let fields_name = format!("{}Fields", variant.name);
+        open(code, format!("public struct {fields_name}"));
+        write_members(code, variant.fields)?;
+        write_functions(code, &fields_name, variant.fields)?;
+        close(code);
+    }
+
+    code.blank();
+    code.line("public Kind Variant;");
+    for variant in with_fields() {
+        code.line(format!("public {0}Fields {0};", variant.name));
+    }
+    write_bytes(code, name);
+
+    // the tag is followed by the fields of the variant
+    code.blank();
+    open(code, "public void Write(ref Writer w)");
+    code.line("w.U8((byte)Variant);");
+    if with_fields().next().is_some() {
+        open(code, "switch (Variant)");
+        for variant in with_fields() {
+            code.line(format!("case Kind.{}:", variant.name));
+            code.indent();
+            code.line(format!("{}.Write(ref w);", variant.name));
+            code.line("break;");
+            code.dedent();
+        }
+        close(code);
+    }
+    close(code);
+
+    code.blank();
+    open(code, format!("public static {name} Read(ref Reader r)"));
+    code.line(format!("var value = new {name}();"));
+    open(code, "switch (r.U8())");
+    for variant in variants {
+        code.line(format!("case {}:", variant.tag));
+        code.indent();
+        code.line(format!("value.Variant = Kind.{};", variant.name));
+        if !variant.fields.is_empty() {
+            code.line(format!("value.{0} = {0}Fields.Read(ref r);", variant.name));
+        }
+        code.line("break;");
+        code.dedent();
+    }
+    code.line("default:");
+    code.indent();
+    code.line(format!(
+        "throw new NetPacketException(\"Unknown variant of {name}\");"
+    ));
+    code.dedent();
+    close(code);
+    code.line("return value;");
+    close(code);
+
+    close(code);
+    Ok(())
+}
+
+/// Write the size of the type when it is constant
+fn write_size(code: &mut Code, schema: &Schema) {
+    if let Some(size) = schema.size {
+        code.line(format!("public const int SerializedSize = {size};"));
+        code.blank();
+    }
+}
+
+/// Write the public members holding the fields
+fn write_members(code: &mut Code, fields: &[SchemaField]) -> Result<(), CodegenError> {
+    for field in fields {
+        code.line(format!(
+            "public {} {};",
+            cs_type(field.schema)?,
+            member(field.name)
+        ));
+    }
+    Ok(())
+}
+
+/// Write the functions converting a packet from and to a buffer at an offset
+fn write_bytes(code: &mut Code, name: &str) {
+    code.blank();
+    code.line(
+        "/// <summary>Write the packet at the offset, returning the offset following it</summary>",
+    );
+    open(code, "public int Serialize(byte[] buf, int offset)");
+    code.line("var w = new Writer(buf, offset);");
+    code.line("Write(ref w);");
+    code.line("return w.Offset;");
+    close(code);
+
+    code.blank();
+    code.line(
+        "/// <summary>Read the packet at the offset, returning the offset following it</summary>",
+    );
+    open(
+        code,
+        format!("public static int Deserialize(byte[] buf, int offset, out {name} value)"),
+    );
+    code.line("var r = new Reader(buf, offset);");
+    code.line("value = Read(ref r);");
+    code.line("return r.Offset;");
+    close(code);
+}
+
+/// Write the functions writing and reading the fields
+fn write_functions(
+    code: &mut Code,
+    name: &str,
+    fields: &[SchemaField],
+) -> Result<(), CodegenError> {
+    // write the flags followed by the values
+    code.blank();
+    code.reset_temps();
+    open(code, "public void Write(ref Writer w)");
+    write_fields(code, fields)?;
+    close(code);
+
+    code.blank();
+    code.reset_temps();
+    open(code, format!("public static {name} Read(ref Reader r)"));
+    code.line(format!("var value = new {name}();"));
+    read_fields(code, fields)?;
+    code.line("return value;");
+    close(code);
+    Ok(())
+}
+
+/// Write the bytes holding the flags, followed by the values
+fn write_fields(code: &mut Code, fields: &[SchemaField]) -> Result<(), CodegenError> {
+    let flags: Vec<&SchemaField> = fields.iter().filter(|field| field.bit.is_some()).collect();
+    for chunk in flags.chunks(8) {
+        let bits: Vec<String> = chunk
+            .iter()
+            .map(|field| {
+                let mask = 1u8 << field.bit.unwrap_or_default();
+                format!("({} ? {mask} : 0)", member(field.name))
+            })
+            .collect();
+        code.line(format!("w.U8((byte)({}));", bits.join(" | ")));
+    }
+
+    for field in fields.iter().filter(|field| field.bit.is_none()) {
+        write_value(code, field.schema, &member(field.name))?;
+    }
+    Ok(())
+}
+
+/// Read the bytes holding the flags, followed by the values
+fn read_fields(code: &mut Code, fields: &[SchemaField]) -> Result<(), CodegenError> {
+    let flags: Vec<&SchemaField> = fields.iter().filter(|field| field.bit.is_some()).collect();
+    for chunk in flags.chunks(8) {
+        let byte = bind(code, format!("r.Flags({})", chunk.len()));
+        for field in chunk {
+            let mask = 1u8 << field.bit.unwrap_or_default();
+            code.line(format!(
+                "value.{} = ({byte} & {mask}) != 0;",
+                member(field.name)
+            ));
+        }
+    }
+
+    for field in fields.iter().filter(|field| field.bit.is_none()) {
+        let value = read_value(code, field.schema)?;
+        code.line(format!("value.{} = {value};", member(field.name)));
+    }
+    Ok(())
+}
+
+/// Type of the value in C#
+fn cs_type(schema: &Schema) -> Result<String, CodegenError> {
+    match schema.kind {
+        SchemaKind::Builtin if schema.angle_bits().is_some() => Ok("float".into()),
+        SchemaKind::Builtin => {
+            let name = match schema.name {
+                "u8" => "byte",
+                "u16" => "ushort",
+                "u32" | "NetworkId" => "uint",
+                "u64" => "ulong",
+                "i8" => "sbyte",
+                "i16" => "short",
+                "i32" => "int",
+                "i64" => "long",
+                "f32" => "float",
+                "f64" => "double",
+                "bool" => "bool",
+                "GString" => "string",
+                "Vector2i" => "Vector2I",
+                "Vector3i" => "Vector3I",
+                "Vector4i" => "Vector4I",
+                "Rect2i" => "Rect2I",
+                "Key" | "MouseButton" | "JoyButton" | "JoyAxis" | "KeyModifierMask"
+                | "MouseButtonMask" | "StringName" | "NodePath" | "Vector2" | "Vector3"
+                | "Vector4" | "Quaternion" | "Basis" | "Transform2D" | "Transform3D" | "Plane"
+                | "Color" | "Rect2" | "Aabb" => schema.name,
+                _ => return Err(unsupported(schema)),
+            };
+            Ok(name.into())
+        }
+        SchemaKind::Wrapper { inner, .. } => match schema.name {
+            "Rigid2D" => Ok("Transform2D".into()),
+            _ => cs_type(inner),
+        },
+        SchemaKind::Struct { .. } | SchemaKind::Enum { .. } => Ok(schema.name.into()),
+        SchemaKind::Array { item, .. } => Ok(format!("{}[]", cs_type(item)?)),
+        SchemaKind::Tuple { items } => {
+            let items = items
+                .iter()
+                .map(|item| cs_type(item))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(format!("({})", items.join(", ")))
+        }
+    }
+}
+
+/// Method of the writer and the reader for an integer, and the integer type of engine enums
+fn int_method(name: &str) -> Option<(&'static str, Option<&'static str>)> {
+    match name {
+        "u8" => Some(("U8", None)),
+        "u16" => Some(("U16", None)),
+        "MouseButtonMask" => Some(("U16", Some("ushort"))),
+        "u32" | "NetworkId" => Some(("U32", None)),
+        "KeyModifierMask" => Some(("U32", Some("uint"))),
+        "u64" => Some(("U64", None)),
+        "i8" => Some(("S8", None)),
+        "MouseButton" | "JoyAxis" => Some(("S8", Some("sbyte"))),
+        "i16" => Some(("S16", None)),
+        "JoyButton" => Some(("S16", Some("short"))),
+        "i32" => Some(("S32", None)),
+        "Key" => Some(("S32", Some("int"))),
+        "i64" => Some(("S64", None)),
+        _ => None,
+    }
+}
+
+/// Components of a vector, quaternion or color
+fn components(name: &str) -> &'static [&'static str] {
+    match name {
+        "Vector2" | "Vector2I" => &["X", "Y"],
+        "Vector3" | "Vector3I" => &["X", "Y", "Z"],
+        "Vector4" | "Vector4I" | "Quaternion" => &["X", "Y", "Z", "W"],
+        "Color" => &["R", "G", "B", "A"],
+        _ => &[],
+    }
+}
+
+/// Name of an integer vector in C#
+fn int_vector(name: &str) -> String {
+    format!("{}I", name.trim_end_matches('i'))
+}
+
+/// Write each component of the value using the method of the writer
+fn write_each(code: &mut Code, method: &str, value: &str, name: &str) {
+    for component in components(name) {
+        code.line(format!("w.{method}({value}.{component});"));
+    }
+}
+
+/// Read each component of the value using the method of the reader
+fn read_each(method: &str, name: &str) -> String {
+    let reads = vec![format!("r.{method}()"); components(name).len()];
+    format!("new {name}({})", reads.join(", "))
+}
+
+/// Write the rows of a basis
+fn write_rows(code: &mut Code, method: &str, value: &str) {
+    for row in ["Row0", "Row1", "Row2"] {
+        write_each(code, method, &format!("{value}.{row}"), "Vector3");
+    }
+}
+
+/// Read the rows of a basis
+fn read_rows(code: &mut Code, method: &str) -> String {
+    let rows: Vec<String> = (0..3)
+        .map(|_| bind(code, read_each(method, "Vector3")))
+        .collect();
+    format!(
+        "new Basis {{ Row0 = {}, Row1 = {}, Row2 = {} }}",
+        rows[0], rows[1], rows[2]
+    )
+}
+
+/// Store the value in a temporary variable to keep the reads in order
+fn bind(code: &mut Code, value: String) -> String {
+    let temp = code.temp();
+    code.line(format!("var {temp} = {value};"));
+    temp
+}
+
+/// Write the statements serializing the value
+fn write_value(code: &mut Code, schema: &Schema, value: &str) -> Result<(), CodegenError> {
+    match schema.kind {
+        SchemaKind::Builtin => write_builtin(code, schema, value),
+        SchemaKind::Wrapper { inner, param } => write_wrapper(code, schema, inner, param, value),
+        SchemaKind::Struct { .. } | SchemaKind::Enum { .. } => {
+            code.line(format!("{value}.Write(ref w);"));
+            Ok(())
+        }
+        SchemaKind::Array { item, len } => {
+            let index = code.temp();
+            open(
+                code,
+                format!("for (var {index} = 0; {index} < {len}; {index}++)"),
+            );
+            write_value(code, item, &format!("{value}[{index}]"))?;
+            close(code);
+            Ok(())
+        }
+        SchemaKind::Tuple { items } => {
+            for (index, item) in items.iter().enumerate() {
+                write_value(code, item, &format!("{value}.Item{}", index + 1))?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Write the statements serializing a number or a godot type
+fn write_builtin(code: &mut Code, schema: &Schema, value: &str) -> Result<(), CodegenError> {
+    let name = schema.name;
+    if let Some(bits) = schema.angle_bits() {
+        code.line(format!("w.Angle({value}, {bits});"));
+        return Ok(());
+    }
+    if let Some((method, cast)) = int_method(name) {
+        match cast {
+            Some(cast) => code.line(format!("w.{method}(({cast}){value});")),
+            None => code.line(format!("w.{method}({value});")),
+        }
+        return Ok(());
+    }
+
+    match name {
+        "f32" => code.line(format!("w.F32({value});")),
+        "f64" => code.line(format!("w.F64({value});")),
+        "bool" => code.line(format!("w.U8((byte)({value} ? 1 : 0));")),
+        "Vector2" | "Vector3" | "Vector4" | "Quaternion" => write_each(code, "F32", value, name),
+        "Vector2i" | "Vector3i" | "Vector4i" => write_each(code, "S32", value, &int_vector(name)),
+        "Basis" => write_rows(code, "F32", value),
+        "Transform2D" => {
+            write_each(code, "F32", &format!("{value}.X"), "Vector2");
+            write_each(code, "F32", &format!("{value}.Y"), "Vector2");
+            write_each(code, "F32", &format!("{value}.Origin"), "Vector2");
+        }
+        "Transform3D" => {
+            write_rows(code, "F32", &format!("{value}.Basis"));
+            write_each(code, "F32", &format!("{value}.Origin"), "Vector3");
+        }
+        "Plane" => {
+            write_each(code, "F32", &format!("{value}.Normal"), "Vector3");
+            code.line(format!("w.F32({value}.D);"));
+        }
+        "Color" => {
+            for channel in components(name) {
+                code.line(format!(
+                    "w.U8((byte)Writer.Channel({value}.{channel}, 255));"
+                ));
+            }
+        }
+        "Rect2" | "Rect2i" | "Aabb" => {
+            let (method, vector) = match name {
+                "Rect2" => ("F32", "Vector2"),
+                "Rect2i" => ("S32", "Vector2I"),
+                _ => ("F32", "Vector3"),
+            };
+            write_each(code, method, &format!("{value}.Position"), vector);
+            write_each(code, method, &format!("{value}.Size"), vector);
+        }
+        "GString" => code.line(format!("w.Text({value});")),
+        "StringName" | "NodePath" => code.line(format!("w.Text({value}.ToString());")),
+        _ => return Err(unsupported(schema)),
+    }
+    Ok(())
+}
+
+/// Write the statements serializing a wrapped value
+fn write_wrapper(
+    code: &mut Code,
+    schema: &Schema,
+    inner: &Schema,
+    param: Option<u32>,
+    value: &str,
+) -> Result<(), CodegenError> {
+    // relative positions are stored as halfs
+    let wrapper = match schema.name {
+        "Relative" => "Half",
+        wrapper => wrapper,
+    };
+
+    match (wrapper, inner.name) {
+        ("Unit", "f32") => code.line(format!("w.Unit({value});")),
+        ("Unit", "Vector2" | "Vector3" | "Vector4" | "Quaternion") => {
+            write_each(code, "Unit", value, inner.name)
+        }
+        ("Unit", "Basis") => {
+            let rotation = bind(code, format!("{value}.GetRotationQuaternion()"));
+            write_each(code, "Unit", &rotation, "Quaternion");
+        }
+        ("Unit", "Transform2D") => {
+            code.line(format!("w.UnitAngle({value}.Rotation);"));
+            write_each(code, "F32", &format!("{value}.Origin"), "Vector2");
+        }
+        ("Unit", "Transform3D") => {
+            let rotation = bind(code, format!("{value}.Basis.GetRotationQuaternion()"));
+            write_each(code, "Unit", &rotation, "Quaternion");
+            write_each(code, "F32", &format!("{value}.Origin"), "Vector3");
+        }
+        ("Unit", "Plane") => {
+            write_each(code, "Unit", &format!("{value}.Normal"), "Vector3");
+            code.line(format!("w.F32({value}.D);"));
+        }
+        ("Half", "f32") => code.line(format!("w.Half({value});")),
+        ("Half", "Vector2" | "Vector3" | "Vector4" | "Quaternion" | "Color") => {
+            write_each(code, "Half", value, inner.name)
+        }
+        ("Half", "Basis") => write_rows(code, "Half", value),
+        ("Half", "Transform2D") => {
+            write_each(code, "Half", &format!("{value}.X"), "Vector2");
+            write_each(code, "Half", &format!("{value}.Y"), "Vector2");
+            write_each(code, "F32", &format!("{value}.Origin"), "Vector2");
+        }
+        ("Half", "Transform3D") => {
+            write_rows(code, "Half", &format!("{value}.Basis"));
+            write_each(code, "F32", &format!("{value}.Origin"), "Vector3");
+        }
+        ("Half", "Plane") => {
+            write_each(code, "Half", &format!("{value}.Normal"), "Vector3");
+            code.line(format!("w.F32({value}.D);"));
+        }
+        ("Half", "Rect2" | "Aabb") => {
+            let vector = if inner.name == "Rect2" {
+                "Vector2"
+            } else {
+                "Vector3"
+            };
+            write_each(code, "F32", &format!("{value}.Position"), vector);
+            write_each(code, "Half", &format!("{value}.Size"), vector);
+        }
+        ("Full", "Color") => write_each(code, "F32", value, "Color"),
+        ("Rgb", "Color") => {
+            for channel in ["R", "G", "B"] {
+                code.line(format!(
+                    "w.U8((byte)Writer.Channel({value}.{channel}, 255));"
+                ));
+            }
+        }
+        ("Rgb565", "Color") => code.line(format!(
+            "w.U16((ushort)(Writer.Channel({value}.R, 31) << 11 | Writer.Channel({value}.G, 63) << 5 | Writer.Channel({value}.B, 31)));"
+        )),
+        ("Rgba4444", "Color") => code.line(format!(
+            "w.U16((ushort)(Writer.Channel({value}.R, 15) << 12 | Writer.Channel({value}.G, 15) << 8 | Writer.Channel({value}.B, 15) << 4 | Writer.Channel({value}.A, 15)));"
+        )),
+        ("UNorm8" | "UNorm16" | "SNorm16", "f32") => code.line(format!("w.{wrapper}({value});")),
+        ("UNorm8" | "UNorm16", "Vector2" | "Vector3" | "Vector4")
+        | ("SNorm16", "Vector2" | "Vector3" | "Vector4" | "Quaternion") => {
+            write_each(code, wrapper, value, inner.name)
+        }
+        ("Trs", "Basis") => write_trs_basis(code, value),
+        ("Trs", "Transform2D") => {
+            code.line(format!("w.F32({value}.Rotation);"));
+            let scale = bind(code, format!("{value}.Scale"));
+            write_each(code, "Half", &scale, "Vector2");
+            write_each(code, "F32", &format!("{value}.Origin"), "Vector2");
+        }
+        ("Trs", "Transform3D") => {
+            write_trs_basis(code, &format!("{value}.Basis"));
+            write_each(code, "F32", &format!("{value}.Origin"), "Vector3");
+        }
+        ("Flags", _) => write_value(code, inner, value)?,
+        ("Cell", "Vector2" | "Vector3") => {
+            let size = param.unwrap_or(1);
+            let position = bind(code, format!("{value} / {size}f"));
+            let cell = bind(code, format!("{position}.Floor()"));
+            let local = bind(code, format!("{position} - {cell}"));
+            for component in components(inner.name) {
+                code.line(format!("w.Cell({cell}.{component});"));
+            }
+            write_each(code, "UNorm16", &local, inner.name);
+        }
+        ("Rigid2D", _) => match inner.kind {
+            SchemaKind::Tuple {
+                items: [rotation, origin],
+            } => {
+                write_value(code, rotation, &format!("{value}.Rotation"))?;
+                write_value(code, origin, &format!("{value}.Origin"))?;
+            }
+            _ => return Err(unsupported(schema)),
+        },
+        _ => return Err(unsupported(schema)),
+    }
+    Ok(())
+}
+
+/// Write a basis as a rotation and a scale, a negative scale is stored on every axis
+fn write_trs_basis(code: &mut Code, value: &str) {
+    let scale = bind(code, format!("{value}.Scale"));
+    let rotation = bind(
+        code,
+        format!(
+            "({value}.Determinant() < 0f ? {value}.Scaled(-Vector3.One) : {value}).GetRotationQuaternion()"
+        ),
+    );
+    write_each(code, "Half", &rotation, "Quaternion");
+    write_each(code, "Half", &scale, "Vector3");
+}
+
+/// Write the statements deserializing the value, and return the expression holding it
+fn read_value(code: &mut Code, schema: &Schema) -> Result<String, CodegenError> {
+    match schema.kind {
+        SchemaKind::Builtin => read_builtin(code, schema),
+        SchemaKind::Wrapper { inner, param } => read_wrapper(code, schema, inner, param),
+        SchemaKind::Struct { .. } | SchemaKind::Enum { .. } => {
+            Ok(format!("{}.Read(ref r)", schema.name))
+        }
+        SchemaKind::Array { item, len } => {
+            let array = bind(code, format!("new {}[{len}]", cs_type(item)?));
+            let index = code.temp();
+            open(
+                code,
+                format!("for (var {index} = 0; {index} < {len}; {index}++)"),
+            );
+            let value = read_value(code, item)?;
+            code.line(format!("{array}[{index}] = {value};"));
+            close(code);
+            Ok(array)
+        }
+        SchemaKind::Tuple { items } => {
+            let mut values = Vec::with_capacity(items.len());
+            for item in items {
+                let value = read_value(code, item)?;
+                values.push(bind(code, value));
+            }
+            Ok(format!("({})", values.join(", ")))
+        }
+    }
+}
+
+/// Write the statements deserializing a number or a godot type
+fn read_builtin(code: &mut Code, schema: &Schema) -> Result<String, CodegenError> {
+    let name = schema.name;
+    if let Some(bits) = schema.angle_bits() {
+        return Ok(format!("r.Angle({bits})"));
+    }
+    if let Some((method, cast)) = int_method(name) {
+        return Ok(match cast {
+            Some(_) => format!("({name})r.{method}()"),
+            None => format!("r.{method}()"),
+        });
+    }
+
+    let value = match name {
+        "f32" => "r.F32()".into(),
+        "f64" => "r.F64()".into(),
+        "bool" => "r.U8() != 0".into(),
+        "Vector2" | "Vector3" | "Vector4" | "Quaternion" => read_each("F32", name),
+        "Vector2i" | "Vector3i" | "Vector4i" => read_each("S32", &int_vector(name)),
+        "Basis" => read_rows(code, "F32"),
+        "Transform2D" => {
+            let vector = read_each("F32", "Vector2");
+            format!("new Transform2D({vector}, {vector}, {vector})")
+        }
+        "Transform3D" => {
+            let basis = read_rows(code, "F32");
+            let basis = bind(code, basis);
+            format!("new Transform3D({basis}, {})", read_each("F32", "Vector3"))
+        }
+        "Plane" => format!("new Plane({}, r.F32())", read_each("F32", "Vector3")),
+        "Color" => read_each("UNorm8", "Color"),
+        "Rect2" => {
+            let vector = read_each("F32", "Vector2");
+            format!("new Rect2({vector}, {vector})")
+        }
+        "Rect2i" => {
+            let vector = read_each("S32", "Vector2I");
+            format!("new Rect2I({vector}, {vector})")
+        }
+        "Aabb" => {
+            let vector = read_each("F32", "Vector3");
+            format!("new Aabb({vector}, {vector})")
+        }
+        "GString" => "r.Text()".into(),
+        "StringName" | "NodePath" => format!("new {name}(r.Text())"),
+        _ => return Err(unsupported(schema)),
+    };
+    Ok(value)
+}
+
+/// Write the statements deserializing a wrapped value
+fn read_wrapper(
+    code: &mut Code,
+    schema: &Schema,
+    inner: &Schema,
+    param: Option<u32>,
+) -> Result<String, CodegenError> {
+    // relative positions are stored as halfs
+    let wrapper = match schema.name {
+        "Relative" => "Half",
+        wrapper => wrapper,
+    };
+
+    let value = match (wrapper, inner.name) {
+        ("Unit", "f32") => "r.Unit()".into(),
+        ("Unit", "Vector2" | "Vector3") => {
+            format!("{}.LimitLength(1f)", read_each("Unit", inner.name))
+        }
+        ("Unit", "Vector4") => format!("Reader.Limit({})", read_each("Unit", "Vector4")),
+        ("Unit", "Quaternion") => {
+            format!("Reader.Normalized({})", read_each("Unit", "Quaternion"))
+        }
+        ("Unit", "Basis") => {
+            let rotation = format!("Reader.Normalized({})", read_each("Unit", "Quaternion"));
+            format!("new Basis({})", bind(code, rotation))
+        }
+        ("Unit", "Transform2D") => format!(
+            "new Transform2D(r.UnitAngle(), {})",
+            read_each("F32", "Vector2")
+        ),
+        ("Unit", "Transform3D") => {
+            let rotation = format!("Reader.Normalized({})", read_each("Unit", "Quaternion"));
+            let rotation = bind(code, rotation);
+            format!(
+                "new Transform3D(new Basis({rotation}), {})",
+                read_each("F32", "Vector3")
+            )
+        }
+        ("Unit", "Plane") => {
+            let normal = format!("{}.LimitLength(1f)", read_each("Unit", "Vector3"));
+            format!("new Plane({}, r.F32())", bind(code, normal))
+        }
+        ("Half", "f32") => "r.Half()".into(),
+        ("Half", "Vector2" | "Vector3" | "Vector4" | "Color") => read_each("Half", inner.name),
+        ("Half", "Quaternion") => {
+            format!("Reader.Normalized({})", read_each("Half", "Quaternion"))
+        }
+        ("Half", "Basis") => read_rows(code, "Half"),
+        ("Half", "Transform2D") => {
+            let axis = read_each("Half", "Vector2");
+            format!(
+                "new Transform2D({axis}, {axis}, {})",
+                read_each("F32", "Vector2")
+            )
+        }
+        ("Half", "Transform3D") => {
+            let basis = read_rows(code, "Half");
+            let basis = bind(code, basis);
+            format!("new Transform3D({basis}, {})", read_each("F32", "Vector3"))
+        }
+        ("Half", "Plane") => format!("new Plane({}, r.F32())", read_each("Half", "Vector3")),
+        ("Half", "Rect2") => format!(
+            "new Rect2({}, {})",
+            read_each("F32", "Vector2"),
+            read_each("Half", "Vector2")
+        ),
+        ("Half", "Aabb") => format!(
+            "new Aabb({}, {})",
+            read_each("F32", "Vector3"),
+            read_each("Half", "Vector3")
+        ),
+        ("Full", "Color") => read_each("F32", "Color"),
+        ("Rgb", "Color") => "new Color(r.UNorm8(), r.UNorm8(), r.UNorm8(), 1f)".into(),
+        ("Rgb565", "Color") => {
+            let packed = bind(code, "r.U16()".into());
+            format!(
+                "new Color(({packed} >> 11) / 31f, (({packed} >> 5) & 63) / 63f, ({packed} & 31) / 31f, 1f)"
+            )
+        }
+        ("Rgba4444", "Color") => {
+            let packed = bind(code, "r.U16()".into());
+            format!(
+                "new Color(({packed} >> 12) / 15f, (({packed} >> 8) & 15) / 15f, (({packed} >> 4) & 15) / 15f, ({packed} & 15) / 15f)"
+            )
+        }
+        ("UNorm8" | "UNorm16" | "SNorm16", "f32") => format!("r.{wrapper}()"),
+        ("UNorm8" | "UNorm16", "Vector2" | "Vector3" | "Vector4")
+        | ("SNorm16", "Vector2" | "Vector3" | "Vector4" | "Quaternion") => {
+            read_each(wrapper, inner.name)
+        }
+        ("Trs", "Basis") => read_trs_basis(code),
+        ("Trs", "Transform2D") => format!(
+            "new Transform2D(r.F32(), {}, 0f, {})",
+            read_each("Half", "Vector2"),
+            read_each("F32", "Vector2")
+        ),
+        ("Trs", "Transform3D") => {
+            let basis = read_trs_basis(code);
+            let basis = bind(code, basis);
+            format!("new Transform3D({basis}, {})", read_each("F32", "Vector3"))
+        }
+        ("Flags", _) => read_value(code, inner)?,
+        ("Cell", "Vector2" | "Vector3") => format!(
+            "({} + {}) * {}f",
+            read_each("S32", inner.name),
+            read_each("UNorm16", inner.name),
+            param.unwrap_or(1)
+        ),
+        ("Rigid2D", _) => match inner.kind {
+            SchemaKind::Tuple {
+                items: [rotation, origin],
+            } => {
+                let rotation = read_value(code, rotation)?;
+                let rotation = bind(code, rotation);
+                let origin = read_value(code, origin)?;
+                let origin = bind(code, origin);
+                format!("new Transform2D({rotation}, {origin})")
+            }
+            _ => return Err(unsupported(schema)),
+        },
+        _ => return Err(unsupported(schema)),
+    };
+    Ok(value)
+}
+
+/// Read a basis stored as a rotation and a scale
+fn read_trs_basis(code: &mut Code) -> String {
+    let rotation = format!("Reader.Normalized({})", read_each("Half", "Quaternion"));
+    let rotation = bind(code, rotation);
+    let scale = bind(code, read_each("Half", "Vector3"));
+    format!("new Basis({rotation}) * Basis.FromScale({scale})")
+}
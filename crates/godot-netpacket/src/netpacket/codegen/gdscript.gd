@@ -0,0 +1,233 @@
+
+
+## Write the values into a buffer growing as needed
+class Writer:
+	var buffer := PackedByteArray()
+	var offset := 0
+	var failed := false
+
+	## Round to a single precision float like the Rust implementation
+	static func _f32(value: float) -> float:
+		return Vector2(value, 0.0).x
+
+	func _take(size: int) -> int:
+		var at := offset
+		offset += size
+		if buffer.size() < offset:
+			buffer.resize(offset)
+		return at
+
+	func u8(value: int) -> void:
+		buffer.encode_u8(_take(1), value)
+
+	func u16(value: int) -> void:
+		buffer.encode_u16(_take(2), value)
+
+	func u32(value: int) -> void:
+		buffer.encode_u32(_take(4), value)
+
+	func u64(value: int) -> void:
+		buffer.encode_u64(_take(8), value)
+
+	func s8(value: int) -> void:
+		buffer.encode_s8(_take(1), value)
+
+	func s16(value: int) -> void:
+		buffer.encode_s16(_take(2), value)
+
+	func s32(value: int) -> void:
+		buffer.encode_s32(_take(4), value)
+
+	func s64(value: int) -> void:
+		buffer.encode_s64(_take(8), value)
+
+	func f32(value: float) -> void:
+		buffer.encode_float(_take(4), value)
+
+	func f64(value: float) -> void:
+		buffer.encode_double(_take(8), value)
+
+	func half(value: float) -> void:
+		buffer.encode_half(_take(2), value)
+
+	## Number in the range [-1.0, 1.0] scaled by 127 and truncated
+	func unit(value: float) -> void:
+		s8(int(clampf(_f32(_f32(value) * 127.0), -128.0, 127.0)))
+
+	## Angle in radians scaled by 127 / PI and truncated
+	func unit_angle(value: float) -> void:
+		s8(int(clampf(_f32(_f32(value) * _f32(127.0 / _f32(PI))), -128.0, 127.0)))
+
+	## Number in the range [low, 1.0] scaled and rounded
+	func _quantize(value: float, low: float, factor: float) -> int:
+		return int(roundf(_f32(clampf(_f32(value), low, 1.0) * factor)))
+
+	func unorm8(value: float) -> void:
+		u8(_quantize(value, 0.0, 255.0))
+
+	func unorm16(value: float) -> void:
+		u16(_quantize(value, 0.0, 65535.0))
+
+	func snorm16(value: float) -> void:
+		s16(_quantize(value, -1.0, 32767.0))
+
+	## Color channel in the range [0.0, 1.0] as a level out of the given number of levels
+	func channel(value: float, levels: int) -> int:
+		return _quantize(value, 0.0, levels)
+
+	## Floored coordinate of a cell on 32 bits, failing when out of range
+	func cell(value: float) -> void:
+		if not (value >= -2147483648.0 and value <= 2147483647.0):
+			failed = true
+			value = 0.0
+		s32(int(value))
+
+	## Angle in radians as a number of steps out of 2^bits
+	func angle(value: float, bits: int) -> void:
+		var steps := 1 << bits
+		var step := posmod(int(roundf(_f32(_f32(_f32(value) / _f32(TAU)) * steps))), steps)
+		if bits > 8:
+			u16(step)
+		else:
+			u8(step)
+
+	## Unsigned integer stored 7 bits at a time
+	func varint(value: int) -> void:
+		while value < 0 or value >= 0x80:
+			u8((value & 0x7F) | 0x80)
+			value = (value >> 7) & 0x01FFFFFFFFFFFFFF
+		u8(value)
+
+	## Length of the text in bytes followed by its UTF-8 content
+	func text(value: String) -> void:
+		var bytes := value.to_utf8_buffer()
+		varint(bytes.size())
+		var at := _take(bytes.size())
+		for index in bytes.size():
+			buffer[at + index] = bytes[index]
+
+
+## Read the values from a buffer, failing once the end of the buffer is reached
+class Reader:
+	var buffer: PackedByteArray
+	var offset := 0
+	var failed := false
+
+	func _init(bytes: PackedByteArray, start: int = 0) -> void:
+		buffer = bytes
+		offset = start
+
+	## Round to a single precision float like the Rust implementation
+	static func _f32(value: float) -> float:
+		return Vector2(value, 0.0).x
+
+	func _take(size: int) -> int:
+		if failed or size < 0 or offset + size > buffer.size():
+			failed = true
+			return -1
+		var at := offset
+		offset += size
+		return at
+
+	func u8() -> int:
+		var at := _take(1)
+		return 0 if at < 0 else buffer.decode_u8(at)
+
+	func u16() -> int:
+		var at := _take(2)
+		return 0 if at < 0 else buffer.decode_u16(at)
+
+	func u32() -> int:
+		var at := _take(4)
+		return 0 if at < 0 else buffer.decode_u32(at)
+
+	func u64() -> int:
+		var at := _take(8)
+		return 0 if at < 0 else buffer.decode_u64(at)
+
+	func s8() -> int:
+		var at := _take(1)
+		return 0 if at < 0 else buffer.decode_s8(at)
+
+	func s16() -> int:
+		var at := _take(2)
+		return 0 if at < 0 else buffer.decode_s16(at)
+
+	func s32() -> int:
+		var at := _take(4)
+		return 0 if at < 0 else buffer.decode_s32(at)
+
+	func s64() -> int:
+		var at := _take(8)
+		return 0 if at < 0 else buffer.decode_s64(at)
+
+	func f32() -> float:
+		var at := _take(4)
+		return 0.0 if at < 0 else buffer.decode_float(at)
+
+	func f64() -> float:
+		var at := _take(8)
+		return 0.0 if at < 0 else buffer.decode_double(at)
+
+	func half() -> float:
+		var at := _take(2)
+		return 0.0 if at < 0 else buffer.decode_half(at)
+
+	func unit() -> float:
+		return _f32(s8() * _f32(1.0 / 127.0))
+
+	func unit_angle() -> float:
+		return _f32(s8() * _f32(_f32(PI) / 127.0))
+
+	func unorm8() -> float:
+		return _f32(u8() / 255.0)
+
+	func unorm16() -> float:
+		return _f32(u16() / 65535.0)
+
+	func snorm16() -> float:
+		return maxf(_f32(s16() / 32767.0), -1.0)
+
+	## Byte holding the given number of flags, the unused bits must be zero
+	func flags(count: int) -> int:
+		var byte := u8()
+		if (byte >> count) != 0:
+			failed = true
+		return byte
+
+	func angle(bits: int) -> float:
+		var steps := 1 << bits
+		var step := u16() if bits > 8 else u8()
+		if (step >> bits) != 0:
+			failed = true
+		var value := _f32(_f32(step * _f32(TAU)) / steps)
+		return _f32(value - _f32(TAU)) if value > _f32(PI) else value
+
+	func varint() -> int:
+		var value := 0
+		for index in 10:
+			var byte := u8()
+			value |= (byte & 0x7F) << (7 * index)
+			if (byte & 0x80) == 0:
+				return value
+		failed = true
+		return 0
+
+	func text() -> String:
+		var size := varint()
+		var at := _take(size)
+		if at < 0:
+			return ""
+		return buffer.slice(at, at + size).get_string_from_utf8()
+
+	## Prevent the vector to be longer than 1.0
+	func limit4(value: Vector4) -> Vector4:
+		var length_squared := value.length_squared()
+		return value / sqrt(length_squared) if length_squared > 1.0 else value
+
+	## Normalize the quaternion, failing if it is null
+	func normalized(value: Quaternion) -> Quaternion:
+		if is_zero_approx(value.length_squared()):
+			failed = true
+			return Quaternion()
+		return value.normalized()
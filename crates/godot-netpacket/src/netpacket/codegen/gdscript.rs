@@ -0,0 +1,786 @@
+//!
+//! Generate GDScript classes reading and writing the same bytes as the Rust implementation.
+//!
+
+use super::{Code, CodegenError, collect, to_upper_snake, unsupported};
+use crate::netpacket::{PacketSchema, Schema, SchemaField, SchemaKind, SchemaVariant};
+
+/// Writer and reader used by the generated classes
+const RUNTIME: &str = include_str!("gdscript.gd");
+
+/// Generate a GDScript file holding a class for each packet type, along with the writer and reader they use.
+/// The classes encode the same bytes as the Rust implementation,
+/// assuming the engine stores vectors using single precision floats.
+#[derive(Debug, Default, Clone)]
+pub struct GdScriptGenerator {
+    class_name: Option<String>,
+    schemas: Vec<&'static Schema>,
+}
+
+impl GdScriptGenerator {
+    /// Create a generator without any packet type
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the script as a global class with the given name
+    pub fn class_name(&mut self, name: &str) -> &mut Self {
+        self.class_name = Some(name.to_owned());
+        self
+    }
+
+    /// Generate a class for the packet type and for the types of its fields
+    pub fn add<T: PacketSchema + ?Sized>(&mut self) -> &mut Self {
+        self.schemas.push(T::SCHEMA);
+        self
+    }
+
+    /// Generate the text of the script
+    pub fn generate(&self) -> Result<String, CodegenError> {
+        let mut types = Vec::new();
+        for schema in &self.schemas {
+            collect(schema, &mut types)?;
+        }
+
+        let mut code = Code::new("\t");
+        code.line("# Generated by godot-netpacket from the packet schemas, do not edit.");
+        if let Some(name) = &self.class_name {
+            code.line(format!("class_name {name}"));
+        }
+        code.line("extends RefCounted");
+        code.text.push_str(RUNTIME);
+
+        for schema in types {
+            code.blank();
+            code.blank();
+            match schema.kind {
+                SchemaKind::Struct { fields } => {
+                    write_struct(&mut code, schema.name, fields, true)?
+                }
+                SchemaKind::Enum { variants } => write_enum(&mut code, schema, variants)?,
+                _ => return Err(unsupported(schema)),
+            }
+        }
+
+        Ok(code.text)
+    }
+}
+
+/// Name of the field usable as a GDScript identifier
+fn ident(name: &str) -> String {
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("field_{name}")
+    } else {
+        name.to_owned()
+    }
+}
+
+/// Write a class holding the fields
+fn write_struct(
+    code: &mut Code,
+    name: &str,
+    fields: &[SchemaField],
+    top_level: bool,
+) -> Result<(), CodegenError> {
+    code.line(format!("class {name}:"));
+    code.indent();
+    for field in fields {
+        code.line(format!(
+            "var {}: {}",
+            ident(field.name),
+            gd_type(field.schema)?
+        ));
+    }
+    if !fields.is_empty() {
+        code.blank();
+    }
+
+    // write the flags followed by the values
+    code.reset_temps();
+    code.line("func write(w: Writer) -> void:");
+    code.indent();
+    write_fields(code, fields, "")?;
+    code.dedent();
+    code.blank();
+
+    code.reset_temps();
+    code.line(format!("static func read(r: Reader) -> {name}:"));
+    code.indent();
+    code.line(format!("var value := {name}.new()"));
+    read_fields(code, fields, "value.")?;
+    code.line("return value");
+    code.dedent();
+
+    if top_level {
+        write_bytes(code, name);
+    }
+    code.dedent();
+    Ok(())
+}
+
+/// Write a class holding the variant and the fields of the variant in a nested class
+fn write_enum(
+    code: &mut Code,
+    schema: &Schema,
+    variants: &[SchemaVariant],
+) -> Result<(), CodegenError> {
+    let name = schema.name;
+    if variants.is_empty() {
+        return Err(unsupported(schema));
+    }
+    let has_data = variants.iter().any(|variant| !variant.fields.is_empty());
+
+    code.line(format!("class {name}:"));
+    code.indent();
+    let tags: Vec<String> = variants
+        .iter()
+        .map(|variant| format!("{} = {}", to_upper_snake(variant.name), variant.tag))
+        .collect();
+    code.line(format!("enum Variant {{ {} }}", tags.join(", ")));
+    code.blank();
+    code.line(format!(
+        "var variant: Variant = Variant.{}",
+        to_upper_snake(variants[0].name)
+    ));
+    if has_data {
+        code.line("## Fields of the variant, null for variants without fields");
+        code.line("var data = null");
+    }
+
+    for variant in variants.iter().filter(|variant| !variant.fields.is_empty()) {
+        code.blank();
+        write_struct(code, variant.name, variant.fields, false)?;
+    }
+    code.blank();
+
+    // the tag is followed by the fields of the variant
+    code.line("func write(w: Writer) -> void:");
+    code.indent();
+    code.line("w.u8(variant)");
+    if has_data {
+        code.line("match variant:");
+        code.indent();
+        for variant in variants.iter().filter(|variant| !variant.fields.is_empty()) {
+            code.line(format!("Variant.{}:", to_upper_snake(variant.name)));
+            code.indent();
+            code.line("data.write(w)");
+            code.dedent();
+        }
+        code.dedent();
+    }
+    code.dedent();
+    code.blank();
+
+    code.line(format!("static func read(r: Reader) -> {name}:"));
+    code.indent();
+    code.line(format!("var value := {name}.new()"));
+    code.line("match r.u8():");
+    code.indent();
+    for variant in variants {
+        code.line(format!("{}:", variant.tag));
+        code.indent();
+        code.line(format!(
+            "value.variant = Variant.{}",
+            to_upper_snake(variant.name)
+        ));
+        if !variant.fields.is_empty() {
+            code.line(format!("value.data = {}.read(r)", variant.name));
+        }
+        code.dedent();
+    }
+    code.line("_:");
+    code.indent();
+    code.line("r.failed = true");
+    code.dedent();
+    code.dedent();
+    code.line("return value");
+    code.dedent();
+
+    write_bytes(code, name);
+    code.dedent();
+    Ok(())
+}
+
+/// Write the functions converting a packet from and to bytes
+fn write_bytes(code: &mut Code, name: &str) {
+    code.blank();
+    code.line("func to_bytes() -> PackedByteArray:");
+    code.indent();
+    code.line("var w := Writer.new()");
+    code.line("write(w)");
+    code.line("return PackedByteArray() if w.failed else w.buffer");
+    code.dedent();
+    code.blank();
+    code.line(format!(
+        "static func from_bytes(bytes: PackedByteArray) -> {name}:"
+    ));
+    code.indent();
+    code.line("var r := Reader.new(bytes)");
+    code.line("var value := read(r)");
+    code.line("return null if r.failed else value");
+    code.dedent();
+}
+
+/// Write the bytes holding the flags, followed by the values
+fn write_fields(code: &mut Code, fields: &[SchemaField], prefix: &str) -> Result<(), CodegenError> {
+    if fields.is_empty() {
+        code.line("pass");
+        return Ok(());
+    }
+
+    let flags: Vec<&SchemaField> = fields.iter().filter(|field| field.bit.is_some()).collect();
+    for chunk in flags.chunks(8) {
+        let bits: Vec<String> = chunk
+            .iter()
+            .map(|field| match field.bit {
+                Some(0) | None => format!("int({prefix}{})", ident(field.name)),
+                Some(bit) => format!("int({prefix}{}) << {bit}", ident(field.name)),
+            })
+            .collect();
+        code.line(format!("w.u8({})", bits.join(" | ")));
+    }
+
+    for field in fields.iter().filter(|field| field.bit.is_none()) {
+        write_value(
+            code,
+            field.schema,
+            &format!("{prefix}{}", ident(field.name)),
+        )?;
+    }
+    Ok(())
+}
+
+/// Read the bytes holding the flags, followed by the values
+fn read_fields(code: &mut Code, fields: &[SchemaField], prefix: &str) -> Result<(), CodegenError> {
+    let flags: Vec<&SchemaField> = fields.iter().filter(|field| field.bit.is_some()).collect();
+    for chunk in flags.chunks(8) {
+        let byte = code.temp();
+        code.line(format!("var {byte} := r.flags({})", chunk.len()));
+        for field in chunk {
+            let bit = field.bit.unwrap_or_default();
+            code.line(format!(
+                "{prefix}{} = (({byte} >> {bit}) & 1) == 1",
+                ident(field.name)
+            ));
+        }
+    }
+
+    for field in fields.iter().filter(|field| field.bit.is_none()) {
+        let value = read_value(code, field.schema)?;
+        code.line(format!("{prefix}{} = {value}", ident(field.name)));
+    }
+    Ok(())
+}
+
+/// Type of the value in GDScript
+fn gd_type(schema: &Schema) -> Result<String, CodegenError> {
+    match schema.kind {
+        SchemaKind::Builtin if schema.angle_bits().is_some() => Ok("float".into()),
+        SchemaKind::Builtin => match schema.name {
+            "u8" | "u16" | "u32" | "u64" | "i8" | "i16" | "i32" | "i64" => Ok("int".into()),
+            "NetworkId" => Ok("int".into()),
+            "Key" | "MouseButton" | "JoyButton" | "JoyAxis" => Ok("int".into()),
+            "KeyModifierMask" | "MouseButtonMask" => Ok("int".into()),
+            "f32" | "f64" => Ok("float".into()),
+            "bool" => Ok("bool".into()),
+            "GString" => Ok("String".into()),
+            "Aabb" => Ok("AABB".into()),
+            "StringName" | "NodePath" | "Vector2" | "Vector3" | "Vector4" | "Vector2i"
+            | "Vector3i" | "Vector4i" | "Quaternion" | "Basis" | "Transform2D" | "Transform3D"
+            | "Plane" | "Color" | "Rect2" | "Rect2i" => Ok(schema.name.into()),
+            _ => Err(unsupported(schema)),
+        },
+        SchemaKind::Wrapper { inner, .. } => match schema.name {
+            "Flags" => Ok("int".into()),
+            "Rigid2D" => Ok("Transform2D".into()),
+            _ => gd_type(inner),
+        },
+        SchemaKind::Struct { .. } | SchemaKind::Enum { .. } => Ok(schema.name.into()),
+        SchemaKind::Array { .. } | SchemaKind::Tuple { .. } => Ok("Array".into()),
+    }
+}
+
+/// Components of a vector, quaternion or color
+fn components(name: &str) -> &'static [&'static str] {
+    match name {
+        "Vector2" | "Vector2i" => &["x", "y"],
+        "Vector3" | "Vector3i" => &["x", "y", "z"],
+        "Vector4" | "Vector4i" | "Quaternion" => &["x", "y", "z", "w"],
+        "Color" => &["r", "g", "b", "a"],
+        _ => &[],
+    }
+}
+
+/// Write each component of the value using the method of the writer
+fn write_each(code: &mut Code, method: &str, value: &str, name: &str) {
+    for component in components(name) {
+        code.line(format!("w.{method}({value}.{component})"));
+    }
+}
+
+/// Read each component of the value using the method of the reader
+fn read_each(method: &str, name: &str) -> String {
+    let reads = vec![format!("r.{method}()"); components(name).len()];
+    format!("{name}({})", reads.join(", "))
+}
+
+/// Write the rows of a basis, the columns of the basis are its axes in GDScript
+fn write_rows(code: &mut Code, method: &str, value: &str) {
+    for row in ["x", "y", "z"] {
+        for column in ["x", "y", "z"] {
+            code.line(format!("w.{method}({value}.{column}.{row})"));
+        }
+    }
+}
+
+/// Read the rows of a basis
+fn read_rows(code: &mut Code, method: &str) -> String {
+    let rows: Vec<String> = (0..3)
+        .map(|_| bind(code, read_each(method, "Vector3")))
+        .collect();
+    let columns: Vec<String> = ["x", "y", "z"]
+        .iter()
+        .map(|axis| {
+            format!(
+                "Vector3({}.{axis}, {}.{axis}, {}.{axis})",
+                rows[0], rows[1], rows[2]
+            )
+        })
+        .collect();
+    format!("Basis({})", columns.join(", "))
+}
+
+/// Store the value in a temporary variable to keep the reads in order
+fn bind(code: &mut Code, value: String) -> String {
+    let temp = code.temp();
+    code.line(format!("var {temp} = {value}"));
+    temp
+}
+
+/// Method of the writer and the reader for an integer
+fn int_method(name: &str) -> Option<&'static str> {
+    match name {
+        "u8" => Some("u8"),
+        "u16" | "MouseButtonMask" => Some("u16"),
+        "u32" | "NetworkId" | "KeyModifierMask" => Some("u32"),
+        "u64" => Some("u64"),
+        "i8" | "MouseButton" | "JoyAxis" => Some("s8"),
+        "i16" | "JoyButton" => Some("s16"),
+        "i32" | "Key" => Some("s32"),
+        "i64" => Some("s64"),
+        _ => None,
+    }
+}
+
+/// Write the statements serializing the value
+fn write_value(code: &mut Code, schema: &Schema, value: &str) -> Result<(), CodegenError> {
+    match schema.kind {
+        SchemaKind::Builtin => write_builtin(code, schema, value),
+        SchemaKind::Wrapper { inner, param } => write_wrapper(code, schema, inner, param, value),
+        SchemaKind::Struct { .. } | SchemaKind::Enum { .. } => {
+            code.line(format!("{value}.write(w)"));
+            Ok(())
+        }
+        SchemaKind::Array { item, len } => {
+            let index = code.temp();
+            code.line(format!("for {index} in {len}:"));
+            code.indent();
+            write_value(code, item, &format!("{value}[{index}]"))?;
+            code.dedent();
+            Ok(())
+        }
+        SchemaKind::Tuple { items } => {
+            for (index, item) in items.iter().enumerate() {
+                write_value(code, item, &format!("{value}[{index}]"))?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Write the statements serializing a number or a godot type
+fn write_builtin(code: &mut Code, schema: &Schema, value: &str) -> Result<(), CodegenError> {
+    let name = schema.name;
+    if let Some(bits) = schema.angle_bits() {
+        code.line(format!("w.angle({value}, {bits})"));
+        return Ok(());
+    }
+    if let Some(method) = int_method(name) {
+        code.line(format!("w.{method}({value})"));
+        return Ok(());
+    }
+
+    match name {
+        "f32" | "f64" => code.line(format!("w.{name}({value})")),
+        "bool" => code.line(format!("w.u8(int({value}))")),
+        "Vector2" | "Vector3" | "Vector4" | "Quaternion" => write_each(code, "f32", value, name),
+        "Vector2i" | "Vector3i" | "Vector4i" => write_each(code, "s32", value, name),
+        "Basis" => write_rows(code, "f32", value),
+        "Transform2D" => {
+            write_each(code, "f32", &format!("{value}.x"), "Vector2");
+            write_each(code, "f32", &format!("{value}.y"), "Vector2");
+            write_each(code, "f32", &format!("{value}.origin"), "Vector2");
+        }
+        "Transform3D" => {
+            write_rows(code, "f32", &format!("{value}.basis"));
+            write_each(code, "f32", &format!("{value}.origin"), "Vector3");
+        }
+        "Plane" => {
+            write_each(code, "f32", &format!("{value}.normal"), "Vector3");
+            code.line(format!("w.f32({value}.d)"));
+        }
+        "Color" => {
+            for channel in components(name) {
+                code.line(format!("w.u8(w.channel({value}.{channel}, 255))"));
+            }
+        }
+        "Rect2" | "Rect2i" | "Aabb" => {
+            let (method, vector) = match name {
+                "Rect2" => ("f32", "Vector2"),
+                "Rect2i" => ("s32", "Vector2i"),
+                _ => ("f32", "Vector3"),
+            };
+            write_each(code, method, &format!("{value}.position"), vector);
+            write_each(code, method, &format!("{value}.size"), vector);
+        }
+        "GString" | "StringName" | "NodePath" => code.line(format!("w.text(str({value}))")),
+        _ => return Err(unsupported(schema)),
+    }
+    Ok(())
+}
+
+/// Write the statements serializing a wrapped value
+fn write_wrapper(
+    code: &mut Code,
+    schema: &Schema,
+    inner: &Schema,
+    param: Option<u32>,
+    value: &str,
+) -> Result<(), CodegenError> {
+    // relative positions are stored as halfs
+    let wrapper = match schema.name {
+        "Relative" => "Half",
+        wrapper => wrapper,
+    };
+
+    match (wrapper, inner.name) {
+        ("Unit", "f32") => code.line(format!("w.unit({value})")),
+        ("Unit", "Vector2" | "Vector3" | "Vector4" | "Quaternion") => {
+            write_each(code, "unit", value, inner.name)
+        }
+        ("Unit", "Basis") => {
+            let rotation = bind(code, format!("{value}.get_rotation_quaternion()"));
+            write_each(code, "unit", &rotation, "Quaternion");
+        }
+        ("Unit", "Transform2D") => {
+            code.line(format!("w.unit_angle({value}.get_rotation())"));
+            write_each(code, "f32", &format!("{value}.origin"), "Vector2");
+        }
+        ("Unit", "Transform3D") => {
+            let rotation = bind(code, format!("{value}.basis.get_rotation_quaternion()"));
+            write_each(code, "unit", &rotation, "Quaternion");
+            write_each(code, "f32", &format!("{value}.origin"), "Vector3");
+        }
+        ("Unit", "Plane") => {
+            write_each(code, "unit", &format!("{value}.normal"), "Vector3");
+            code.line(format!("w.f32({value}.d)"));
+        }
+        ("Half", "f32") => code.line(format!("w.half({value})")),
+        ("Half", "Vector2" | "Vector3" | "Vector4" | "Quaternion" | "Color") => {
+            write_each(code, "half", value, inner.name)
+        }
+        ("Half", "Basis") => write_rows(code, "half", value),
+        ("Half", "Transform2D") => {
+            write_each(code, "half", &format!("{value}.x"), "Vector2");
+            write_each(code, "half", &format!("{value}.y"), "Vector2");
+            write_each(code, "f32", &format!("{value}.origin"), "Vector2");
+        }
+        ("Half", "Transform3D") => {
+            write_rows(code, "half", &format!("{value}.basis"));
+            write_each(code, "f32", &format!("{value}.origin"), "Vector3");
+        }
+        ("Half", "Plane") => {
+            write_each(code, "half", &format!("{value}.normal"), "Vector3");
+            code.line(format!("w.f32({value}.d)"));
+        }
+        ("Half", "Rect2" | "Aabb") => {
+            let vector = if inner.name == "Rect2" {
+                "Vector2"
+            } else {
+                "Vector3"
+            };
+            write_each(code, "f32", &format!("{value}.position"), vector);
+            write_each(code, "half", &format!("{value}.size"), vector);
+        }
+        ("Full", "Color") => write_each(code, "f32", value, "Color"),
+        ("Rgb", "Color") => {
+            for channel in ["r", "g", "b"] {
+                code.line(format!("w.u8(w.channel({value}.{channel}, 255))"));
+            }
+        }
+        ("Rgb565", "Color") => code.line(format!(
+            "w.u16(w.channel({value}.r, 31) << 11 | w.channel({value}.g, 63) << 5 | w.channel({value}.b, 31))"
+        )),
+        ("Rgba4444", "Color") => code.line(format!(
+            "w.u16(w.channel({value}.r, 15) << 12 | w.channel({value}.g, 15) << 8 | w.channel({value}.b, 15) << 4 | w.channel({value}.a, 15))"
+        )),
+        ("UNorm8" | "UNorm16" | "SNorm16", "f32") => {
+            code.line(format!("w.{}({value})", wrapper.to_lowercase()))
+        }
+        ("UNorm8" | "UNorm16", "Vector2" | "Vector3" | "Vector4")
+        | ("SNorm16", "Vector2" | "Vector3" | "Vector4" | "Quaternion") => {
+            write_each(code, &wrapper.to_lowercase(), value, inner.name)
+        }
+        ("Trs", "Basis") => write_trs_basis(code, value),
+        ("Trs", "Transform2D") => {
+            code.line(format!("w.f32({value}.get_rotation())"));
+            let scale = bind(code, format!("{value}.get_scale()"));
+            write_each(code, "half", &scale, "Vector2");
+            write_each(code, "f32", &format!("{value}.origin"), "Vector2");
+        }
+        ("Trs", "Transform3D") => {
+            write_trs_basis(code, &format!("{value}.basis"));
+            write_each(code, "f32", &format!("{value}.origin"), "Vector3");
+        }
+        ("Flags", _) => write_value(code, inner, value)?,
+        ("Cell", "Vector2" | "Vector3") => {
+            let size = param.unwrap_or(1);
+            let position = bind(code, format!("{value} / {size}.0"));
+            let cell = bind(code, format!("{position}.floor()"));
+            let local = bind(code, format!("{position} - {cell}"));
+            for component in components(inner.name) {
+                code.line(format!("w.cell({cell}.{component})"));
+            }
+            write_each(code, "unorm16", &local, inner.name);
+        }
+        ("Rigid2D", _) => match inner.kind {
+            SchemaKind::Tuple {
+                items: [rotation, origin],
+            } => {
+                write_value(code, rotation, &format!("{value}.get_rotation()"))?;
+                write_value(code, origin, &format!("{value}.origin"))?;
+            }
+            _ => return Err(unsupported(schema)),
+        },
+        _ => return Err(unsupported(schema)),
+    }
+    Ok(())
+}
+
+/// Write a basis as a rotation and a scale, a negative scale is stored on every axis
+fn write_trs_basis(code: &mut Code, value: &str) {
+    let scale = bind(code, format!("{value}.get_scale()"));
+    let rotation = bind(
+        code,
+        format!(
+            "({value}.scaled(-Vector3.ONE) if {value}.determinant() < 0.0 else {value}).get_rotation_quaternion()"
+        ),
+    );
+    write_each(code, "half", &rotation, "Quaternion");
+    write_each(code, "half", &scale, "Vector3");
+}
+
+/// Write the statements deserializing the value, and return the expression holding it
+fn read_value(code: &mut Code, schema: &Schema) -> Result<String, CodegenError> {
+    match schema.kind {
+        SchemaKind::Builtin => read_builtin(code, schema),
+        SchemaKind::Wrapper { inner, param } => read_wrapper(code, schema, inner, param),
+        SchemaKind::Struct { .. } | SchemaKind::Enum { .. } => {
+            Ok(format!("{}.read(r)", schema.name))
+        }
+        SchemaKind::Array { item, len } => {
+            let array = code.temp();
+            let index = code.temp();
+            code.line(format!("var {array} = []"));
+            code.line(format!("for {index} in {len}:"));
+            code.indent();
+            let value = read_value(code, item)?;
+            code.line(format!("{array}.append({value})"));
+            code.dedent();
+            Ok(array)
+        }
+        SchemaKind::Tuple { items } => {
+            let mut values = Vec::with_capacity(items.len());
+            for item in items {
+                let value = read_value(code, item)?;
+                values.push(bind(code, value));
+            }
+            Ok(format!("[{}]", values.join(", ")))
+        }
+    }
+}
+
+/// Write the statements deserializing a number or a godot type
+fn read_builtin(code: &mut Code, schema: &Schema) -> Result<String, CodegenError> {
+    let name = schema.name;
+    if let Some(bits) = schema.angle_bits() {
+        return Ok(format!("r.angle({bits})"));
+    }
+    if let Some(method) = int_method(name) {
+        return Ok(format!("r.{method}()"));
+    }
+
+    let value = match name {
+        "f32" | "f64" => format!("r.{name}()"),
+        "bool" => "r.u8() != 0".into(),
+        "Vector2" | "Vector3" | "Vector4" | "Quaternion" => read_each("f32", name),
+        "Vector2i" | "Vector3i" | "Vector4i" => read_each("s32", name),
+        "Basis" => read_rows(code, "f32"),
+        "Transform2D" => {
+            let vector = read_each("f32", "Vector2");
+            format!("Transform2D({vector}, {vector}, {vector})")
+        }
+        "Transform3D" => {
+            let basis = read_rows(code, "f32");
+            let basis = bind(code, basis);
+            format!("Transform3D({basis}, {})", read_each("f32", "Vector3"))
+        }
+        "Plane" => format!("Plane({}, r.f32())", read_each("f32", "Vector3")),
+        "Color" => read_each("unorm8", "Color"),
+        "Rect2" => {
+            let vector = read_each("f32", "Vector2");
+            format!("Rect2({vector}, {vector})")
+        }
+        "Rect2i" => {
+            let vector = read_each("s32", "Vector2i");
+            format!("Rect2i({vector}, {vector})")
+        }
+        "Aabb" => {
+            let vector = read_each("f32", "Vector3");
+            format!("AABB({vector}, {vector})")
+        }
+        "GString" => "r.text()".into(),
+        "StringName" | "NodePath" => format!("{name}(r.text())"),
+        _ => return Err(unsupported(schema)),
+    };
+    Ok(value)
+}
+
+/// Write the statements deserializing a wrapped value
+fn read_wrapper(
+    code: &mut Code,
+    schema: &Schema,
+    inner: &Schema,
+    param: Option<u32>,
+) -> Result<String, CodegenError> {
+    // relative positions are stored as halfs
+    let wrapper = match schema.name {
+        "Relative" => "Half",
+        wrapper => wrapper,
+    };
+
+    let value = match (wrapper, inner.name) {
+        ("Unit", "f32") => "r.unit()".into(),
+        ("Unit", "Vector2" | "Vector3") => {
+            format!("{}.limit_length(1.0)", read_each("unit", inner.name))
+        }
+        ("Unit", "Vector4") => format!("r.limit4({})", read_each("unit", "Vector4")),
+        ("Unit", "Quaternion") => format!("r.normalized({})", read_each("unit", "Quaternion")),
+        ("Unit", "Basis") => {
+            let rotation = format!("r.normalized({})", read_each("unit", "Quaternion"));
+            format!("Basis({})", bind(code, rotation))
+        }
+        ("Unit", "Transform2D") => format!(
+            "Transform2D(r.unit_angle(), {})",
+            read_each("f32", "Vector2")
+        ),
+        ("Unit", "Transform3D") => {
+            let rotation = format!("r.normalized({})", read_each("unit", "Quaternion"));
+            let rotation = bind(code, rotation);
+            format!(
+                "Transform3D(Basis({rotation}), {})",
+                read_each("f32", "Vector3")
+            )
+        }
+        ("Unit", "Plane") => {
+            let normal = format!("{}.limit_length(1.0)", read_each("unit", "Vector3"));
+            format!("Plane({}, r.f32())", bind(code, normal))
+        }
+        ("Half", "f32") => "r.half()".into(),
+        ("Half", "Vector2" | "Vector3" | "Vector4" | "Color") => read_each("half", inner.name),
+        ("Half", "Quaternion") => format!("r.normalized({})", read_each("half", "Quaternion")),
+        ("Half", "Basis") => read_rows(code, "half"),
+        ("Half", "Transform2D") => {
+            let axis = read_each("half", "Vector2");
+            format!(
+                "Transform2D({axis}, {axis}, {})",
+                read_each("f32", "Vector2")
+            )
+        }
+        ("Half", "Transform3D") => {
+            let basis = read_rows(code, "half");
+            let basis = bind(code, basis);
+            format!("Transform3D({basis}, {})", read_each("f32", "Vector3"))
+        }
+        ("Half", "Plane") => format!("Plane({}, r.f32())", read_each("half", "Vector3")),
+        ("Half", "Rect2") => format!(
+            "Rect2({}, {})",
+            read_each("f32", "Vector2"),
+            read_each("half", "Vector2")
+        ),
+        ("Half", "Aabb") => format!(
+            "AABB({}, {})",
+            read_each("f32", "Vector3"),
+            read_each("half", "Vector3")
+        ),
+        ("Full", "Color") => read_each("f32", "Color"),
+        ("Rgb", "Color") => "Color(r.unorm8(), r.unorm8(), r.unorm8(), 1.0)".into(),
+        ("Rgb565", "Color") => {
+            let packed = bind(code, "r.u16()".into());
+            format!(
+                "Color(({packed} >> 11) / 31.0, (({packed} >> 5) & 63) / 63.0, ({packed} & 31) / 31.0, 1.0)"
+            )
+        }
+        ("Rgba4444", "Color") => {
+            let packed = bind(code, "r.u16()".into());
+            format!(
+                "Color(({packed} >> 12) / 15.0, (({packed} >> 8) & 15) / 15.0, (({packed} >> 4) & 15) / 15.0, ({packed} & 15) / 15.0)"
+            )
+        }
+        ("UNorm8" | "UNorm16" | "SNorm16", "f32") => format!("r.{}()", wrapper.to_lowercase()),
+        ("UNorm8" | "UNorm16", "Vector2" | "Vector3" | "Vector4")
+        | ("SNorm16", "Vector2" | "Vector3" | "Vector4" | "Quaternion") => {
+            read_each(&wrapper.to_lowercase(), inner.name)
+        }
+        ("Trs", "Basis") => read_trs_basis(code),
+        ("Trs", "Transform2D") => format!(
+            "Transform2D(r.f32(), {}, 0.0, {})",
+            read_each("half", "Vector2"),
+            read_each("f32", "Vector2")
+        ),
+        ("Trs", "Transform3D") => {
+            let basis = read_trs_basis(code);
+            let basis = bind(code, basis);
+            format!("Transform3D({basis}, {})", read_each("f32", "Vector3"))
+        }
+        ("Flags", _) => read_value(code, inner)?,
+        ("Cell", "Vector2" | "Vector3") => format!(
+            "({} + {}) * {}.0",
+            read_each("s32", inner.name),
+            read_each("unorm16", inner.name),
+            param.unwrap_or(1)
+        ),
+        ("Rigid2D", _) => match inner.kind {
+            SchemaKind::Tuple {
+                items: [rotation, origin],
+            } => {
+                let rotation = read_value(code, rotation)?;
+                let rotation = bind(code, rotation);
+                let origin = read_value(code, origin)?;
+                let origin = bind(code, origin);
+                format!("Transform2D({rotation}, {origin})")
+            }
+            _ => return Err(unsupported(schema)),
+        },
+        _ => return Err(unsupported(schema)),
+    };
+    Ok(value)
+}
+
+/// Read a basis stored as a rotation and a scale
+fn read_trs_basis(code: &mut Code) -> String {
+    let rotation = format!("r.normalized({})", read_each("half", "Quaternion"));
+    let rotation = bind(code, rotation);
+    let scale = bind(code, read_each("half", "Vector3"));
+    format!("Basis({rotation}) * Basis.from_scale({scale})")
+}
@@ -4,10 +4,7 @@
 
 use super::{ConstSize, Deserialize, Serialize};
 use godot::builtin::*;
-use std::{
-    mem::{MaybeUninit, forget},
-    ptr::read,
-};
+use std::{mem::MaybeUninit, ptr::read};
 
 // implement for static arrays
 
@@ -24,7 +21,7 @@ where
 {
     fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
         for (i, t) in self.iter().enumerate() {
-            let _ = t.serialize(buffer, offset + i * T::SIZE)?;
+            t.serialize(buffer, offset + i * T::SIZE)?;
         }
         Ok(())
     }
@@ -40,16 +37,13 @@ where
     {
         // SAFETY: creating uninitialized array of MaybeUninit<T>
         let mut array: [MaybeUninit<T>; S] = unsafe { MaybeUninit::uninit().assume_init() };
-        for i in 0..S {
-            array[i].write(T::deserialize(buffer, offset + i * T::SIZE)?);
+        for (i, item) in array.iter_mut().enumerate() {
+            item.write(T::deserialize(buffer, offset + i * T::SIZE)?);
         }
 
         // SAFETY: all elements initialized, so this is valid
         let initialized = unsafe { read(&array as *const _ as *const [T; S]) };
 
-        // Prevent dropping of uninitialized memory
-        forget(array);
-
         Ok(initialized)
     }
 }
@@ -68,7 +62,7 @@ where
     T0: Serialize + ConstSize,
 {
     fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
-        let _ = self.0.serialize(buffer, offset)?;
+        self.0.serialize(buffer, offset)?;
         Ok(())
     }
 }
@@ -99,8 +93,8 @@ where
     T1: Serialize + ConstSize,
 {
     fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
-        let _ = self.0.serialize(buffer, offset)?;
-        let _ = self.1.serialize(buffer, offset + T0::SIZE)?;
+        self.0.serialize(buffer, offset)?;
+        self.1.serialize(buffer, offset + T0::SIZE)?;
         Ok(())
     }
 }
@@ -141,9 +135,9 @@ where
         let i1 = i0 + T0::SIZE;
         let i2 = i1 + T1::SIZE;
 
-        let _ = self.0.serialize(buffer, i0)?;
-        let _ = self.1.serialize(buffer, i1)?;
-        let _ = self.2.serialize(buffer, i2)?;
+        self.0.serialize(buffer, i0)?;
+        self.1.serialize(buffer, i1)?;
+        self.2.serialize(buffer, i2)?;
         Ok(())
     }
 }
@@ -193,10 +187,10 @@ where
         let i2 = i1 + T1::SIZE;
         let i3 = i2 + T2::SIZE;
 
-        let _ = self.0.serialize(buffer, i0)?;
-        let _ = self.1.serialize(buffer, i1)?;
-        let _ = self.2.serialize(buffer, i2)?;
-        let _ = self.3.serialize(buffer, i3)?;
+        self.0.serialize(buffer, i0)?;
+        self.1.serialize(buffer, i1)?;
+        self.2.serialize(buffer, i2)?;
+        self.3.serialize(buffer, i3)?;
         Ok(())
     }
 }
@@ -252,11 +246,11 @@ where
         let i3 = i2 + T2::SIZE;
         let i4 = i3 + T3::SIZE;
 
-        let _ = self.0.serialize(buffer, i0)?;
-        let _ = self.1.serialize(buffer, i1)?;
-        let _ = self.2.serialize(buffer, i2)?;
-        let _ = self.3.serialize(buffer, i3)?;
-        let _ = self.4.serialize(buffer, i4)?;
+        self.0.serialize(buffer, i0)?;
+        self.1.serialize(buffer, i1)?;
+        self.2.serialize(buffer, i2)?;
+        self.3.serialize(buffer, i3)?;
+        self.4.serialize(buffer, i4)?;
         Ok(())
     }
 }
@@ -318,12 +312,12 @@ where
         let i4 = i3 + T3::SIZE;
         let i5 = i4 + T4::SIZE;
 
-        let _ = self.0.serialize(buffer, i0)?;
-        let _ = self.1.serialize(buffer, i1)?;
-        let _ = self.2.serialize(buffer, i2)?;
-        let _ = self.3.serialize(buffer, i3)?;
-        let _ = self.4.serialize(buffer, i4)?;
-        let _ = self.5.serialize(buffer, i5)?;
+        self.0.serialize(buffer, i0)?;
+        self.1.serialize(buffer, i1)?;
+        self.2.serialize(buffer, i2)?;
+        self.3.serialize(buffer, i3)?;
+        self.4.serialize(buffer, i4)?;
+        self.5.serialize(buffer, i5)?;
         Ok(())
     }
 }
@@ -358,3 +352,13 @@ where
         ))
     }
 }
+
+crate::impl_contextless![
+    [T, const S: usize] [T; S],
+    [T0] (T0,),
+    [T0, T1] (T0, T1),
+    [T0, T1, T2] (T0, T1, T2),
+    [T0, T1, T2, T3] (T0, T1, T2, T3),
+    [T0, T1, T2, T3, T4] (T0, T1, T2, T3, T4),
+    [T0, T1, T2, T3, T4, T5] (T0, T1, T2, T3, T4, T5),
+];
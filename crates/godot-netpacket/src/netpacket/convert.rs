@@ -0,0 +1,205 @@
+//!
+//! Convert packets to and from godot values, the encodings of the wrappers are transparent.
+//!
+
+use super::{
+    Angle, Cell, Flags, Full, Half, Interned, NetRef, NetworkId, Relative, ResourceRef, Rgb,
+    Rgb565, Rgba4444, Rigid2D, SNorm16, Trs, UNorm8, UNorm16, Unit,
+    codec::{FIELDS_KEY, VARIANT_KEY, lookup},
+};
+use godot::{
+    builtin::*,
+    meta::{ByValue, FromGodot, GodotConvert, ToGodot, error::ConvertError},
+    obj::{Gd, GodotClass},
+};
+
+/// Convert the wrappers as the value they encapsulate
+macro_rules! impl_convert {
+    ( $( $wrap:ident < T $( , const $param:ident : $ptype:ty )? > ),+ ) => {
+        $(
+            impl<T: GodotConvert $( , const $param: $ptype )?> GodotConvert for $wrap<T $( , $param )?> {
+                type Via = T::Via;
+            }
+
+            impl<T: ToGodot<Via: Clone> $( , const $param: $ptype )?> ToGodot for $wrap<T $( , $param )?> {
+                type Pass = ByValue;
+
+                #[inline]
+                fn to_godot(&self) -> Self::Via {
+                    self.0.to_godot_owned()
+                }
+            }
+
+            impl<T: FromGodot $( , const $param: $ptype )?> FromGodot for $wrap<T $( , $param )?> {
+                #[inline]
+                fn try_from_godot(via: Self::Via) -> Result<Self, ConvertError> {
+                    T::try_from_godot(via).map(Self)
+                }
+            }
+        )+
+    };
+}
+
+impl_convert![
+    Unit<T>,
+    Half<T>,
+    UNorm8<T, const STRICT: bool>,
+    UNorm16<T, const STRICT: bool>,
+    SNorm16<T, const STRICT: bool>,
+    Trs<T>,
+    Full<T>,
+    Rgb<T>,
+    Rgb565<T>,
+    Rgba4444<T>,
+    Cell<T, const CELL_SIZE: u32>,
+    Relative<T>,
+    Flags<T>,
+    Interned<T>
+];
+
+/// Angles are converted as radians
+impl<const BITS: u32> GodotConvert for Angle<BITS> {
+    type Via = real;
+}
+
+impl<const BITS: u32> ToGodot for Angle<BITS> {
+    type Pass = ByValue;
+
+    #[inline]
+    fn to_godot(&self) -> real {
+        self.0
+    }
+}
+
+impl<const BITS: u32> FromGodot for Angle<BITS> {
+    #[inline]
+    fn try_from_godot(via: real) -> Result<Self, ConvertError> {
+        Ok(Self(via))
+    }
+}
+
+/// 2D transforms are converted as the whole transform
+impl<A, O> GodotConvert for Rigid2D<A, O> {
+    type Via = Transform2D;
+}
+
+impl<A, O> ToGodot for Rigid2D<A, O> {
+    type Pass = ByValue;
+
+    #[inline]
+    fn to_godot(&self) -> Transform2D {
+        self.0
+    }
+}
+
+impl<A, O> FromGodot for Rigid2D<A, O> {
+    #[inline]
+    fn try_from_godot(via: Transform2D) -> Result<Self, ConvertError> {
+        Ok(Self::new(via))
+    }
+}
+
+/// Resources are converted as the object itself
+impl<T: GodotClass, const TABLE: bool> GodotConvert for ResourceRef<T, TABLE> {
+    type Via = Gd<T>;
+}
+
+impl<T: GodotClass, const TABLE: bool> ToGodot for ResourceRef<T, TABLE> {
+    type Pass = ByValue;
+
+    #[inline]
+    fn to_godot(&self) -> Gd<T> {
+        self.0.clone()
+    }
+}
+
+impl<T: GodotClass, const TABLE: bool> FromGodot for ResourceRef<T, TABLE> {
+    #[inline]
+    fn try_from_godot(via: Gd<T>) -> Result<Self, ConvertError> {
+        Ok(Self(via))
+    }
+}
+
+/// Network identifiers are converted as integers
+impl GodotConvert for NetworkId {
+    type Via = i64;
+}
+
+impl ToGodot for NetworkId {
+    type Pass = ByValue;
+
+    #[inline]
+    fn to_godot(&self) -> i64 {
+        self.0.into()
+    }
+}
+
+impl FromGodot for NetworkId {
+    fn try_from_godot(via: i64) -> Result<Self, ConvertError> {
+        u32::try_from(via)
+            .map(Self)
+            .map_err(|_| ConvertError::new(format!("Network identifier {via} is out of range")))
+    }
+}
+
+/// References are converted as the object itself
+impl<T: GodotClass> GodotConvert for NetRef<T> {
+    type Via = Gd<T>;
+}
+
+impl<T: GodotClass> ToGodot for NetRef<T> {
+    type Pass = ByValue;
+
+    #[inline]
+    fn to_godot(&self) -> Gd<T> {
+        self.0.clone()
+    }
+}
+
+impl<T: GodotClass> FromGodot for NetRef<T> {
+    #[inline]
+    fn try_from_godot(via: Gd<T>) -> Result<Self, ConvertError> {
+        Ok(Self(via))
+    }
+}
+
+/// Get the field with the given name from the dictionary, used by the `PacketConvert` derive
+#[doc(hidden)]
+pub fn field_from_godot<T: FromGodot>(data: &VarDictionary, name: &str) -> Result<T, ConvertError> {
+    lookup(data, name)
+        .ok_or_else(|| ConvertError::new(format!("Missing value for {name}")))?
+        .try_to_relaxed()
+        .map_err(|error| ConvertError::new(format!("Invalid value for {name}: {error}")))
+}
+
+/// Represent the variant of an enum with its name and its fields, used by the `PacketConvert` derive
+#[doc(hidden)]
+pub fn variant_to_godot(variant: &str, fields: VarDictionary) -> VarDictionary {
+    let mut data = VarDictionary::new();
+    data.set(VARIANT_KEY, variant);
+    data.set(FIELDS_KEY, fields);
+    data
+}
+
+/// Get the name and the fields of the variant of an enum, used by the `PacketConvert` derive.
+/// Variants without fields do not need to provide them.
+#[doc(hidden)]
+pub fn variant_from_godot(data: &VarDictionary) -> Result<(String, VarDictionary), ConvertError> {
+    let variant = lookup(data, VARIANT_KEY)
+        .ok_or_else(|| ConvertError::new(format!("Missing value for {VARIANT_KEY}")))?
+        .stringify()
+        .to_string();
+    let fields = match lookup(data, FIELDS_KEY) {
+        Some(fields) => fields.try_to().map_err(|error| {
+            ConvertError::new(format!("Invalid value for {FIELDS_KEY}: {error}"))
+        })?,
+        None => VarDictionary::new(),
+    };
+    Ok((variant, fields))
+}
+
+/// Error for a variant name matching none of the variants of the enum, used by the `PacketConvert` derive
+#[doc(hidden)]
+pub fn unknown_variant(variant: &str) -> ConvertError {
+    ConvertError::new(format!("Unknown variant {variant}"))
+}
@@ -0,0 +1,808 @@
+//!
+//! Describe packet types at runtime, so that mods can define packets without recompiling the extension.
+//!
+
+use super::{
+    Angle, Cell, ConstSize, Flags, Full, Half, NetRef, NetworkId, PacketSchema, Rgb, Rgb565,
+    Rgba4444, SNorm16, Schema, SchemaField, SchemaKind, SchemaVariant, Trs, UNorm8, UNorm16, Unit,
+    codec::{
+        CodecError, FIELDS_KEY, NetPacketCodec, child, decode_dictionary, encode_dictionary,
+        for_builtin, for_wrapper, lookup,
+    },
+};
+use godot::{
+    builtin::*,
+    classes::{InputEvent, Json, Object},
+    global::{JoyAxis, JoyButton, Key, KeyModifierMask, MouseButton, MouseButtonMask},
+    obj::Gd,
+};
+
+/// Key holding the name of a packet, a field or a variant
+const NAME_KEY: &str = "name";
+
+/// Key holding the type of a field
+const TYPE_KEY: &str = "type";
+
+/// Key holding the variants of an enum
+const VARIANTS_KEY: &str = "variants";
+
+/// Packet type described at runtime, from a dictionary or from JSON.
+/// It is encoded with the same bytes as a derived type declaring the same fields in the same order.
+///
+/// Structs are described as `{"name": "Move", "fields": [{"name": "position", "type": "Half<Vector3>"}]}`
+/// and enums as `{"name": "Command", "variants": [{"name": "Stop", "fields": []}]}`, the fields of a variant being optional.
+/// Types are written as displayed by the schemas, such as `u16`, `Unit<Vector3>`, `Angle<16>`, `Cell<Vector3, 4>`,
+/// `Rigid2D<Angle<12>, Half<Vector2>>` or `[u8; 4]`.
+///
+/// The schemas are never freed so that they can be registered like the derived types,
+/// load each definition once or through `register_definition`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DynamicSchema {
+    schema: &'static Schema,
+}
+
+impl DynamicSchema {
+    /// Describe a struct from the name and the type of each field
+    pub fn new_struct(name: &str, fields: &[(&str, &str)]) -> Result<Self, CodecError> {
+        Draft::new_struct(name, fields).map(Self::leak)
+    }
+
+    /// Describe an enum from the name and the fields of each variant, tagged in order
+    pub fn new_enum(name: &str, variants: &[(&str, &[(&str, &str)])]) -> Result<Self, CodecError> {
+        Draft::new_enum(name, variants).map(Self::leak)
+    }
+
+    /// Describe a struct or an enum from its definition
+    pub fn from_dictionary(definition: &VarDictionary) -> Result<Self, CodecError> {
+        Draft::from_dictionary(definition).map(Self::leak)
+    }
+
+    /// Describe a struct or an enum from the JSON text of its definition
+    pub fn from_json(text: &str) -> Result<Self, CodecError> {
+        Self::from_variant(&Json::parse_string(text))
+    }
+
+    /// Describe a struct or an enum from a JSON resource, such as a `.json` file loaded by the engine
+    pub fn from_resource(resource: &Gd<Json>) -> Result<Self, CodecError> {
+        Self::from_variant(&resource.get_data())
+    }
+
+    /// Describe and register the packet type from its definition, see `register`.
+    /// The schema is only kept when the name was not registered yet,
+    /// so that definitions loaded again or conflicting with a registered packet type do not leak.
+    pub fn register_definition(definition: &VarDictionary) -> Result<Self, CodecError> {
+        let draft = Draft::from_dictionary(definition)?;
+        let name = draft.name().to_owned();
+        let schema = NetPacketCodec::register_with(&name, |known| match known {
+            Some(known) if draft.matches(known) => Ok(known),
+            Some(known) => Err(CodecError::Conflict(known.name)),
+            None => Ok(draft.leak()),
+        })?;
+        Ok(Self { schema })
+    }
+
+    /// Describe a struct or an enum from the parsed JSON
+    fn from_variant(value: &Variant) -> Result<Self, CodecError> {
+        let definition = value
+            .try_to::<VarDictionary>()
+            .map_err(|_| definition("packet", "the definition is not a JSON object"))?;
+        Self::from_dictionary(&definition)
+    }
+
+    /// Keep the schema described by the draft for the rest of the program
+    fn leak(draft: Draft) -> Self {
+        Self {
+            schema: draft.leak(),
+        }
+    }
+
+    /// Get the schema of the packet type
+    #[inline]
+    pub fn schema(&self) -> &'static Schema {
+        self.schema
+    }
+
+    /// Make the packet type reachable by its name from `NetPacketCodec`
+    pub fn register(&self) -> Result<(), CodecError> {
+        NetPacketCodec::register_schema(self.schema)
+    }
+
+    /// Encode the packet from the dictionary holding its fields
+    pub fn encode(&self, data: &VarDictionary) -> Result<PackedByteArray, CodecError> {
+        encode_dictionary(self.schema, data)
+    }
+
+    /// Decode the packet into a dictionary holding its fields
+    pub fn decode(&self, buffer: &PackedByteArray) -> Result<VarDictionary, CodecError> {
+        decode_dictionary(self.schema, buffer)
+    }
+}
+
+/// Error for the definition at the given path
+fn definition(path: &str, reason: &str) -> CodecError {
+    CodecError::Definition(format!("{path}: {reason}"))
+}
+
+/// Keep the schema for the rest of the program
+fn leak(schema: Schema) -> &'static Schema {
+    Box::leak(Box::new(schema))
+}
+
+/// Get the text stored with the key
+fn text(data: &VarDictionary, key: &str, path: &str) -> Result<String, CodecError> {
+    lookup(data, key)
+        .map(|value| value.stringify().to_string())
+        .ok_or_else(|| definition(path, &format!("missing {key}")))
+}
+
+/// Get the dictionaries listed by the value
+fn list(value: &Variant, path: &str) -> Result<Vec<VarDictionary>, CodecError> {
+    let items = value
+        .try_to::<VarArray>()
+        .map_err(|_| definition(path, "expected a list"))?;
+    items
+        .iter_shared()
+        .map(|item| {
+            item.try_to::<VarDictionary>()
+                .map_err(|_| definition(path, "expected a list of objects"))
+        })
+        .collect()
+}
+
+/// Get the name and the type of each field listed by the value
+fn read_fields(value: &Variant, path: &str) -> Result<Vec<(String, String)>, CodecError> {
+    list(value, path)?
+        .iter()
+        .map(|field| {
+            let name = text(field, NAME_KEY, path)?;
+            let atype = text(field, TYPE_KEY, &child(path, &name))?;
+            Ok((name, atype))
+        })
+        .collect()
+}
+
+/// Borrow the names and the types of the fields
+fn borrow(fields: &[(String, String)]) -> Vec<(&str, &str)> {
+    fields
+        .iter()
+        .map(|(name, atype)| (name.as_str(), atype.as_str()))
+        .collect()
+}
+
+/// Offset following a value of the given size, failing when it overflows
+fn after(offset: Option<usize>, size: Option<usize>) -> Result<Option<usize>, String> {
+    match (offset, size) {
+        (Some(offset), Some(size)) => offset
+            .checked_add(size)
+            .map(Some)
+            .ok_or_else(|| "the packet is too large".to_owned()),
+        _ => Ok(None),
+    }
+}
+
+/// Schema described at runtime and not leaked yet
+#[derive(Debug)]
+enum Draft {
+    /// Schema of a type known at compile time
+    Known(&'static Schema),
+
+    /// Schema created for the definition
+    New {
+        name: String,
+        size: Option<usize>,
+        kind: DraftKind,
+    },
+}
+
+/// Way a value described at runtime is stored, like `SchemaKind`
+#[derive(Debug)]
+enum DraftKind {
+    Wrapper {
+        inner: Box<Draft>,
+        param: Option<u32>,
+    },
+    Struct {
+        fields: Vec<DraftField>,
+    },
+    Enum {
+        variants: Vec<DraftVariant>,
+    },
+    Array {
+        item: Box<Draft>,
+        len: usize,
+    },
+    Tuple {
+        items: Vec<Draft>,
+    },
+}
+
+/// Field described at runtime, like `SchemaField`
+#[derive(Debug)]
+struct DraftField {
+    name: String,
+    schema: Draft,
+    offset: Option<usize>,
+    bit: Option<u8>,
+}
+
+/// Variant described at runtime, like `SchemaVariant`
+#[derive(Debug)]
+struct DraftVariant {
+    name: String,
+    tag: u8,
+    size: Option<usize>,
+    fields: Vec<DraftField>,
+}
+
+impl Draft {
+    /// Describe a struct from the name and the type of each field
+    fn new_struct(name: &str, fields: &[(&str, &str)]) -> Result<Self, CodecError> {
+        let (fields, size) = describe_fields(fields, 0, name)?;
+        Ok(Self::New {
+            name: name.to_owned(),
+            size,
+            kind: DraftKind::Struct { fields },
+        })
+    }
+
+    /// Describe an enum from the name and the fields of each variant, tagged in order
+    fn new_enum(name: &str, variants: &[(&str, &[(&str, &str)])]) -> Result<Self, CodecError> {
+        if variants.len() > u8::MAX as usize + 1 {
+            return Err(definition(name, "too many variants"));
+        }
+
+        let mut described = Vec::with_capacity(variants.len());
+        for (tag, (variant, fields)) in variants.iter().enumerate() {
+            let path = child(name, variant);
+            if variants[..tag].iter().any(|(other, _)| other == variant) {
+                return Err(definition(&path, "duplicate variant"));
+            }
+            let (fields, size) = describe_fields(fields, 1, &path)?;
+            described.push(DraftVariant {
+                name: (*variant).to_owned(),
+                tag: tag as u8,
+                size,
+                fields,
+            });
+        }
+
+        // the size is only known when every variant has the same
+        let size = match described.split_first() {
+            Some((first, rest)) if rest.iter().all(|variant| variant.size == first.size) => {
+                first.size
+            }
+            _ => None,
+        };
+        Ok(Self::New {
+            name: name.to_owned(),
+            size,
+            kind: DraftKind::Enum {
+                variants: described,
+            },
+        })
+    }
+
+    /// Describe a struct or an enum from its definition
+    fn from_dictionary(definition: &VarDictionary) -> Result<Self, CodecError> {
+        let name = text(definition, NAME_KEY, "packet")?;
+
+        // structs list their fields, enums their variants
+        let Some(variants) = lookup(definition, VARIANTS_KEY) else {
+            let fields = match lookup(definition, FIELDS_KEY) {
+                Some(fields) => read_fields(&fields, &name)?,
+                None => Vec::new(),
+            };
+            return Self::new_struct(&name, &borrow(&fields));
+        };
+
+        let mut owned = Vec::new();
+        for variant in list(&variants, &name)? {
+            let variant_name = text(&variant, NAME_KEY, &name)?;
+            let fields = match lookup(&variant, FIELDS_KEY) {
+                Some(fields) => read_fields(&fields, &child(&name, &variant_name))?,
+                None => Vec::new(),
+            };
+            owned.push((variant_name, fields));
+        }
+        let fields = owned
+            .iter()
+            .map(|(_, fields)| borrow(fields))
+            .collect::<Vec<_>>();
+        let variants = owned
+            .iter()
+            .zip(&fields)
+            .map(|((name, _), fields)| (name.as_str(), fields.as_slice()))
+            .collect::<Vec<_>>();
+        Self::new_enum(&name, &variants)
+    }
+
+    /// Name of the type
+    fn name(&self) -> &str {
+        match self {
+            Self::Known(schema) => schema.name,
+            Self::New { name, .. } => name,
+        }
+    }
+
+    /// Size in bytes, unknown for types with a dynamic size
+    fn size(&self) -> Option<usize> {
+        match self {
+            Self::Known(schema) => schema.size,
+            Self::New { size, .. } => *size,
+        }
+    }
+
+    /// Get the schema if the type is known at compile time
+    fn known(&self) -> Option<&'static Schema> {
+        match self {
+            Self::Known(schema) => Some(schema),
+            Self::New { .. } => None,
+        }
+    }
+
+    /// Check if the value is stored in the bitfield, like the `bool` fields of the derived types
+    fn is_flag(&self) -> bool {
+        self.known() == Some(<bool as PacketSchema>::SCHEMA)
+    }
+
+    /// Check if the draft describes the same layout as the schema
+    fn matches(&self, schema: &Schema) -> bool {
+        let (name, size, kind) = match self {
+            Self::Known(known) => return *known == schema,
+            Self::New { name, size, kind } => (name, size, kind),
+        };
+        if name != schema.name || *size != schema.size {
+            return false;
+        }
+        match (kind, schema.kind) {
+            (
+                DraftKind::Wrapper { inner, param },
+                SchemaKind::Wrapper {
+                    inner: other,
+                    param: p,
+                },
+            ) => *param == p && inner.matches(other),
+            (DraftKind::Struct { fields }, SchemaKind::Struct { fields: other }) => {
+                fields.len() == other.len()
+                    && fields
+                        .iter()
+                        .zip(other)
+                        .all(|(field, other)| field.matches(other))
+            }
+            (DraftKind::Enum { variants }, SchemaKind::Enum { variants: other }) => {
+                variants.len() == other.len()
+                    && variants
+                        .iter()
+                        .zip(other)
+                        .all(|(variant, other)| variant.matches(other))
+            }
+            (
+                DraftKind::Array { item, len },
+                SchemaKind::Array {
+                    item: other,
+                    len: l,
+                },
+            ) => *len == l && item.matches(other),
+            (DraftKind::Tuple { items }, SchemaKind::Tuple { items: other }) => {
+                items.len() == other.len()
+                    && items
+                        .iter()
+                        .zip(other)
+                        .all(|(item, other)| item.matches(other))
+            }
+            _ => false,
+        }
+    }
+
+    /// Keep the schema for the rest of the program
+    fn leak(self) -> &'static Schema {
+        let (name, size, kind) = match self {
+            Self::Known(schema) => return schema,
+            Self::New { name, size, kind } => (name, size, kind),
+        };
+        let kind = match kind {
+            DraftKind::Wrapper { inner, param } => SchemaKind::Wrapper {
+                inner: inner.leak(),
+                param,
+            },
+            DraftKind::Struct { fields } => SchemaKind::Struct {
+                fields: fields
+                    .into_iter()
+                    .map(DraftField::leak)
+                    .collect::<Vec<_>>()
+                    .leak(),
+            },
+            DraftKind::Enum { variants } => SchemaKind::Enum {
+                variants: variants
+                    .into_iter()
+                    .map(DraftVariant::leak)
+                    .collect::<Vec<_>>()
+                    .leak(),
+            },
+            DraftKind::Array { item, len } => SchemaKind::Array {
+                item: item.leak(),
+                len,
+            },
+            DraftKind::Tuple { items } => SchemaKind::Tuple {
+                items: items
+                    .into_iter()
+                    .map(Draft::leak)
+                    .collect::<Vec<_>>()
+                    .leak(),
+            },
+        };
+        leak(Schema {
+            name: name.leak(),
+            size,
+            kind,
+        })
+    }
+}
+
+impl DraftField {
+    /// Check if the draft describes the same field as the schema
+    fn matches(&self, field: &SchemaField) -> bool {
+        self.name == field.name
+            && self.offset == field.offset
+            && self.bit == field.bit
+            && self.schema.matches(field.schema)
+    }
+
+    /// Keep the field for the rest of the program
+    fn leak(self) -> SchemaField {
+        SchemaField {
+            name: self.name.leak(),
+            schema: self.schema.leak(),
+            offset: self.offset,
+            bit: self.bit,
+        }
+    }
+}
+
+impl DraftVariant {
+    /// Check if the draft describes the same variant as the schema
+    fn matches(&self, variant: &SchemaVariant) -> bool {
+        self.name == variant.name
+            && self.tag == variant.tag
+            && self.size == variant.size
+            && self.fields.len() == variant.fields.len()
+            && self
+                .fields
+                .iter()
+                .zip(variant.fields)
+                .all(|(field, other)| field.matches(other))
+    }
+
+    /// Keep the variant for the rest of the program
+    fn leak(self) -> SchemaVariant {
+        SchemaVariant {
+            name: self.name.leak(),
+            tag: self.tag,
+            size: self.size,
+            fields: self
+                .fields
+                .into_iter()
+                .map(DraftField::leak)
+                .collect::<Vec<_>>()
+                .leak(),
+        }
+    }
+}
+
+/// Describe each field with its offset, starting after `base` bytes, like the `Serialize` derive.
+/// Returns the fields along with the offset following the last value.
+fn describe_fields(
+    fields: &[(&str, &str)],
+    base: usize,
+    path: &str,
+) -> Result<(Vec<DraftField>, Option<usize>), CodecError> {
+    let mut schemas = Vec::with_capacity(fields.len());
+    for (index, (name, atype)) in fields.iter().enumerate() {
+        let path = child(path, name);
+        if fields[..index].iter().any(|(other, _)| other == name) {
+            return Err(definition(&path, "duplicate field"));
+        }
+        schemas.push(Parser::parse(atype).map_err(|reason| definition(&path, &reason))?);
+    }
+
+    // flags go into the bitfield, the values follow each other after it
+    let flags = schemas.iter().filter(|schema| schema.is_flag()).count();
+    let mut bit = 0;
+    let mut offset = Some(base + flags.div_ceil(8));
+    let mut described = Vec::with_capacity(fields.len());
+
+    for ((name, _), schema) in fields.iter().zip(schemas) {
+        let name = (*name).to_owned();
+        if schema.is_flag() {
+            described.push(DraftField {
+                name,
+                schema,
+                offset: Some(base + bit / 8),
+                bit: Some((bit % 8) as u8),
+            });
+            bit += 1;
+        } else {
+            let next = after(offset, schema.size())
+                .map_err(|reason| definition(&child(path, &name), &reason))?;
+            described.push(DraftField {
+                name,
+                schema,
+                offset,
+                bit: None,
+            });
+            offset = next;
+        }
+    }
+
+    Ok((described, offset))
+}
+
+/// Argument of a wrapper, either the wrapped type or a constant
+enum Argument {
+    Type(Draft),
+    Number(u32),
+}
+
+/// Read the type of a field, such as `Half<Vector3>` or `[u8; 4]`
+struct Parser<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    /// Read the whole text as a type
+    fn parse(text: &'a str) -> Result<Draft, String> {
+        let mut parser = Self { rest: text };
+        let schema = parser.schema()?;
+        match parser.rest.trim() {
+            "" => Ok(schema),
+            rest => Err(format!("unexpected {rest}")),
+        }
+    }
+
+    /// Skip the symbol if it comes next
+    fn eat(&mut self, symbol: char) -> bool {
+        self.rest = self.rest.trim_start();
+        match self.rest.strip_prefix(symbol) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Skip the symbol, failing if it does not come next
+    fn expect(&mut self, symbol: char) -> Result<(), String> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            Err(format!("expected {symbol}"))
+        }
+    }
+
+    /// Read a name or a number
+    fn word(&mut self) -> Result<&'a str, String> {
+        self.rest = self.rest.trim_start();
+        let end = self
+            .rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(self.rest.len());
+        if end == 0 {
+            return Err("expected a type".to_owned());
+        }
+        let (word, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        Ok(word)
+    }
+
+    /// Read a constant
+    fn number(&mut self) -> Result<u32, String> {
+        let word = self.word()?;
+        word.parse()
+            .map_err(|_| format!("expected a number instead of {word}"))
+    }
+
+    /// Read the argument of a wrapper
+    fn argument(&mut self) -> Result<Argument, String> {
+        self.rest = self.rest.trim_start();
+        if self.rest.starts_with(|c: char| c.is_ascii_digit()) {
+            self.number().map(Argument::Number)
+        } else {
+            self.schema().map(Argument::Type)
+        }
+    }
+
+    /// Read a type
+    fn schema(&mut self) -> Result<Draft, String> {
+        // arrays hold their item and their length
+        if self.eat('[') {
+            let item = self.schema()?;
+            self.expect(';')?;
+            let len = self.number()? as usize;
+            self.expect(']')?;
+            let size = match item.size() {
+                Some(size) => Some(
+                    size.checked_mul(len)
+                        .ok_or_else(|| format!("array of {len} items is too large"))?,
+                ),
+                None => None,
+            };
+            return Ok(Draft::New {
+                name: "array".to_owned(),
+                size,
+                kind: DraftKind::Array {
+                    item: Box::new(item),
+                    len,
+                },
+            });
+        }
+
+        // tuples hold their items
+        if self.eat('(') {
+            let mut items = Vec::new();
+            loop {
+                items.push(self.schema()?);
+                if self.eat(')') {
+                    break;
+                }
+                self.expect(',')?;
+                if self.eat(')') {
+                    break;
+                }
+            }
+            let size = items
+                .iter()
+                .try_fold(Some(0), |size, item| after(size, item.size()))?;
+            return Ok(Draft::New {
+                name: "tuple".to_owned(),
+                size,
+                kind: DraftKind::Tuple { items },
+            });
+        }
+
+        let name = self.word()?;
+        if !self.eat('<') {
+            return builtin(name);
+        }
+        let mut arguments = vec![self.argument()?];
+        while self.eat(',') {
+            arguments.push(self.argument()?);
+        }
+        self.expect('>')?;
+        wrapper(name, arguments)
+    }
+}
+
+/// Schema of the type with a dedicated encoding
+fn builtin(name: &str) -> Result<Draft, String> {
+    macro_rules! schema {
+        ($atype:ty) => {
+            Ok(Draft::Known(<$atype as PacketSchema>::SCHEMA))
+        };
+    }
+    for_builtin!(name, schema, {
+        match name {
+            "u64" => schema!(u64),
+            "NetworkId" => schema!(NetworkId),
+            "NetRef" => schema!(NetRef<Object>),
+            _ => Err(format!("unknown type {name}")),
+        }
+    })
+}
+
+/// Schema of the wrapper changing the encoding of its arguments
+fn wrapper(name: &str, arguments: Vec<Argument>) -> Result<Draft, String> {
+    macro_rules! schema {
+        ($wrap:ident, $atype:ty) => {
+            Ok(Draft::Known(<$wrap<$atype> as PacketSchema>::SCHEMA))
+        };
+        ($wrap:ty) => {
+            Ok(Draft::Known(<$wrap as PacketSchema>::SCHEMA))
+        };
+    }
+    let unsupported = || Err(format!("unsupported wrapper {name}"));
+
+    match (name, arguments.as_slice()) {
+        // angles wrap a real number
+        ("Angle", [Argument::Number(bits)]) => match bits {
+            8 => schema!(Angle<8>),
+            12 => schema!(Angle<12>),
+            16 => schema!(Angle<16>),
+            _ => Err(format!("angles use 8, 12 or 16 bits, not {bits}")),
+        },
+
+        // cells store the size along with the position
+        ("Cell", [Argument::Type(inner), Argument::Number(size)]) => {
+            if *size == 0 {
+                return Err("cells cannot have a size of zero".to_owned());
+            }
+            let cell_size = if inner.known() == Some(<Vector2 as PacketSchema>::SCHEMA) {
+                <Cell<Vector2, 1> as ConstSize>::SIZE
+            } else if inner.known() == Some(<Vector3 as PacketSchema>::SCHEMA) {
+                <Cell<Vector3, 1> as ConstSize>::SIZE
+            } else {
+                return unsupported();
+            };
+            let (size, Some(Argument::Type(inner))) = (*size, arguments.into_iter().next()) else {
+                return unsupported();
+            };
+            Ok(Draft::New {
+                name: "Cell".to_owned(),
+                size: Some(cell_size),
+                kind: DraftKind::Wrapper {
+                    inner: Box::new(inner),
+                    param: Some(size),
+                },
+            })
+        }
+
+        // 2D transforms wrap the tuple of their angle and origin
+        ("Rigid2D", [Argument::Type(_), Argument::Type(_)]) => {
+            let items = arguments
+                .into_iter()
+                .filter_map(|argument| match argument {
+                    Argument::Type(item) => Some(item),
+                    Argument::Number(_) => None,
+                })
+                .collect::<Vec<_>>();
+            let size = items
+                .iter()
+                .try_fold(Some(0), |size, item| after(size, item.size()))?;
+            let inner = Draft::New {
+                name: "tuple".to_owned(),
+                size,
+                kind: DraftKind::Tuple { items },
+            };
+            Ok(Draft::New {
+                name: "Rigid2D".to_owned(),
+                size: inner.size(),
+                kind: DraftKind::Wrapper {
+                    inner: Box::new(inner),
+                    param: None,
+                },
+            })
+        }
+
+        // flags wrap an unsigned integer
+        ("Flags", [Argument::Type(inner)]) => match inner.known() {
+            Some(inner) if inner.kind == SchemaKind::Builtin => match inner.name {
+                "u8" => schema!(Flags, u8),
+                "u16" => schema!(Flags, u16),
+                "u32" => schema!(Flags, u32),
+                "u64" => schema!(Flags, u64),
+                _ => unsupported(),
+            },
+            _ => unsupported(),
+        },
+
+        // relative positions are stored like half positions
+        ("Relative", [Argument::Type(_)]) => {
+            let half = wrapper("Half", arguments)?;
+            let Some(Schema {
+                size,
+                kind: SchemaKind::Wrapper { inner, param },
+                ..
+            }) = half.known()
+            else {
+                return unsupported();
+            };
+            Ok(Draft::New {
+                name: "Relative".to_owned(),
+                size: *size,
+                kind: DraftKind::Wrapper {
+                    inner: Box::new(Draft::Known(inner)),
+                    param: *param,
+                },
+            })
+        }
+
+        // other wrappers change the encoding of a builtin type
+        (_, [Argument::Type(inner)]) => match inner.known() {
+            Some(inner) if inner.kind == SchemaKind::Builtin => {
+                for_wrapper!(name, inner.name, None::<u32>, schema, unsupported())
+            }
+            _ => unsupported(),
+        },
+        _ => unsupported(),
+    }
+}
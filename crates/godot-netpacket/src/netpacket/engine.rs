@@ -0,0 +1,121 @@
+//!
+//! Define serialization and deserialization for godot engine enums and bitfields.
+//!
+
+use super::{ConstSize, Deserialize, Serialize};
+use godot::{
+    builtin::*,
+    global::{JoyAxis, JoyButton, Key, KeyModifierMask, MouseButton, MouseButtonMask},
+    obj::{EngineBitfield, EngineEnum},
+};
+
+/// Recover a closed enum from its ordinal, rejecting ordinals matching no enumerator
+#[inline]
+fn to_enum<T: EngineEnum>(ord: i32) -> Result<T, ()> {
+    // open enums accept any ordinal, an unknown enumerator has no name
+    match T::try_from_ord(ord) {
+        Some(value) if !value.as_str().is_empty() => Ok(value),
+        _ => Err(()),
+    }
+}
+
+/// Recover a bitfield from its ordinal, rejecting bits matching no flag
+#[inline]
+fn to_bitfield<T: EngineBitfield + Eq + 'static>(ord: u64) -> Result<T, ()> {
+    let known = T::all_constants()
+        .iter()
+        .fold(0, |mask, constant| mask | constant.value().ord());
+    if ord & !known != 0 {
+        return Err(());
+    }
+    T::try_from_ord(ord).ok_or(())
+}
+
+/// Macro to quickly implement traits for network packets.
+macro_rules! impl_packet {
+    // implement for enum types
+    ( enum $enum:ty => $stored:ty ; $encode:ident ; $decode:ident ) => {
+        impl ConstSize for $enum {
+            const SIZE: usize = size_of::<$stored>();
+        }
+        impl Serialize for $enum {
+            #[inline]
+            fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
+                let ord = <$stored>::try_from(self.ord()).map_err(|_| ())?;
+                buffer.$encode(offset, ord)
+            }
+        }
+        impl Deserialize for $enum {
+            #[inline]
+            fn deserialize(buffer: &PackedByteArray, offset: usize) -> Result<Self, ()>
+            where
+                Self: Sized,
+            {
+                to_enum(buffer.$decode(offset)? as i32)
+            }
+        }
+    };
+
+    // implement for open enum types, any ordinal is valid
+    ( open enum $enum:ty => $stored:ty ; $encode:ident ; $decode:ident ) => {
+        impl ConstSize for $enum {
+            const SIZE: usize = size_of::<$stored>();
+        }
+        impl Serialize for $enum {
+            #[inline]
+            fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
+                let ord = <$stored>::try_from(self.ord()).map_err(|_| ())?;
+                buffer.$encode(offset, ord)
+            }
+        }
+        impl Deserialize for $enum {
+            #[inline]
+            fn deserialize(buffer: &PackedByteArray, offset: usize) -> Result<Self, ()>
+            where
+                Self: Sized,
+            {
+                Ok(<$enum>::from_ord(buffer.$decode(offset)? as i32))
+            }
+        }
+    };
+
+    // implement for bitfield types
+    ( bitfield $bitfield:ty => $stored:ty ; $encode:ident ; $decode:ident ) => {
+        impl ConstSize for $bitfield {
+            const SIZE: usize = size_of::<$stored>();
+        }
+        impl Serialize for $bitfield {
+            #[inline]
+            fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
+                let ord = <$stored>::try_from(self.ord()).map_err(|_| ())?;
+                buffer.$encode(offset, ord)
+            }
+        }
+        impl Deserialize for $bitfield {
+            #[inline]
+            fn deserialize(buffer: &PackedByteArray, offset: usize) -> Result<Self, ()>
+            where
+                Self: Sized,
+            {
+                to_bitfield(buffer.$decode(offset)? as u64)
+            }
+        }
+    };
+}
+
+// implement for enums, keycodes and key labels of other layouts are unicode code points without a name
+
+impl_packet![ open enum Key    => i32 ; encode_s32 ; decode_s32 ];
+impl_packet![ enum MouseButton => i8  ; encode_s8  ; decode_s8  ];
+impl_packet![ enum JoyButton   => i16 ; encode_s16 ; decode_s16 ];
+impl_packet![ enum JoyAxis     => i8  ; encode_s8  ; decode_s8  ];
+
+// implement for bitfields
+
+impl_packet![ bitfield KeyModifierMask => u32 ; encode_u32 ; decode_u32 ];
+impl_packet![ bitfield MouseButtonMask => u16 ; encode_u16 ; decode_u16 ];
+
+crate::impl_contextless![
+    [] Key, [] MouseButton, [] JoyButton, [] JoyAxis,
+    [] KeyModifierMask, [] MouseButtonMask,
+];
@@ -0,0 +1,359 @@
+//!
+//! Split payloads larger than the MTU into fragments, and reassemble them on the receiving side.
+//! The fragments are plain byte buffers, so they can be sent through any unreliable transport.
+//!
+
+use std::{collections::HashMap, mem::size_of};
+
+/// Size of the header preceding each fragment: the message identifier, the index of the fragment and the number of fragments
+pub const FRAGMENT_HEADER_SIZE: usize = 6;
+
+/// Time after which an incomplete message is dropped by default, in milliseconds
+pub const DEFAULT_REASSEMBLY_TIMEOUT: u64 = 1000;
+
+/// Bytes buffered by default for the incomplete messages
+pub const DEFAULT_REASSEMBLY_MEMORY: usize = 1 << 20;
+
+/// Error encountered when fragmenting or reassembling a message
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FragmentError {
+    #[error("The MTU of {0} bytes cannot hold the header of a fragment")]
+    MtuTooSmall(usize),
+
+    #[error("Message of {size} bytes needs more than {max} fragments")]
+    TooLarge { size: usize, max: usize },
+
+    #[error("Fragment is too short to contain a header")]
+    MissingHeader,
+
+    #[error("Fragment {index} is out of the {count} fragments of the message")]
+    InvalidIndex { index: u16, count: u16 },
+
+    #[error("Fragments of message {0} disagree on the number of fragments")]
+    CountMismatch(u16),
+
+    #[error("Message of {size} bytes exceeds the memory limit of {max} bytes")]
+    MemoryLimit { size: usize, max: usize },
+
+    #[error("Message of {count} fragments exceeds the limit of {max} fragments")]
+    TooManyFragments { count: u16, max: usize },
+
+    #[error("Fragment {index} of message {id} is empty while it is not the last one")]
+    EmptyFragment { id: u16, index: u16 },
+}
+
+/// Memory used by the slot of each fragment of a message being reassembled
+const SLOT_SIZE: usize = size_of::<Option<Vec<u8>>>();
+
+/// Header preceding each fragment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Header {
+    id: u16,
+    index: u16,
+    count: u16,
+}
+
+impl Header {
+    fn write(&self, fragment: &mut Vec<u8>) {
+        fragment.extend_from_slice(&self.id.to_le_bytes());
+        fragment.extend_from_slice(&self.index.to_le_bytes());
+        fragment.extend_from_slice(&self.count.to_le_bytes());
+    }
+
+    fn read(fragment: &[u8]) -> Result<Self, FragmentError> {
+        let word = |offset: usize| u16::from_le_bytes([fragment[offset], fragment[offset + 1]]);
+        if fragment.len() < FRAGMENT_HEADER_SIZE {
+            return Err(FragmentError::MissingHeader);
+        }
+        let header = Self {
+            id: word(0),
+            index: word(2),
+            count: word(4),
+        };
+        if header.index >= header.count {
+            return Err(FragmentError::InvalidIndex {
+                index: header.index,
+                count: header.count,
+            });
+        }
+        Ok(header)
+    }
+}
+
+/// Split payloads into fragments no larger than the MTU, each message getting a new identifier.
+/// Payloads fitting in a single fragment still get a header, so the receiver handles every packet the same way.
+#[derive(Debug, Clone)]
+pub struct Fragmenter {
+    mtu: usize,
+
+    /// Identifier of the next message
+    next_id: u16,
+}
+
+impl Default for Fragmenter {
+    fn default() -> Self {
+        Self {
+            mtu: super::DEFAULT_MTU,
+            next_id: 0,
+        }
+    }
+}
+
+impl Fragmenter {
+    /// Create a fragmenter writing fragments up to `DEFAULT_MTU` bytes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the largest fragment the fragmenter writes, header included
+    pub fn mtu(mut self, mtu: usize) -> Self {
+        self.mtu = mtu;
+        self
+    }
+
+    /// Split the payload into fragments, to be sent in any order
+    pub fn split(&mut self, payload: &[u8]) -> Result<Vec<Vec<u8>>, FragmentError> {
+        let body = self.mtu.saturating_sub(FRAGMENT_HEADER_SIZE);
+        if body == 0 {
+            return Err(FragmentError::MtuTooSmall(self.mtu));
+        }
+
+        // an empty payload is still sent as one fragment
+        let count = payload.len().div_ceil(body).max(1);
+        let count = u16::try_from(count).map_err(|_| FragmentError::TooLarge {
+            size: payload.len(),
+            max: u16::MAX as usize,
+        })?;
+
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        let fragments = (0..count)
+            .map(|index| {
+                let start = index as usize * body;
+                let chunk = &payload[start.min(payload.len())..(start + body).min(payload.len())];
+                let mut fragment = Vec::with_capacity(FRAGMENT_HEADER_SIZE + chunk.len());
+                Header { id, index, count }.write(&mut fragment);
+                fragment.extend_from_slice(chunk);
+                fragment
+            })
+            .collect();
+        Ok(fragments)
+    }
+}
+
+/// Message being reassembled
+#[derive(Debug, Clone)]
+struct Partial {
+    fragments: Vec<Option<Vec<u8>>>,
+
+    /// Number of fragments received
+    received: usize,
+
+    /// Bytes buffered for the message, including its slots
+    size: usize,
+
+    /// Time the first fragment was received
+    started: u64,
+}
+
+/// Reassemble the messages from their fragments, received in any order.
+/// Duplicated fragments are ignored, including the ones of messages completed recently.
+/// Incomplete messages are dropped once the timeout elapses, or when the buffered fragments exceed the memory limit,
+/// starting with the oldest message.
+/// Times are in milliseconds, such as the ones returned by `Time.get_ticks_msec`.
+#[derive(Debug, Clone)]
+pub struct Reassembler {
+    timeout: u64,
+    max_memory: usize,
+    mtu: usize,
+
+    /// Messages being reassembled
+    pending: HashMap<u16, Partial>,
+
+    /// Messages completed recently along with their completion time, to ignore their duplicates
+    completed: HashMap<u16, u64>,
+
+    /// Bytes buffered for the incomplete messages
+    memory: usize,
+
+    /// Time of the last expiry, received fragments expire the messages at most once per tick
+    expired: u64,
+}
+
+impl Default for Reassembler {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_REASSEMBLY_TIMEOUT,
+            max_memory: DEFAULT_REASSEMBLY_MEMORY,
+            mtu: super::DEFAULT_MTU,
+            pending: HashMap::new(),
+            completed: HashMap::new(),
+            memory: 0,
+            expired: 0,
+        }
+    }
+}
+
+impl Reassembler {
+    /// Create a reassembler with `DEFAULT_REASSEMBLY_TIMEOUT` and `DEFAULT_REASSEMBLY_MEMORY`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the time after which an incomplete message is dropped, in milliseconds
+    pub fn timeout(mut self, timeout: u64) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the bytes buffered for the incomplete messages
+    pub fn max_memory(mut self, max_memory: usize) -> Self {
+        self.max_memory = max_memory;
+        self
+    }
+
+    /// Set the MTU of the remote fragmenter, messages with more fragments than the memory limit can hold are rejected
+    pub fn mtu(mut self, mtu: usize) -> Self {
+        self.mtu = mtu;
+        self
+    }
+
+    /// Largest number of fragments of a message fitting in the memory limit,
+    /// every fragment but the last one being filled up to the MTU
+    pub fn max_fragments(&self) -> usize {
+        let body = self.mtu.saturating_sub(FRAGMENT_HEADER_SIZE).max(1);
+        self.max_memory.div_ceil(body + SLOT_SIZE).max(1)
+    }
+
+    /// Number of incomplete messages
+    #[inline]
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Bytes buffered for the incomplete messages, including the slots of their fragments
+    #[inline]
+    pub fn memory(&self) -> usize {
+        self.memory
+    }
+
+    /// Add the received fragment, returns the payload of the message once all its fragments are received
+    pub fn receive(&mut self, fragment: &[u8], now: u64) -> Result<Option<Vec<u8>>, FragmentError> {
+        if now > self.expired {
+            self.expire(now);
+        }
+        let header = Header::read(fragment)?;
+        let body = &fragment[FRAGMENT_HEADER_SIZE..];
+        if self.completed.contains_key(&header.id) {
+            return Ok(None);
+        }
+
+        // a single fragment does not need to be buffered
+        if header.count == 1 && !self.pending.contains_key(&header.id) {
+            self.completed.insert(header.id, now);
+            return Ok(Some(body.to_vec()));
+        }
+
+        // hostile fragments could allocate slots for many fragments without sending any byte
+        if body.is_empty() && header.index + 1 < header.count {
+            return Err(FragmentError::EmptyFragment {
+                id: header.id,
+                index: header.index,
+            });
+        }
+        if header.count as usize > self.max_fragments() {
+            return Err(FragmentError::TooManyFragments {
+                count: header.count,
+                max: self.max_fragments(),
+            });
+        }
+
+        let slots = match self.pending.get(&header.id) {
+            Some(partial) if partial.fragments.len() != header.count as usize => {
+                return Err(FragmentError::CountMismatch(header.id));
+            }
+            Some(partial) if partial.fragments[header.index as usize].is_some() => {
+                return Ok(None);
+            }
+            Some(_) => 0,
+            None => header.count as usize * SLOT_SIZE,
+        };
+
+        // the message is dropped when it cannot fit even alone
+        let size = slots + body.len();
+        if !self.evict(size, header.id) {
+            let buffered = match self.pending.remove(&header.id) {
+                Some(partial) => {
+                    self.memory -= partial.size;
+                    partial.size
+                }
+                None => 0,
+            };
+            return Err(FragmentError::MemoryLimit {
+                size: buffered + size,
+                max: self.max_memory,
+            });
+        }
+
+        // the slots are accounted before they are allocated
+        self.memory += slots;
+        let partial = self.pending.entry(header.id).or_insert_with(|| Partial {
+            fragments: vec![None; header.count as usize],
+            received: 0,
+            size: slots,
+            started: now,
+        });
+        partial.fragments[header.index as usize] = Some(body.to_vec());
+        partial.received += 1;
+        partial.size += body.len();
+        self.memory += body.len();
+        if partial.received < partial.fragments.len() {
+            return Ok(None);
+        }
+
+        // every fragment was received
+        let Some(partial) = self.pending.remove(&header.id) else {
+            return Ok(None);
+        };
+        self.memory -= partial.size;
+        self.completed.insert(header.id, now);
+        Ok(Some(
+            partial.fragments.into_iter().flatten().flatten().collect(),
+        ))
+    }
+
+    /// Drop the incomplete messages whose timeout elapsed, returns the number of messages dropped
+    pub fn expire(&mut self, now: u64) -> usize {
+        self.expired = self.expired.max(now);
+        let timeout = self.timeout;
+        let before = self.pending.len();
+        self.pending.retain(|_, partial| {
+            let alive = now.saturating_sub(partial.started) < timeout;
+            if !alive {
+                self.memory -= partial.size;
+            }
+            alive
+        });
+        self.completed
+            .retain(|_, completed| now.saturating_sub(*completed) < timeout);
+        before - self.pending.len()
+    }
+
+    /// Drop the oldest incomplete messages, other than the one receiving the fragment, until the fragment fits.
+    /// Returns whether the fragment fits.
+    fn evict(&mut self, size: usize, receiving: u16) -> bool {
+        while self.memory + size > self.max_memory {
+            let oldest = self
+                .pending
+                .iter()
+                .filter(|(id, _)| **id != receiving)
+                .min_by_key(|(id, partial)| (partial.started, **id))
+                .map(|(id, _)| *id);
+            match oldest.and_then(|oldest| self.pending.remove(&oldest)) {
+                Some(partial) => self.memory -= partial.size,
+                None => return false,
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,289 @@
+//!
+//! Split a stream such as `StreamPeerTCP` into frames, each prefixed by its length stored as a varint.
+//!
+
+use super::{
+    DeserializeWith, PacketId, PacketRegistry, SerializeWith, check, decode_varint, encode_varint,
+    varint_size,
+};
+use godot::{
+    builtin::*,
+    classes::StreamPeer,
+    global::Error,
+    obj::{Gd, Inherits},
+};
+
+/// Largest frame accepted by default, in bytes
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 1 << 20;
+
+/// Error encountered when framing packets on a stream
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameError {
+    #[error("Frame of {size} bytes exceeds the limit of {max} bytes")]
+    TooLarge { size: u64, max: usize },
+
+    #[error("The length of the frame is not a valid varint")]
+    InvalidLength,
+
+    #[error("Failed to serialize the packet")]
+    Serialize,
+
+    #[error("Failed to deserialize the packet")]
+    Deserialize,
+
+    #[error("The frame has {0} bytes left after its content")]
+    TrailingBytes(usize),
+
+    #[error("Frame is too short to contain a header")]
+    MissingHeader,
+
+    #[error("Expected packet identifier {expected}, found {found}")]
+    UnexpectedId { expected: u16, found: u16 },
+
+    #[error("The stream failed with {0:?}")]
+    Godot(Error),
+}
+
+/// Write the frames sent on a stream.
+/// Frames written with `encode_packet` start with the header identifying the packet type,
+/// so that they can be dispatched by a `PacketRegistry`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameEncoder {
+    max_size: usize,
+}
+
+impl Default for FrameEncoder {
+    fn default() -> Self {
+        Self {
+            max_size: DEFAULT_MAX_FRAME_SIZE,
+        }
+    }
+}
+
+impl FrameEncoder {
+    /// Create an encoder accepting frames up to `DEFAULT_MAX_FRAME_SIZE` bytes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the largest frame the encoder writes, it should match the limit of the remote decoder
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Prefix the payload with its length
+    pub fn frame(&self, payload: &PackedByteArray) -> Result<PackedByteArray, FrameError> {
+        let size = payload.len();
+        if size > self.max_size {
+            return Err(FrameError::TooLarge {
+                size: size as u64,
+                max: self.max_size,
+            });
+        }
+
+        let prefix = varint_size(size as u64);
+        let mut buffer = PackedByteArray::new();
+        buffer.resize(prefix);
+        encode_varint(&mut buffer, 0, size as u64).map_err(|_| FrameError::Serialize)?;
+        buffer.extend_array(payload);
+        Ok(buffer)
+    }
+
+    /// Serialize the value into a frame
+    pub fn encode<T: SerializeWith<()>>(&self, value: &T) -> Result<PackedByteArray, FrameError> {
+        self.encode_with(&mut (), value)
+    }
+
+    /// Serialize the value into a frame using the context
+    pub fn encode_with<C, T: SerializeWith<C>>(
+        &self,
+        ctx: &mut C,
+        value: &T,
+    ) -> Result<PackedByteArray, FrameError> {
+        let mut payload = PackedByteArray::new();
+        value
+            .serialize_with(ctx, &mut payload, 0)
+            .map_err(|_| FrameError::Serialize)?;
+        self.frame(&payload)
+    }
+
+    /// Serialize the packet into a frame, after the header identifying its type
+    pub fn encode_packet<T>(&self, packet: &T) -> Result<PackedByteArray, FrameError>
+    where
+        T: PacketId + SerializeWith<()>,
+    {
+        self.encode_packet_with(&mut (), packet)
+    }
+
+    /// Serialize the packet into a frame using the context, after the header identifying its type
+    pub fn encode_packet_with<C, T>(
+        &self,
+        ctx: &mut C,
+        packet: &T,
+    ) -> Result<PackedByteArray, FrameError>
+    where
+        T: PacketId + SerializeWith<C>,
+    {
+        let payload = PacketRegistry::encode(ctx, packet).map_err(|_| FrameError::Serialize)?;
+        self.frame(&payload)
+    }
+
+    /// Write the frame to the stream, waiting until it is fully sent
+    pub fn write<S: Inherits<StreamPeer>>(
+        &self,
+        stream: &mut Gd<S>,
+        frame: &PackedByteArray,
+    ) -> Result<(), FrameError> {
+        check(stream.upcast_mut::<StreamPeer>().put_data(frame)).map_err(FrameError::Godot)
+    }
+}
+
+/// Read the frames received on a stream, accumulating the bytes until a whole frame is available.
+/// A frame exceeding the limit is an error the stream cannot recover from, the connection should be closed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameDecoder {
+    max_size: usize,
+
+    /// Bytes received and not read yet
+    pending: Vec<u8>,
+}
+
+impl Default for FrameDecoder {
+    fn default() -> Self {
+        Self {
+            max_size: DEFAULT_MAX_FRAME_SIZE,
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl FrameDecoder {
+    /// Create a decoder accepting frames up to `DEFAULT_MAX_FRAME_SIZE` bytes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the largest frame the decoder accepts
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Number of bytes received and not read yet
+    #[inline]
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Append the received bytes
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.pending.extend_from_slice(bytes);
+    }
+
+    /// Append the bytes available on the stream without waiting, returns the number of bytes read
+    pub fn read_from<S: Inherits<StreamPeer>>(
+        &mut self,
+        stream: &mut Gd<S>,
+    ) -> Result<usize, FrameError> {
+        let stream = stream.upcast_mut::<StreamPeer>();
+        let available = stream.get_available_bytes();
+        if available <= 0 {
+            return Ok(0);
+        }
+
+        // the result holds the error code followed by the bytes
+        let result = stream.get_partial_data(available);
+        let error = result.get(0).and_then(|code| code.try_to::<Error>().ok());
+        check(error.unwrap_or(Error::FAILED)).map_err(FrameError::Godot)?;
+        let bytes = result
+            .get(1)
+            .and_then(|bytes| bytes.try_to::<PackedByteArray>().ok())
+            .unwrap_or_default();
+        self.push(bytes.as_slice());
+        Ok(bytes.len())
+    }
+
+    /// Take the payload of the next complete frame, if any
+    pub fn next_frame(&mut self) -> Result<Option<PackedByteArray>, FrameError> {
+        // the length is read from the first bytes only
+        let head = self.pending.len().min(varint_size(u64::MAX));
+        let (size, prefix) = match decode_varint(&PackedByteArray::from(&self.pending[..head]), 0) {
+            Ok(length) => length,
+            Err(()) if head < varint_size(u64::MAX) => return Ok(None),
+            Err(()) => return Err(FrameError::InvalidLength),
+        };
+        if size > self.max_size as u64 {
+            return Err(FrameError::TooLarge {
+                size,
+                max: self.max_size,
+            });
+        }
+
+        let end = prefix + size as usize;
+        if self.pending.len() < end {
+            return Ok(None);
+        }
+        let payload = PackedByteArray::from(&self.pending[prefix..end]);
+        self.pending.drain(..end);
+        Ok(Some(payload))
+    }
+
+    /// Deserialize the value held by the next complete frame, if any
+    pub fn next_value<T: DeserializeWith<()>>(&mut self) -> Result<Option<T>, FrameError> {
+        self.next_value_with(&mut ())
+    }
+
+    /// Deserialize the value held by the next complete frame using the context, if any
+    pub fn next_value_with<C, T: DeserializeWith<C>>(
+        &mut self,
+        ctx: &mut C,
+    ) -> Result<Option<T>, FrameError> {
+        match self.next_frame()? {
+            Some(payload) => decode(ctx, &payload, 0).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Deserialize the packet held by the next complete frame, checking the header identifies its type
+    pub fn next_packet<T>(&mut self) -> Result<Option<T>, FrameError>
+    where
+        T: PacketId + DeserializeWith<()>,
+    {
+        self.next_packet_with(&mut ())
+    }
+
+    /// Deserialize the packet held by the next complete frame using the context,
+    /// checking the header identifies its type
+    pub fn next_packet_with<C, T>(&mut self, ctx: &mut C) -> Result<Option<T>, FrameError>
+    where
+        T: PacketId + DeserializeWith<C>,
+    {
+        let Some(payload) = self.next_frame()? else {
+            return Ok(None);
+        };
+        let found =
+            PacketRegistry::<C>::peek_id(&payload).map_err(|_| FrameError::MissingHeader)?;
+        if found != T::ID {
+            return Err(FrameError::UnexpectedId {
+                expected: T::ID,
+                found,
+            });
+        }
+        decode(ctx, &payload, PacketRegistry::<C>::HEADER_SIZE).map(Some)
+    }
+}
+
+/// Deserialize the value filling the rest of the payload
+fn decode<C, T: DeserializeWith<C>>(
+    ctx: &mut C,
+    payload: &PackedByteArray,
+    offset: usize,
+) -> Result<T, FrameError> {
+    let (value, size) =
+        T::deserialize_with(ctx, payload, offset).map_err(|_| FrameError::Deserialize)?;
+    match payload.len().saturating_sub(offset + size) {
+        0 => Ok(value),
+        left => Err(FrameError::TrailingBytes(left)),
+    }
+}
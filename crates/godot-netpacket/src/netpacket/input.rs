@@ -0,0 +1,383 @@
+//!
+//! Define serialization and deserialization for input events.
+//! An event is stored as a tag identifying its class followed by its properties.
+//!
+
+use super::{ConstSize, Deserialize, DynSize, Half, SNorm16, Serialize, UNorm8, Unit};
+use godot::{
+    builtin::*,
+    classes::{
+        InputEvent, InputEventAction, InputEventJoypadButton, InputEventJoypadMotion,
+        InputEventKey, InputEventMouseButton, InputEventMouseMotion, InputEventWithModifiers,
+    },
+    global::{JoyAxis, JoyButton, Key, MouseButton, MouseButtonMask},
+    obj::{Gd, Inherits, NewGd},
+};
+use godot_netpacket_macros::{ConstSize, Deserialize, Serialize};
+
+/// Modifier keys held when the event occurred
+#[derive(ConstSize, Serialize, Deserialize)]
+struct Modifiers {
+    shift: bool,
+    ctrl: bool,
+    alt: bool,
+    meta: bool,
+}
+
+impl Modifiers {
+    /// Read the modifiers of an event
+    fn read<T: Inherits<InputEventWithModifiers>>(event: &Gd<T>) -> Self {
+        let event = event.upcast_ref::<InputEventWithModifiers>();
+        Self {
+            shift: event.is_shift_pressed(),
+            ctrl: event.is_ctrl_pressed(),
+            alt: event.is_alt_pressed(),
+            meta: event.is_meta_pressed(),
+        }
+    }
+
+    /// Write the modifiers to an event
+    fn write<T: Inherits<InputEventWithModifiers>>(&self, event: &mut Gd<T>) {
+        let event = event.upcast_mut::<InputEventWithModifiers>();
+        event.set_shift_pressed(self.shift);
+        event.set_ctrl_pressed(self.ctrl);
+        event.set_alt_pressed(self.alt);
+        event.set_meta_pressed(self.meta);
+    }
+}
+
+/// Properties of an `InputEventKey`
+#[derive(ConstSize, Serialize, Deserialize)]
+struct KeyEvent {
+    device: i16,
+    modifiers: Modifiers,
+    pressed: bool,
+    echo: bool,
+    keycode: Key,
+    physical_keycode: Key,
+    key_label: Key,
+    unicode: u32,
+}
+
+/// Properties of an `InputEventMouseButton`
+#[derive(ConstSize, Serialize, Deserialize)]
+struct MouseButtonEvent {
+    device: i16,
+    modifiers: Modifiers,
+    pressed: bool,
+    canceled: bool,
+    double_click: bool,
+    button_index: MouseButton,
+    button_mask: MouseButtonMask,
+    position: Half<Vector2>,
+    global_position: Half<Vector2>,
+    factor: Half<f32>,
+}
+
+/// Properties of an `InputEventMouseMotion`
+#[derive(ConstSize, Serialize, Deserialize)]
+struct MouseMotionEvent {
+    device: i16,
+    modifiers: Modifiers,
+    pen_inverted: bool,
+    button_mask: MouseButtonMask,
+    position: Half<Vector2>,
+    global_position: Half<Vector2>,
+    relative: Half<Vector2>,
+    velocity: Half<Vector2>,
+    screen_relative: Half<Vector2>,
+    screen_velocity: Half<Vector2>,
+    pressure: UNorm8<f32>,
+    tilt: SNorm16<Vector2>,
+}
+
+/// Properties of an `InputEventJoypadButton`
+#[derive(ConstSize, Serialize, Deserialize)]
+struct JoypadButtonEvent {
+    device: i16,
+    pressed: bool,
+    button_index: JoyButton,
+    pressure: UNorm8<f32>,
+}
+
+/// Properties of an `InputEventJoypadMotion`
+#[derive(ConstSize, Serialize, Deserialize)]
+struct JoypadMotionEvent {
+    device: i16,
+    axis: JoyAxis,
+    axis_value: Unit<f32>,
+}
+
+/// Properties of an `InputEventAction`, the name of the action is stored after them
+#[derive(ConstSize, Serialize, Deserialize)]
+struct ActionEvent {
+    device: i16,
+    pressed: bool,
+    strength: Unit<f32>,
+}
+
+/// Supported input events
+enum Event {
+    Key(KeyEvent),
+    MouseButton(MouseButtonEvent),
+    MouseMotion(MouseMotionEvent),
+    JoypadButton(JoypadButtonEvent),
+    JoypadMotion(JoypadMotionEvent),
+    Action(ActionEvent, StringName),
+}
+
+/// Size of the tag identifying the event
+const TAG_SIZE: usize = 1;
+
+/// Convert the device identifier to its stored value
+#[inline]
+fn to_device(device: i32) -> Result<i16, ()> {
+    i16::try_from(device).map_err(|_| ())
+}
+
+impl Event {
+    /// Read the properties of an event, fails if its class is not supported
+    fn read(event: &Gd<InputEvent>) -> Result<Self, ()> {
+        let device = to_device(event.get_device())?;
+
+        if let Ok(key) = event.clone().try_cast::<InputEventKey>() {
+            return Ok(Self::Key(KeyEvent {
+                device,
+                modifiers: Modifiers::read(&key),
+                pressed: key.is_pressed(),
+                echo: key.is_echo(),
+                keycode: key.get_keycode(),
+                physical_keycode: key.get_physical_keycode(),
+                key_label: key.get_key_label(),
+                unicode: key.get_unicode(),
+            }));
+        }
+
+        if let Ok(button) = event.clone().try_cast::<InputEventMouseButton>() {
+            return Ok(Self::MouseButton(MouseButtonEvent {
+                device,
+                modifiers: Modifiers::read(&button),
+                pressed: button.is_pressed(),
+                canceled: button.is_canceled(),
+                double_click: button.is_double_click(),
+                button_index: button.get_button_index(),
+                button_mask: button.get_button_mask(),
+                position: Half(button.get_position()),
+                global_position: Half(button.get_global_position()),
+                factor: Half(button.get_factor()),
+            }));
+        }
+
+        if let Ok(motion) = event.clone().try_cast::<InputEventMouseMotion>() {
+            return Ok(Self::MouseMotion(MouseMotionEvent {
+                device,
+                modifiers: Modifiers::read(&motion),
+                pen_inverted: motion.get_pen_inverted(),
+                button_mask: motion.get_button_mask(),
+                position: Half(motion.get_position()),
+                global_position: Half(motion.get_global_position()),
+                relative: Half(motion.get_relative()),
+                velocity: Half(motion.get_velocity()),
+                screen_relative: Half(motion.get_screen_relative()),
+                screen_velocity: Half(motion.get_screen_velocity()),
+                pressure: UNorm8(motion.get_pressure()),
+                tilt: SNorm16(motion.get_tilt()),
+            }));
+        }
+
+        if let Ok(button) = event.clone().try_cast::<InputEventJoypadButton>() {
+            return Ok(Self::JoypadButton(JoypadButtonEvent {
+                device,
+                pressed: button.is_pressed(),
+                button_index: button.get_button_index(),
+                pressure: UNorm8(button.get_pressure()),
+            }));
+        }
+
+        if let Ok(motion) = event.clone().try_cast::<InputEventJoypadMotion>() {
+            return Ok(Self::JoypadMotion(JoypadMotionEvent {
+                device,
+                axis: motion.get_axis(),
+                axis_value: Unit(motion.get_axis_value()),
+            }));
+        }
+
+        if let Ok(action) = event.clone().try_cast::<InputEventAction>() {
+            let properties = ActionEvent {
+                device,
+                pressed: action.is_pressed(),
+                strength: Unit(action.get_strength()),
+            };
+            return Ok(Self::Action(properties, action.get_action()));
+        }
+
+        Err(())
+    }
+
+    /// Build the event object
+    fn build(self) -> Gd<InputEvent> {
+        match self {
+            Self::Key(properties) => {
+                let mut key = InputEventKey::new_gd();
+                key.set_device(properties.device as i32);
+                properties.modifiers.write(&mut key);
+                key.set_pressed(properties.pressed);
+                key.set_echo(properties.echo);
+                key.set_keycode(properties.keycode);
+                key.set_physical_keycode(properties.physical_keycode);
+                key.set_key_label(properties.key_label);
+                key.set_unicode(properties.unicode);
+                key.upcast()
+            }
+            Self::MouseButton(properties) => {
+                let mut button = InputEventMouseButton::new_gd();
+                button.set_device(properties.device as i32);
+                properties.modifiers.write(&mut button);
+                button.set_pressed(properties.pressed);
+                button.set_canceled(properties.canceled);
+                button.set_double_click(properties.double_click);
+                button.set_button_index(properties.button_index);
+                button.set_button_mask(properties.button_mask);
+                button.set_position(properties.position.0);
+                button.set_global_position(properties.global_position.0);
+                button.set_factor(properties.factor.0);
+                button.upcast()
+            }
+            Self::MouseMotion(properties) => {
+                let mut motion = InputEventMouseMotion::new_gd();
+                motion.set_device(properties.device as i32);
+                properties.modifiers.write(&mut motion);
+                motion.set_pen_inverted(properties.pen_inverted);
+                motion.set_button_mask(properties.button_mask);
+                motion.set_position(properties.position.0);
+                motion.set_global_position(properties.global_position.0);
+                motion.set_relative(properties.relative.0);
+                motion.set_velocity(properties.velocity.0);
+                motion.set_screen_relative(properties.screen_relative.0);
+                motion.set_screen_velocity(properties.screen_velocity.0);
+                motion.set_pressure(properties.pressure.0);
+                motion.set_tilt(properties.tilt.0);
+                motion.upcast()
+            }
+            Self::JoypadButton(properties) => {
+                let mut button = InputEventJoypadButton::new_gd();
+                button.set_device(properties.device as i32);
+                button.set_pressed(properties.pressed);
+                button.set_button_index(properties.button_index);
+                button.set_pressure(properties.pressure.0);
+                button.upcast()
+            }
+            Self::JoypadMotion(properties) => {
+                let mut motion = InputEventJoypadMotion::new_gd();
+                motion.set_device(properties.device as i32);
+                motion.set_axis(properties.axis);
+                motion.set_axis_value(properties.axis_value.0);
+                motion.upcast()
+            }
+            Self::Action(properties, name) => {
+                let mut action = InputEventAction::new_gd();
+                action.set_device(properties.device as i32);
+                action.set_pressed(properties.pressed);
+                action.set_strength(properties.strength.0);
+                action.set_action(&name);
+                action.upcast()
+            }
+        }
+    }
+
+    /// Tag identifying the class of the event
+    fn tag(&self) -> u8 {
+        match self {
+            Self::Key(_) => 0,
+            Self::MouseButton(_) => 1,
+            Self::MouseMotion(_) => 2,
+            Self::JoypadButton(_) => 3,
+            Self::JoypadMotion(_) => 4,
+            Self::Action(_, _) => 5,
+        }
+    }
+}
+
+impl DynSize for Event {
+    fn size(&self) -> usize {
+        TAG_SIZE
+            + match self {
+                Self::Key(_) => KeyEvent::SIZE,
+                Self::MouseButton(_) => MouseButtonEvent::SIZE,
+                Self::MouseMotion(_) => MouseMotionEvent::SIZE,
+                Self::JoypadButton(_) => JoypadButtonEvent::SIZE,
+                Self::JoypadMotion(_) => JoypadMotionEvent::SIZE,
+                Self::Action(_, name) => ActionEvent::SIZE + name.size(),
+            }
+    }
+}
+
+impl Serialize for Event {
+    fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
+        buffer.encode_u8(offset, self.tag())?;
+
+        let offset = offset + TAG_SIZE;
+        match self {
+            Self::Key(properties) => properties.serialize(buffer, offset),
+            Self::MouseButton(properties) => properties.serialize(buffer, offset),
+            Self::MouseMotion(properties) => properties.serialize(buffer, offset),
+            Self::JoypadButton(properties) => properties.serialize(buffer, offset),
+            Self::JoypadMotion(properties) => properties.serialize(buffer, offset),
+            Self::Action(properties, name) => {
+                properties.serialize(buffer, offset)?;
+                name.serialize(buffer, offset + ActionEvent::SIZE)
+            }
+        }
+    }
+}
+
+impl Deserialize for Event {
+    fn deserialize(buffer: &PackedByteArray, offset: usize) -> Result<Self, ()>
+    where
+        Self: Sized,
+    {
+        let tag = buffer.decode_u8(offset)?;
+
+        let offset = offset + TAG_SIZE;
+        match tag {
+            0 => KeyEvent::deserialize(buffer, offset).map(Self::Key),
+            1 => MouseButtonEvent::deserialize(buffer, offset).map(Self::MouseButton),
+            2 => MouseMotionEvent::deserialize(buffer, offset).map(Self::MouseMotion),
+            3 => JoypadButtonEvent::deserialize(buffer, offset).map(Self::JoypadButton),
+            4 => JoypadMotionEvent::deserialize(buffer, offset).map(Self::JoypadMotion),
+            5 => {
+                let properties = ActionEvent::deserialize(buffer, offset)?;
+                let name = StringName::deserialize(buffer, offset + ActionEvent::SIZE)?;
+                Ok(Self::Action(properties, name))
+            }
+            _ => Err(()),
+        }
+    }
+}
+
+// implement for input events, unsupported classes fail to serialize
+
+impl DynSize for Gd<InputEvent> {
+    /// Size of the event, zero if its class is not supported
+    #[inline]
+    fn size(&self) -> usize {
+        Event::read(self).map_or(0, |event| event.size())
+    }
+}
+impl Serialize for Gd<InputEvent> {
+    #[inline]
+    fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
+        Event::read(self)?.serialize(buffer, offset)
+    }
+}
+impl Deserialize for Gd<InputEvent> {
+    #[inline]
+    fn deserialize(buffer: &PackedByteArray, offset: usize) -> Result<Self, ()>
+    where
+        Self: Sized,
+    {
+        Event::deserialize(buffer, offset).map(Event::build)
+    }
+}
+
+crate::impl_contextless![[] Gd<InputEvent>];
@@ -0,0 +1,286 @@
+//!
+//! Intern strings repeated across packets of a connection.
+//! The first occurrence of a string is sent inline along with a new identifier,
+//! later occurrences only send the identifier.
+//! Identifiers are tagged with the epoch of the table, so that packets sent before a reset cannot be misread.
+//!
+
+use super::{
+    DeserializeWith, SerializeWith, decode_varint, encode_varint, reserve,
+    string::{read_text, text_size, write_text},
+    varint_size,
+};
+use godot::builtin::*;
+use std::collections::HashMap;
+
+/// Number of bits of the epoch stored along with each identifier
+const EPOCH_BITS: u32 = 4;
+
+/// Mask of the epoch, which wraps around
+const EPOCH_MASK: u8 = (1 << EPOCH_BITS) - 1;
+
+/// Number of strings a table can intern by default in each direction
+pub const DEFAULT_MAX_INTERNED: u32 = 1 << 16;
+
+/// Table of interned strings of a connection, kept in sync by the sender and the receiver.
+///
+/// On unreliable channels a string is sent inline until the receiver acknowledges its identifier:
+/// the receiver sends back the identifiers returned by `take_acks` and the sender passes them to `acknowledge`.
+/// On reliable channels a string is considered known by the receiver as soon as it is sent.
+///
+/// Each identifier carries the epoch of the sender, incremented by `reset`.
+/// The receiver forgets its strings when it sees a newer epoch, and rejects the references of older epochs.
+/// Strings sent inline stay readable across epochs.
+#[derive(Debug, Clone)]
+pub struct InternTable {
+    /// Identifiers of the strings sent, and whether the receiver knows them
+    sent: HashMap<String, (u32, bool)>,
+
+    /// Identifier of the next string sent
+    next: u32,
+
+    /// Epoch of the strings sent
+    epoch: u8,
+
+    /// Epoch of the strings received
+    received_epoch: u8,
+
+    /// Number of strings interned in each direction, both peers must use the same limit
+    max_strings: u32,
+
+    /// Strings received from the sender
+    received: HashMap<u32, String>,
+
+    /// Identifiers received since the last acknowledgement
+    acks: Vec<(u8, u32)>,
+
+    /// Consider strings known by the receiver as soon as they are sent
+    reliable: bool,
+}
+
+impl InternTable {
+    /// Create a table for an unreliable channel, strings are sent inline until acknowledged
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the number of strings interned in each direction, the identifiers beyond are rejected
+    pub fn max_strings(mut self, max_strings: u32) -> Self {
+        self.max_strings = max_strings;
+        self
+    }
+
+    /// Create a table for a reliable channel, strings are sent inline only once
+    pub fn reliable() -> Self {
+        Self {
+            reliable: true,
+            ..Self::default()
+        }
+    }
+
+    /// Mark the string with the given epoch and identifier as known by the receiver,
+    /// acknowledgements of a previous epoch are ignored
+    pub fn acknowledge(&mut self, epoch: u8, id: u32) {
+        if epoch != self.epoch {
+            return;
+        }
+        if let Some(entry) = self.sent.values_mut().find(|(sent, _)| *sent == id) {
+            entry.1 = true;
+        }
+    }
+
+    /// Take the epochs and identifiers of the strings received inline since the last call, to be sent back
+    pub fn take_acks(&mut self) -> Vec<(u8, u32)> {
+        std::mem::take(&mut self.acks)
+    }
+
+    /// Forget the strings sent and start a new epoch.
+    /// The receiver forgets them as soon as it reads a string of the new epoch,
+    /// the peers do not need to reset at the same time.
+    pub fn reset(&mut self) {
+        self.sent.clear();
+        self.next = 0;
+        self.epoch = (self.epoch + 1) & EPOCH_MASK;
+    }
+
+    /// Epoch of the strings sent
+    #[inline]
+    pub fn epoch(&self) -> u8 {
+        self.epoch
+    }
+
+    /// Number of strings sent
+    pub fn sent_len(&self) -> usize {
+        self.sent.len()
+    }
+
+    /// Number of strings received
+    pub fn received_len(&self) -> usize {
+        self.received.len()
+    }
+
+    /// Get the identifier of the string and whether it must be sent inline,
+    /// fails once the table holds `max_strings` strings
+    fn intern(&mut self, text: &str) -> Result<(u32, bool), ()> {
+        if let Some((id, known)) = self.sent.get(text) {
+            return Ok((*id, !known));
+        }
+
+        let id = self.next;
+        if id >= self.max_strings {
+            return Err(());
+        }
+        self.next = id.checked_add(1).ok_or(())?;
+        self.sent.insert(text.to_owned(), (id, self.reliable));
+        Ok((id, true))
+    }
+
+    /// Check the epoch of a string received, forgetting the strings of the previous epoch when a newer one starts.
+    /// Returns whether the string belongs to the current epoch.
+    fn enter(&mut self, epoch: u8) -> bool {
+        // half of the epochs ahead are considered newer, the other half older
+        match epoch.wrapping_sub(self.received_epoch) & EPOCH_MASK {
+            0 => true,
+            ahead if ahead <= EPOCH_MASK / 2 => {
+                self.received.clear();
+                self.acks.clear();
+                self.received_epoch = epoch;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Store a string received inline
+    fn learn(&mut self, id: u32, text: &str) -> Result<(), ()> {
+        if id >= self.max_strings {
+            return Err(());
+        }
+        self.received.insert(id, text.to_owned());
+        if !self.reliable {
+            self.acks.push((self.received_epoch, id));
+        }
+        Ok(())
+    }
+
+    /// Get a string received previously
+    fn lookup(&self, id: u32) -> Option<&str> {
+        self.received.get(&id).map(String::as_str)
+    }
+}
+
+impl Default for InternTable {
+    fn default() -> Self {
+        Self {
+            sent: HashMap::new(),
+            next: 0,
+            epoch: 0,
+            received_epoch: 0,
+            max_strings: DEFAULT_MAX_INTERNED,
+            received: HashMap::new(),
+            acks: Vec::new(),
+            reliable: false,
+        }
+    }
+}
+
+/// A table can be used directly as a context
+impl AsMut<InternTable> for InternTable {
+    fn as_mut(&mut self) -> &mut InternTable {
+        self
+    }
+}
+
+/// Encapsulate strings to be interned using the `InternTable` provided by the context.
+/// Each string is stored as a varint holding its identifier, the epoch of the table and whether it is sent inline,
+/// followed by the string itself if needed.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Interned<T>(pub T);
+
+/// Write the interned text
+fn write_interned(
+    table: &mut InternTable,
+    buffer: &mut PackedByteArray,
+    offset: usize,
+    text: &str,
+) -> Result<usize, ()> {
+    let (id, inline) = table.intern(text)?;
+    let tag = ((id as u64) << EPOCH_BITS) | table.epoch as u64;
+    let header = (tag << 1) | inline as u64;
+
+    let size = varint_size(header) + if inline { text_size(text) } else { 0 };
+    reserve(buffer, offset + size);
+
+    let written = encode_varint(buffer, offset, header)?;
+    if inline {
+        write_text(buffer, offset + written, text)?;
+    }
+    Ok(size)
+}
+
+/// Read the interned text
+fn read_interned(
+    table: &mut InternTable,
+    buffer: &PackedByteArray,
+    offset: usize,
+) -> Result<(String, usize), ()> {
+    let (header, read) = decode_varint(buffer, offset)?;
+    let epoch = (header >> 1) as u8 & EPOCH_MASK;
+    let id = u32::try_from(header >> (1 + EPOCH_BITS)).map_err(|_| ())?;
+    let current = table.enter(epoch);
+
+    // references of another epoch could resolve to a different string
+    if header & 1 == 0 {
+        let text = current.then(|| table.lookup(id)).flatten().ok_or(())?;
+        return Ok((text.to_owned(), read));
+    }
+
+    let text = read_text(buffer, offset + read)?;
+    if current {
+        table.learn(id, text)?;
+    }
+    Ok((text.to_owned(), read + text_size(text)))
+}
+
+/// Macro to quickly implement traits for network packets.
+macro_rules! impl_interned {
+    ( $string:ty ) => {
+        impl<C> SerializeWith<C> for Interned<$string>
+        where
+            C: AsMut<InternTable>,
+        {
+            #[inline]
+            fn serialize_with(
+                &self,
+                ctx: &mut C,
+                buffer: &mut PackedByteArray,
+                offset: usize,
+            ) -> Result<usize, ()> {
+                write_interned(ctx.as_mut(), buffer, offset, &self.0.to_string())
+            }
+        }
+        impl<C> DeserializeWith<C> for Interned<$string>
+        where
+            C: AsMut<InternTable>,
+        {
+            #[inline]
+            fn deserialize_with(
+                ctx: &mut C,
+                buffer: &PackedByteArray,
+                offset: usize,
+            ) -> Result<(Self, usize), ()>
+            where
+                Self: Sized,
+            {
+                let (text, read) = read_interned(ctx.as_mut(), buffer, offset)?;
+                Ok((Self(<$string>::from(text.as_str())), read))
+            }
+        }
+    };
+}
+
+// implement for godot strings
+
+impl_interned![GString];
+impl_interned![StringName];
+impl_interned![NodePath];
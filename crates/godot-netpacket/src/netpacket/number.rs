@@ -3,12 +3,62 @@
 //!
 
 use super::{
-    ConstSize, Deserialize, HALF_SIZE, Half, Serialize, UNIT_SIZE, Unit, from_unit, to_unit,
+    Angle, ConstSize, Deserialize, Flags, HALF_SIZE, Half, SNorm16, Serialize, UNIT_SIZE, UNorm8,
+    UNorm16, Unit, from_angle, from_snorm16, from_unit, from_unorm8, from_unorm16, to_angle,
+    to_snorm16, to_unit, to_unorm8, to_unorm16,
 };
 use godot::builtin::*;
 
 /// Macro to quickly implement traits for network packets.
 macro_rules! impl_packet {
+    // implement angle on a given number of bits
+    ( Angle < $bits:literal > => $stored:ty ; $encode:ident ; $decode:ident ) => {
+        impl ConstSize for Angle<$bits> {
+            const SIZE: usize = size_of::<$stored>();
+        }
+        impl Serialize for Angle<$bits> {
+            #[inline]
+            fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
+                buffer.$encode(offset, to_angle(self.0, $bits) as $stored)
+            }
+        }
+        impl Deserialize for Angle<$bits> {
+            #[inline]
+            fn deserialize(buffer: &PackedByteArray, offset: usize) -> Result<Self, ()>
+            where
+                Self: Sized,
+            {
+                let step = buffer.$decode(offset)? as u32;
+                if step >> $bits != 0 {
+                    return Err(());
+                }
+                Ok(Self(from_angle(step, $bits)))
+            }
+        }
+    };
+
+    // implement set of bits stored as the integer itself
+    ( Flags < $num:ty > => $encode:ident ; $decode:ident ) => {
+        impl ConstSize for Flags<$num> {
+            const SIZE: usize = size_of::<$num>();
+        }
+        impl Serialize for Flags<$num> {
+            #[inline]
+            fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
+                buffer.$encode(offset, self.0)
+            }
+        }
+        impl Deserialize for Flags<$num> {
+            #[inline]
+            fn deserialize(buffer: &PackedByteArray, offset: usize) -> Result<Self, ()>
+            where
+                Self: Sized,
+            {
+                buffer.$decode(offset).map(Self)
+            }
+        }
+    };
+
     // implement for numeric types
     ( $num:ty => $stored:ty ; $encode:ident ; $decode:ident ) => {
         impl ConstSize for $num {
@@ -30,6 +80,28 @@ macro_rules! impl_packet {
             }
         }
     };
+
+    // implement normalized wrappers for floating point number
+    ( $wrap:ident < $num:ty > => $stored:ty ; $to:ident ; $from:ident ; $encode:ident ; $decode:ident ) => {
+        impl<const STRICT: bool> ConstSize for $wrap<$num, STRICT> {
+            const SIZE: usize = size_of::<$stored>();
+        }
+        impl<const STRICT: bool> Serialize for $wrap<$num, STRICT> {
+            #[inline]
+            fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
+                buffer.$encode(offset, $to(self.0, STRICT)?)
+            }
+        }
+        impl<const STRICT: bool> Deserialize for $wrap<$num, STRICT> {
+            #[inline]
+            fn deserialize(buffer: &PackedByteArray, offset: usize) -> Result<Self, ()>
+            where
+                Self: Sized,
+            {
+                buffer.$decode(offset).map(|num| Self($from(num)))
+            }
+        }
+    };
 }
 
 // implement for numeric types
@@ -47,6 +119,19 @@ impl_packet![ i64 => i64 ; encode_s64 ; decode_s64 ];
 impl_packet![ f32 => f32 ; encode_float  ; decode_float  ];
 impl_packet![ f64 => f64 ; encode_double ; decode_double ];
 
+crate::impl_contextless![
+    [] u8, [] u16, [] u32, [] u64,
+    [] i8, [] i16, [] i32, [] i64,
+    [] f32, [] f64,
+];
+
+// implement for sets of bits
+
+impl_packet![ Flags<u8>  => encode_u8  ; decode_u8  ];
+impl_packet![ Flags<u16> => encode_u16 ; decode_u16 ];
+impl_packet![ Flags<u32> => encode_u32 ; decode_u32 ];
+impl_packet![ Flags<u64> => encode_u64 ; decode_u64 ];
+
 // implement unit for floating point number
 
 impl ConstSize for Unit<f32> {
@@ -88,3 +173,15 @@ impl Deserialize for Half<f32> {
         buffer.decode_half(offset).map(Self)
     }
 }
+
+// implement normalized wrappers for floating point number
+
+impl_packet![ UNorm8<f32>  => u8  ; to_unorm8  ; from_unorm8  ; encode_u8  ; decode_u8  ];
+impl_packet![ UNorm16<f32> => u16 ; to_unorm16 ; from_unorm16 ; encode_u16 ; decode_u16 ];
+impl_packet![ SNorm16<f32> => i16 ; to_snorm16 ; from_snorm16 ; encode_s16 ; decode_s16 ];
+
+// implement angles
+
+impl_packet![ Angle<8>  => u8  ; encode_u8  ; decode_u8  ];
+impl_packet![ Angle<12> => u16 ; encode_u16 ; decode_u16 ];
+impl_packet![ Angle<16> => u16 ; encode_u16 ; decode_u16 ];
@@ -0,0 +1,235 @@
+//!
+//! Identify objects shared between peers using compact network identifiers.
+//!
+
+use super::{ConstSize, Deserialize, DeserializeWith, Serialize, SerializeWith};
+use godot::{
+    builtin::*,
+    classes::Object,
+    obj::{Gd, GodotClass, Inherits, InstanceId},
+};
+use std::{collections::HashMap, fmt};
+
+/// Compact identifier of an object shared between two peers
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NetworkId(pub u32);
+
+impl ConstSize for NetworkId {
+    const SIZE: usize = u32::SIZE;
+}
+impl Serialize for NetworkId {
+    #[inline]
+    fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
+        self.0.serialize(buffer, offset)
+    }
+}
+impl Deserialize for NetworkId {
+    #[inline]
+    fn deserialize(buffer: &PackedByteArray, offset: usize) -> Result<Self, ()>
+    where
+        Self: Sized,
+    {
+        u32::deserialize(buffer, offset).map(Self)
+    }
+}
+
+/// Error encountered when resolving a network identifier
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetRefError {
+    #[error("Unknown network identifier {0:?}")]
+    Unknown(NetworkId),
+
+    #[error("Object with network identifier {0:?} was freed")]
+    Freed(NetworkId),
+
+    #[error("Object with network identifier {0:?} is not of the expected class")]
+    WrongClass(NetworkId),
+}
+
+/// Map live objects to network identifiers and back.
+/// Each peer owns one registry per remote peer, an identifier is only meaningful between these two peers.
+#[derive(Debug, Default)]
+pub struct NetworkObjectRegistry {
+    ids: HashMap<InstanceId, NetworkId>,
+    objects: HashMap<NetworkId, InstanceId>,
+    next: u32,
+}
+
+impl NetworkObjectRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the identifier of the object, allocating a new one if it is not registered yet
+    pub fn register<T: GodotClass>(&mut self, object: &Gd<T>) -> NetworkId {
+        self.register_instance(object.instance_id())
+    }
+
+    /// Get the identifier of the instance, allocating a new one if it is not registered yet
+    pub fn register_instance(&mut self, instance: InstanceId) -> NetworkId {
+        if let Some(id) = self.ids.get(&instance) {
+            return *id;
+        }
+
+        // skip identifiers still in use after wrapping around
+        let mut id = NetworkId(self.next);
+        while self.objects.contains_key(&id) {
+            id = NetworkId(id.0.wrapping_add(1));
+        }
+        self.next = id.0.wrapping_add(1);
+
+        self.bind(id, instance);
+        id
+    }
+
+    /// Bind an identifier chosen by the remote peer to a local instance, replacing any previous binding
+    pub fn insert(&mut self, id: NetworkId, instance: InstanceId) {
+        self.remove(id);
+        self.unregister_instance(instance);
+        self.bind(id, instance);
+    }
+
+    /// Remove the binding of the object, returns its identifier if it was registered
+    pub fn unregister<T: GodotClass>(&mut self, object: &Gd<T>) -> Option<NetworkId> {
+        self.unregister_instance(object.instance_id())
+    }
+
+    /// Remove the binding of the instance, returns its identifier if it was registered
+    pub fn unregister_instance(&mut self, instance: InstanceId) -> Option<NetworkId> {
+        let id = self.ids.remove(&instance)?;
+        self.objects.remove(&id);
+        Some(id)
+    }
+
+    /// Remove the binding of the identifier, returns its instance if it was bound
+    pub fn remove(&mut self, id: NetworkId) -> Option<InstanceId> {
+        let instance = self.objects.remove(&id)?;
+        self.ids.remove(&instance);
+        Some(instance)
+    }
+
+    /// Remove the bindings of freed objects
+    pub fn prune(&mut self) {
+        let freed: Vec<NetworkId> = self
+            .objects
+            .iter()
+            .filter(|(_, instance)| Gd::<Object>::try_from_instance_id(**instance).is_err())
+            .map(|(id, _)| *id)
+            .collect();
+        for id in freed {
+            self.remove(id);
+        }
+    }
+
+    /// Get the identifier of a registered instance
+    pub fn id_of(&self, instance: InstanceId) -> Option<NetworkId> {
+        self.ids.get(&instance).copied()
+    }
+
+    /// Get the instance bound to the identifier
+    pub fn instance_of(&self, id: NetworkId) -> Option<InstanceId> {
+        self.objects.get(&id).copied()
+    }
+
+    /// Get the live object bound to the identifier
+    pub fn resolve<T>(&self, id: NetworkId) -> Result<Gd<T>, NetRefError>
+    where
+        T: GodotClass + Inherits<Object>,
+    {
+        let instance = self.instance_of(id).ok_or(NetRefError::Unknown(id))?;
+        let object =
+            Gd::<Object>::try_from_instance_id(instance).map_err(|_| NetRefError::Freed(id))?;
+        object
+            .try_cast::<T>()
+            .map_err(|_| NetRefError::WrongClass(id))
+    }
+
+    /// Number of registered objects
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    /// Check if no object is registered
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    /// Bind the identifier and the instance to each other
+    fn bind(&mut self, id: NetworkId, instance: InstanceId) {
+        self.ids.insert(instance, id);
+        self.objects.insert(id, instance);
+    }
+}
+
+/// A registry can be used directly as a context
+impl AsMut<NetworkObjectRegistry> for NetworkObjectRegistry {
+    fn as_mut(&mut self) -> &mut NetworkObjectRegistry {
+        self
+    }
+}
+
+/// Reference to an object of class `T` stored as its network identifier.
+/// The registry provided by the context gives the identifier of the object when sending,
+/// registering it if needed, and resolves the identifier back to the object when receiving.
+pub struct NetRef<T: GodotClass>(pub Gd<T>);
+
+impl<T: GodotClass> fmt::Debug for NetRef<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("NetRef").field(&self.0).finish()
+    }
+}
+
+impl<T: GodotClass> Clone for NetRef<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T: GodotClass> PartialEq for NetRef<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<T: GodotClass> Eq for NetRef<T> {}
+
+impl<T: GodotClass> ConstSize for NetRef<T> {
+    const SIZE: usize = NetworkId::SIZE;
+}
+impl<C, T> SerializeWith<C> for NetRef<T>
+where
+    C: AsMut<NetworkObjectRegistry>,
+    T: GodotClass,
+{
+    fn serialize_with(
+        &self,
+        ctx: &mut C,
+        buffer: &mut PackedByteArray,
+        offset: usize,
+    ) -> Result<usize, ()> {
+        ctx.as_mut()
+            .register(&self.0)
+            .serialize_with(&mut (), buffer, offset)
+    }
+}
+impl<C, T> DeserializeWith<C> for NetRef<T>
+where
+    C: AsMut<NetworkObjectRegistry>,
+    T: GodotClass + Inherits<Object>,
+{
+    fn deserialize_with(
+        ctx: &mut C,
+        buffer: &PackedByteArray,
+        offset: usize,
+    ) -> Result<(Self, usize), ()>
+    where
+        Self: Sized,
+    {
+        let id = NetworkId::deserialize(buffer, offset)?;
+        let object = ctx.as_mut().resolve(id).map_err(|_| ())?;
+        Ok((Self(object), NetworkId::SIZE))
+    }
+}
+
+crate::impl_contextless![[] NetworkId];
@@ -2,7 +2,10 @@
 //! Define serialization and deserialization for other types.
 //!
 
-use super::{ConstSize, Deserialize, Half, Serialize, Unit};
+use super::{
+    ConstSize, Deserialize, Full, HALF_SIZE, Half, Rgb, Rgb565, Rgba4444, Serialize, Unit,
+    from_channel, to_channel,
+};
 use godot::builtin::*;
 
 // implement for boolean
@@ -107,12 +110,10 @@ impl ConstSize for Color {
 impl Serialize for Color {
     #[inline]
     fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
-        const FACTOR: f32 = 255.0;
-
-        buffer.encode_u8(offset, (self.r * FACTOR) as u8)?;
-        buffer.encode_u8(offset + 1, (self.g * FACTOR) as u8)?;
-        buffer.encode_u8(offset + 2, (self.b * FACTOR) as u8)?;
-        buffer.encode_u8(offset + 3, (self.a * FACTOR) as u8)?;
+        buffer.encode_u8(offset, to_channel(self.r, 255) as u8)?;
+        buffer.encode_u8(offset + 1, to_channel(self.g, 255) as u8)?;
+        buffer.encode_u8(offset + 2, to_channel(self.b, 255) as u8)?;
+        buffer.encode_u8(offset + 3, to_channel(self.a, 255) as u8)?;
         Ok(())
     }
 }
@@ -122,13 +123,155 @@ impl Deserialize for Color {
     where
         Self: Sized,
     {
-        const FACTOR: f32 = 1.0 / 255.0;
-
-        let r = buffer.decode_u8(offset)? as f32 * FACTOR;
-        let g = buffer.decode_u8(offset + 1)? as f32 * FACTOR;
-        let b = buffer.decode_u8(offset + 2)? as f32 * FACTOR;
-        let a = buffer.decode_u8(offset + 3)? as f32 * FACTOR;
+        let r = from_channel(buffer.decode_u8(offset)? as u16, 255);
+        let g = from_channel(buffer.decode_u8(offset + 1)? as u16, 255);
+        let b = from_channel(buffer.decode_u8(offset + 2)? as u16, 255);
+        let a = from_channel(buffer.decode_u8(offset + 3)? as u16, 255);
 
         Ok(Color { r, g, b, a })
     }
 }
+
+impl ConstSize for Half<Color> {
+    const SIZE: usize = HALF_SIZE * 4;
+}
+impl Serialize for Half<Color> {
+    #[inline]
+    fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
+        buffer.encode_half(offset, self.0.r)?;
+        buffer.encode_half(offset + HALF_SIZE, self.0.g)?;
+        buffer.encode_half(offset + (HALF_SIZE * 2), self.0.b)?;
+        buffer.encode_half(offset + (HALF_SIZE * 3), self.0.a)?;
+        Ok(())
+    }
+}
+impl Deserialize for Half<Color> {
+    #[inline]
+    fn deserialize(buffer: &PackedByteArray, offset: usize) -> Result<Self, ()>
+    where
+        Self: Sized,
+    {
+        let r = buffer.decode_half(offset)?;
+        let g = buffer.decode_half(offset + HALF_SIZE)?;
+        let b = buffer.decode_half(offset + (HALF_SIZE * 2))?;
+        let a = buffer.decode_half(offset + (HALF_SIZE * 3))?;
+
+        Ok(Half(Color { r, g, b, a }))
+    }
+}
+
+impl ConstSize for Full<Color> {
+    const SIZE: usize = f32::SIZE * 4;
+}
+impl Serialize for Full<Color> {
+    #[inline]
+    fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
+        const SIZE: usize = f32::SIZE;
+
+        buffer.encode_float(offset, self.0.r)?;
+        buffer.encode_float(offset + SIZE, self.0.g)?;
+        buffer.encode_float(offset + (SIZE * 2), self.0.b)?;
+        buffer.encode_float(offset + (SIZE * 3), self.0.a)?;
+        Ok(())
+    }
+}
+impl Deserialize for Full<Color> {
+    #[inline]
+    fn deserialize(buffer: &PackedByteArray, offset: usize) -> Result<Self, ()>
+    where
+        Self: Sized,
+    {
+        const SIZE: usize = f32::SIZE;
+
+        let r = buffer.decode_float(offset)?;
+        let g = buffer.decode_float(offset + SIZE)?;
+        let b = buffer.decode_float(offset + (SIZE * 2))?;
+        let a = buffer.decode_float(offset + (SIZE * 3))?;
+
+        Ok(Full(Color { r, g, b, a }))
+    }
+}
+
+impl ConstSize for Rgb<Color> {
+    const SIZE: usize = 3;
+}
+impl Serialize for Rgb<Color> {
+    #[inline]
+    fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
+        buffer.encode_u8(offset, to_channel(self.0.r, 255) as u8)?;
+        buffer.encode_u8(offset + 1, to_channel(self.0.g, 255) as u8)?;
+        buffer.encode_u8(offset + 2, to_channel(self.0.b, 255) as u8)?;
+        Ok(())
+    }
+}
+impl Deserialize for Rgb<Color> {
+    #[inline]
+    fn deserialize(buffer: &PackedByteArray, offset: usize) -> Result<Self, ()>
+    where
+        Self: Sized,
+    {
+        let r = from_channel(buffer.decode_u8(offset)? as u16, 255);
+        let g = from_channel(buffer.decode_u8(offset + 1)? as u16, 255);
+        let b = from_channel(buffer.decode_u8(offset + 2)? as u16, 255);
+
+        Ok(Rgb(Color { r, g, b, a: 1.0 }))
+    }
+}
+
+impl ConstSize for Rgb565<Color> {
+    const SIZE: usize = 2;
+}
+impl Serialize for Rgb565<Color> {
+    #[inline]
+    fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
+        let r = to_channel(self.0.r, 0x1F);
+        let g = to_channel(self.0.g, 0x3F);
+        let b = to_channel(self.0.b, 0x1F);
+        buffer.encode_u16(offset, (r << 11) | (g << 5) | b)
+    }
+}
+impl Deserialize for Rgb565<Color> {
+    #[inline]
+    fn deserialize(buffer: &PackedByteArray, offset: usize) -> Result<Self, ()>
+    where
+        Self: Sized,
+    {
+        let packed = buffer.decode_u16(offset)?;
+        let r = from_channel(packed >> 11, 0x1F);
+        let g = from_channel((packed >> 5) & 0x3F, 0x3F);
+        let b = from_channel(packed & 0x1F, 0x1F);
+
+        Ok(Rgb565(Color { r, g, b, a: 1.0 }))
+    }
+}
+
+impl ConstSize for Rgba4444<Color> {
+    const SIZE: usize = 2;
+}
+impl Serialize for Rgba4444<Color> {
+    #[inline]
+    fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
+        let r = to_channel(self.0.r, 0xF);
+        let g = to_channel(self.0.g, 0xF);
+        let b = to_channel(self.0.b, 0xF);
+        let a = to_channel(self.0.a, 0xF);
+        buffer.encode_u16(offset, (r << 12) | (g << 8) | (b << 4) | a)
+    }
+}
+impl Deserialize for Rgba4444<Color> {
+    #[inline]
+    fn deserialize(buffer: &PackedByteArray, offset: usize) -> Result<Self, ()>
+    where
+        Self: Sized,
+    {
+        let packed = buffer.decode_u16(offset)?;
+        let r = from_channel(packed >> 12, 0xF);
+        let g = from_channel((packed >> 8) & 0xF, 0xF);
+        let b = from_channel((packed >> 4) & 0xF, 0xF);
+        let a = from_channel(packed & 0xF, 0xF);
+
+        Ok(Rgba4444(Color { r, g, b, a }))
+    }
+}
+
+crate::impl_contextless![[] bool, [] Plane, [] Color];
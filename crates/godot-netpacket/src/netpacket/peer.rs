@@ -0,0 +1,174 @@
+//!
+//! Send and receive packets through the peers of the engine, such as `PacketPeerUDP`, `WebSocketPeer` or `ENetMultiplayerPeer`.
+//!
+
+use super::{DeserializeWith, SerializeWith, check};
+use godot::{
+    builtin::*,
+    classes::{MultiplayerPeer, PacketPeer, multiplayer_peer::TransferMode},
+    global::Error,
+    obj::{Gd, Inherits},
+};
+
+/// Error encountered when sending or receiving a packet
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeerError {
+    #[error("No packet is available")]
+    Empty,
+
+    #[error("Failed to serialize the packet")]
+    Serialize,
+
+    #[error("Failed to deserialize the packet")]
+    Deserialize,
+
+    #[error("The packet has {0} bytes left after its content")]
+    TrailingBytes(usize),
+
+    #[error("The peer failed with {0:?}")]
+    Godot(Error),
+}
+
+/// Serialize the value into a new buffer
+fn encode<C, T: SerializeWith<C>>(ctx: &mut C, value: &T) -> Result<PackedByteArray, PeerError> {
+    let mut buffer = PackedByteArray::new();
+    value
+        .serialize_with(ctx, &mut buffer, 0)
+        .map_err(|_| PeerError::Serialize)?;
+    Ok(buffer)
+}
+
+/// Deserialize the value filling the whole buffer
+fn decode<C, T: DeserializeWith<C>>(ctx: &mut C, buffer: &PackedByteArray) -> Result<T, PeerError> {
+    let (value, size) = T::deserialize_with(ctx, buffer, 0).map_err(|_| PeerError::Deserialize)?;
+    match buffer.len().saturating_sub(size) {
+        0 => Ok(value),
+        left => Err(PeerError::TrailingBytes(left)),
+    }
+}
+
+/// Send and receive packets through a `PacketPeer`, each packet holding a single value.
+/// The `_with` variants provide a context, such as an `InternTable`.
+pub trait PacketPeerExt {
+    /// Send the value as a packet
+    fn send<T: SerializeWith<()>>(&mut self, value: &T) -> Result<(), PeerError> {
+        self.send_with(&mut (), value)
+    }
+
+    /// Receive the next packet as a value
+    fn recv<T: DeserializeWith<()>>(&mut self) -> Result<T, PeerError> {
+        self.recv_with(&mut ())
+    }
+
+    /// Send the value as a packet using the context
+    fn send_with<C, T: SerializeWith<C>>(
+        &mut self,
+        ctx: &mut C,
+        value: &T,
+    ) -> Result<(), PeerError>;
+
+    /// Receive the next packet as a value using the context
+    fn recv_with<C, T: DeserializeWith<C>>(&mut self, ctx: &mut C) -> Result<T, PeerError>;
+}
+
+impl<P: Inherits<PacketPeer>> PacketPeerExt for Gd<P> {
+    fn send_with<C, T: SerializeWith<C>>(
+        &mut self,
+        ctx: &mut C,
+        value: &T,
+    ) -> Result<(), PeerError> {
+        let buffer = encode(ctx, value)?;
+        check(self.upcast_mut::<PacketPeer>().put_packet(&buffer)).map_err(PeerError::Godot)
+    }
+
+    fn recv_with<C, T: DeserializeWith<C>>(&mut self, ctx: &mut C) -> Result<T, PeerError> {
+        let peer = self.upcast_mut::<PacketPeer>();
+        if peer.get_available_packet_count() == 0 {
+            return Err(PeerError::Empty);
+        }
+        let buffer = peer.get_packet();
+        check(peer.get_packet_error()).map_err(PeerError::Godot)?;
+        decode(ctx, &buffer)
+    }
+}
+
+/// Send and receive packets through a `MultiplayerPeer`, addressing the peers by their identifier.
+/// Multiplayer peers are also packet peers, `send` and `recv` use the current target and drop the sender.
+pub trait MultiplayerPeerExt {
+    /// Send the value as a packet to the peer, 0 targets all the peers and negative identifiers exclude a peer
+    fn send_to<T: SerializeWith<()>>(
+        &mut self,
+        peer: i32,
+        channel: i32,
+        mode: TransferMode,
+        value: &T,
+    ) -> Result<(), PeerError> {
+        self.send_to_with(&mut (), peer, channel, mode, value)
+    }
+
+    /// Receive the next packet as a value along with the identifier of its sender
+    fn recv_from<T: DeserializeWith<()>>(&mut self) -> Result<(i32, T), PeerError> {
+        self.recv_from_with(&mut ())
+    }
+
+    /// Send the value as a packet to the peer using the context.
+    /// The transfer channel and mode of the peer are restored afterwards, but the target peer is left set
+    /// since the engine cannot read it back.
+    fn send_to_with<C, T: SerializeWith<C>>(
+        &mut self,
+        ctx: &mut C,
+        peer: i32,
+        channel: i32,
+        mode: TransferMode,
+        value: &T,
+    ) -> Result<(), PeerError>;
+
+    /// Receive the next packet as a value along with the identifier of its sender using the context
+    fn recv_from_with<C, T: DeserializeWith<C>>(
+        &mut self,
+        ctx: &mut C,
+    ) -> Result<(i32, T), PeerError>;
+}
+
+impl<P: Inherits<MultiplayerPeer>> MultiplayerPeerExt for Gd<P> {
+    fn send_to_with<C, T: SerializeWith<C>>(
+        &mut self,
+        ctx: &mut C,
+        peer: i32,
+        channel: i32,
+        mode: TransferMode,
+        value: &T,
+    ) -> Result<(), PeerError> {
+        let buffer = encode(ctx, value)?;
+        let multiplayer = self.upcast_mut::<MultiplayerPeer>();
+        let (previous_channel, previous_mode) = (
+            multiplayer.get_transfer_channel(),
+            multiplayer.get_transfer_mode(),
+        );
+        multiplayer.set_target_peer(peer);
+        multiplayer.set_transfer_channel(channel);
+        multiplayer.set_transfer_mode(mode);
+        let result = check(multiplayer.put_packet(&buffer)).map_err(PeerError::Godot);
+
+        // the engine has no getter for the target peer, only the channel and mode are restored
+        multiplayer.set_transfer_channel(previous_channel);
+        multiplayer.set_transfer_mode(previous_mode);
+        result
+    }
+
+    fn recv_from_with<C, T: DeserializeWith<C>>(
+        &mut self,
+        ctx: &mut C,
+    ) -> Result<(i32, T), PeerError> {
+        let multiplayer = self.upcast_mut::<MultiplayerPeer>();
+        if multiplayer.get_available_packet_count() == 0 {
+            return Err(PeerError::Empty);
+        }
+
+        // the sender is known before the packet is taken
+        let sender = multiplayer.get_packet_peer();
+        let buffer = multiplayer.get_packet();
+        check(multiplayer.get_packet_error()).map_err(PeerError::Godot)?;
+        decode(ctx, &buffer).map(|value| (sender, value))
+    }
+}
@@ -0,0 +1,241 @@
+//!
+//! Identify the type of a packet using a header, and dispatch received packets to their handler.
+//!
+
+use super::{
+    ConstSize, Deserialize, DeserializeWith, DynSize, SchemaHash, Serialize, SerializeWith,
+    combine_hashes, decode_varint, encode_varint, varint_size,
+};
+use godot::builtin::*;
+use godot_netpacket_macros::PacketId;
+use std::collections::{BTreeMap, HashMap, hash_map::Entry};
+
+/// Stable identifier of a packet type, written in the header of the packet
+pub trait PacketId {
+    const ID: u16;
+}
+
+/// Error encountered when sending or receiving packets through a registry
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketError {
+    #[error("Packet is too short to contain a header")]
+    MissingHeader,
+
+    #[error("Unknown packet identifier {0}")]
+    Unknown(u16),
+
+    #[error("Packet identifier {0} is already registered")]
+    Duplicate(u16),
+
+    #[error("Packet identifier {0} is reserved for the handshake")]
+    Reserved(u16),
+
+    #[error("Failed to serialize packet with identifier {0}")]
+    Serialize(u16),
+
+    #[error("Failed to deserialize packet with identifier {0}")]
+    Deserialize(u16),
+
+    #[error(
+        "Protocol mismatch on packet identifier {0}: \
+        the peers were built with different packet definitions"
+    )]
+    ProtocolMismatch(u16),
+}
+
+/// First message exchanged by the peers to check they use the same packet definitions.
+/// Its identifier is reserved, registries refuse packet types using it.
+#[derive(Debug, Clone, PartialEq, Eq, Default, PacketId)]
+#[netpacket(id = 65535)]
+pub struct Handshake {
+    /// Identifier and schema hash of each type registered by the sender, sorted by identifier
+    pub packets: Vec<(u16, u64)>,
+}
+
+impl Handshake {
+    /// Hash combining the identifier and the schema hash of every type, like `PacketRegistry::protocol_hash`
+    pub fn protocol_hash(&self) -> u64 {
+        let hashes: Vec<u64> = self
+            .packets
+            .iter()
+            .flat_map(|(id, hash)| [*id as u64, *hash])
+            .collect();
+        combine_hashes(&hashes)
+    }
+}
+
+/// Number of packet types followed by their identifier and schema hash
+impl DynSize for Handshake {
+    fn size(&self) -> usize {
+        varint_size(self.packets.len() as u64) + self.packets.len() * <(u16, u64)>::SIZE
+    }
+}
+
+impl Serialize for Handshake {
+    fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
+        let mut offset = offset + encode_varint(buffer, offset, self.packets.len() as u64)?;
+        for packet in &self.packets {
+            packet.serialize(buffer, offset)?;
+            offset += <(u16, u64)>::SIZE;
+        }
+        Ok(())
+    }
+}
+
+impl Deserialize for Handshake {
+    fn deserialize(buffer: &PackedByteArray, offset: usize) -> Result<Self, ()> {
+        let (count, read) = decode_varint(buffer, offset)?;
+        let start = offset + read;
+
+        // the count is checked against the buffer before allocating
+        let count = usize::try_from(count).map_err(|_| ())?;
+        let end = count
+            .checked_mul(<(u16, u64)>::SIZE)
+            .and_then(|size| start.checked_add(size))
+            .ok_or(())?;
+        if end > buffer.len() {
+            return Err(());
+        }
+        let packets = (0..count)
+            .map(|index| <(u16, u64)>::deserialize(buffer, start + index * <(u16, u64)>::SIZE))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { packets })
+    }
+}
+
+crate::impl_contextless![[] Handshake];
+
+/// Handler of a packet type
+type Handler<C> = Box<dyn FnMut(&mut C, &PackedByteArray) -> Result<(), PacketError>>;
+
+/// Registry of packet types with a context of type `C` shared by the handlers.
+/// Each packet is preceded by a header holding the identifier of its type.
+pub struct PacketRegistry<C = ()> {
+    handlers: HashMap<u16, Handler<C>>,
+
+    /// Schema hash of each registered type, sorted by identifier
+    schemas: BTreeMap<u16, u64>,
+}
+
+impl<C> Default for PacketRegistry<C> {
+    fn default() -> Self {
+        Self {
+            handlers: HashMap::new(),
+            schemas: BTreeMap::new(),
+        }
+    }
+}
+
+impl<C> PacketRegistry<C> {
+    /// Size of the header preceding each packet
+    pub const HEADER_SIZE: usize = u16::SIZE;
+
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the handler called with each received packet of type `T`.
+    /// The identifier of `Handshake` is reserved and cannot be registered.
+    pub fn register<T, F>(&mut self, mut handler: F) -> Result<(), PacketError>
+    where
+        T: PacketId + SchemaHash + DeserializeWith<C>,
+        F: FnMut(&mut C, T) + 'static,
+    {
+        if T::ID == Handshake::ID {
+            return Err(PacketError::Reserved(T::ID));
+        }
+        match self.handlers.entry(T::ID) {
+            Entry::Occupied(_) => Err(PacketError::Duplicate(T::ID)),
+            Entry::Vacant(entry) => {
+                self.schemas.insert(T::ID, T::SCHEMA_HASH);
+                entry.insert(Box::new(move |ctx, buffer| {
+                    let (packet, _) = T::deserialize_with(ctx, buffer, Self::HEADER_SIZE)
+                        .map_err(|_| PacketError::Deserialize(T::ID))?;
+                    handler(ctx, packet);
+                    Ok(())
+                }));
+                Ok(())
+            }
+        }
+    }
+
+    /// Remove the handler of the packet type `T`, returns whether it was registered
+    pub fn unregister<T: PacketId>(&mut self) -> bool {
+        self.schemas.remove(&T::ID);
+        self.handlers.remove(&T::ID).is_some()
+    }
+
+    /// Check if a handler is registered for the identifier
+    pub fn contains(&self, id: u16) -> bool {
+        self.handlers.contains_key(&id)
+    }
+
+    /// Hash combining the identifier and the schema hash of every registered type
+    pub fn protocol_hash(&self) -> u64 {
+        self.handshake().protocol_hash()
+    }
+
+    /// Create the handshake message to send to the remote peer
+    pub fn handshake(&self) -> Handshake {
+        Handshake {
+            packets: self.schemas.iter().map(|(id, hash)| (*id, *hash)).collect(),
+        }
+    }
+
+    /// List the identifiers registered by only one of the peers or with a different schema, sorted
+    pub fn mismatches(&self, remote: &Handshake) -> Vec<u16> {
+        let remote: BTreeMap<u16, u64> = remote.packets.iter().copied().collect();
+        let mut ids: Vec<u16> = self
+            .schemas
+            .iter()
+            .filter(|(id, hash)| remote.get(id) != Some(hash))
+            .map(|(id, _)| *id)
+            .chain(
+                remote
+                    .keys()
+                    .filter(|id| !self.schemas.contains_key(id))
+                    .copied(),
+            )
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Check the handshake message received from the remote peer,
+    /// reporting the first packet type that differs, see `mismatches` for all of them
+    pub fn verify(&self, remote: &Handshake) -> Result<(), PacketError> {
+        match self.mismatches(remote).first() {
+            Some(id) => Err(PacketError::ProtocolMismatch(*id)),
+            None => Ok(()),
+        }
+    }
+
+    /// Write the header followed by the packet into a new buffer
+    pub fn encode<T>(ctx: &mut C, packet: &T) -> Result<PackedByteArray, PacketError>
+    where
+        T: PacketId + SerializeWith<C>,
+    {
+        let mut buffer = PackedByteArray::new();
+        buffer.resize(Self::HEADER_SIZE);
+        buffer
+            .encode_u16(0, T::ID)
+            .map_err(|_| PacketError::Serialize(T::ID))?;
+        packet
+            .serialize_with(ctx, &mut buffer, Self::HEADER_SIZE)
+            .map_err(|_| PacketError::Serialize(T::ID))?;
+        Ok(buffer)
+    }
+
+    /// Read the identifier in the header of the packet
+    pub fn peek_id(buffer: &PackedByteArray) -> Result<u16, PacketError> {
+        buffer.decode_u16(0).map_err(|_| PacketError::MissingHeader)
+    }
+
+    /// Decode the packet and call the handler of its type
+    pub fn dispatch(&mut self, ctx: &mut C, buffer: &PackedByteArray) -> Result<(), PacketError> {
+        let id = Self::peek_id(buffer)?;
+        let handler = self.handlers.get_mut(&id).ok_or(PacketError::Unknown(id))?;
+        handler(ctx, buffer)
+    }
+}
@@ -0,0 +1,345 @@
+//!
+//! Reliable and ordered delivery over unreliable transports such as `PacketPeerUDP` or WebRTC data channels.
+//! The channel works on plain byte buffers, the transport only has to carry them.
+//!
+
+use std::collections::{HashMap, VecDeque};
+
+/// Size of the header preceding each packet: its kind, its sequence number, the latest sequence received
+/// and the bitfield of the previous ones
+pub const RELIABLE_HEADER_SIZE: usize = 9;
+
+/// Largest distance between the oldest packet not acknowledged and the next one sent,
+/// so that every packet in flight can be acknowledged by the bitfield
+pub const RELIABLE_WINDOW: u16 = 32;
+
+/// Time before resending a packet until the round-trip time is measured, in milliseconds
+pub const DEFAULT_RESEND_TIMEOUT: u64 = 200;
+
+/// Kind of the packets holding a message
+const DATA: u8 = 0;
+
+/// Kind of the packets only acknowledging the received ones
+const ACK: u8 = 1;
+
+/// Error encountered when sending or receiving through a reliable channel
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReliableError {
+    #[error("Too many packets are waiting to be acknowledged")]
+    WindowFull,
+
+    #[error("Packet is too short to contain a header")]
+    MissingHeader,
+
+    #[error("Unknown packet kind {0}")]
+    InvalidKind(u8),
+
+    #[error("Sequence {0} is outside of the receiving window")]
+    OutOfWindow(u16),
+}
+
+/// Check if the sequence `a` is more recent than `b`, sequences wrap around
+#[inline]
+fn more_recent(a: u16, b: u16) -> bool {
+    (a.wrapping_sub(b) as i16) > 0
+}
+
+/// Header preceding each packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Header {
+    kind: u8,
+    sequence: u16,
+
+    /// Latest sequence received
+    ack: u16,
+
+    /// Bit `n` is set when the sequence `ack - n - 1` was received
+    ack_bits: u32,
+}
+
+impl Header {
+    fn write(&self, payload: &[u8]) -> Vec<u8> {
+        let mut packet = Vec::with_capacity(RELIABLE_HEADER_SIZE + payload.len());
+        packet.push(self.kind);
+        packet.extend_from_slice(&self.sequence.to_le_bytes());
+        packet.extend_from_slice(&self.ack.to_le_bytes());
+        packet.extend_from_slice(&self.ack_bits.to_le_bytes());
+        packet.extend_from_slice(payload);
+        packet
+    }
+
+    fn read(packet: &[u8]) -> Result<Self, ReliableError> {
+        if packet.len() < RELIABLE_HEADER_SIZE {
+            return Err(ReliableError::MissingHeader);
+        }
+        let header = Self {
+            kind: packet[0],
+            sequence: u16::from_le_bytes([packet[1], packet[2]]),
+            ack: u16::from_le_bytes([packet[3], packet[4]]),
+            ack_bits: u32::from_le_bytes([packet[5], packet[6], packet[7], packet[8]]),
+        };
+        match header.kind {
+            DATA | ACK => Ok(header),
+            kind => Err(ReliableError::InvalidKind(kind)),
+        }
+    }
+}
+
+/// Packet sent and not acknowledged yet
+#[derive(Debug, Clone)]
+struct Sent {
+    sequence: u16,
+    payload: Vec<u8>,
+
+    /// Time of the last transmission
+    sent_at: u64,
+
+    /// Time to wait for the acknowledgment before resending
+    timeout: u64,
+
+    /// Resent packets do not measure the round-trip time, their acknowledgment is ambiguous
+    resent: bool,
+}
+
+/// Deliver messages reliably and in order over an unreliable transport.
+/// Each packet carries a 16-bit sequence number, along with the latest sequence received and a bitfield of the 32 previous ones,
+/// so that acknowledgments survive the loss of a few packets.
+/// Packets not acknowledged in time are resent, the timeout follows the measured round-trip time.
+/// Times are in milliseconds, such as the ones returned by `Time.get_ticks_msec`.
+#[derive(Debug, Clone)]
+pub struct ReliableChannel {
+    min_timeout: u64,
+    max_timeout: u64,
+
+    /// Sequence of the next message sent
+    next_sequence: u16,
+
+    /// Packets waiting to be acknowledged, oldest first
+    unacked: VecDeque<Sent>,
+
+    /// Smoothed round-trip time and its variation, once measured
+    rtt: Option<(f64, f64)>,
+
+    /// Latest sequence received, the one before the first sequence until a packet is received
+    ack: u16,
+
+    /// Bit `n` is set when the sequence `ack - n - 1` was received
+    ack_bits: u32,
+
+    /// Whether received packets were not acknowledged yet
+    ack_pending: bool,
+
+    /// Sequence of the next message delivered
+    next_delivery: u16,
+
+    /// Messages received ahead of the next one delivered
+    buffered: HashMap<u16, Vec<u8>>,
+
+    /// Messages ready to be delivered, in order
+    ready: VecDeque<Vec<u8>>,
+}
+
+impl Default for ReliableChannel {
+    fn default() -> Self {
+        Self {
+            min_timeout: 20,
+            max_timeout: 2000,
+            next_sequence: 0,
+            unacked: VecDeque::new(),
+            rtt: None,
+            ack: u16::MAX,
+            ack_bits: 0,
+            ack_pending: false,
+            next_delivery: 0,
+            buffered: HashMap::new(),
+            ready: VecDeque::new(),
+        }
+    }
+}
+
+impl ReliableChannel {
+    /// Create a channel resending packets after 20 ms to 2 s
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the shortest time before resending a packet, in milliseconds
+    pub fn min_timeout(mut self, min_timeout: u64) -> Self {
+        self.min_timeout = min_timeout;
+        self
+    }
+
+    /// Set the longest time before resending a packet, in milliseconds
+    pub fn max_timeout(mut self, max_timeout: u64) -> Self {
+        self.max_timeout = max_timeout;
+        self
+    }
+
+    /// Smoothed round-trip time in milliseconds, once measured
+    #[inline]
+    pub fn rtt(&self) -> Option<f64> {
+        self.rtt.map(|(rtt, _)| rtt)
+    }
+
+    /// Time before resending a packet, in milliseconds
+    pub fn resend_timeout(&self) -> u64 {
+        let timeout = match self.rtt {
+            Some((rtt, variation)) => (rtt + 4.0 * variation).ceil() as u64,
+            None => DEFAULT_RESEND_TIMEOUT,
+        };
+        timeout.clamp(self.min_timeout, self.max_timeout)
+    }
+
+    /// Number of packets waiting to be acknowledged
+    #[inline]
+    pub fn in_flight(&self) -> usize {
+        self.unacked.len()
+    }
+
+    /// Check if another message can be sent without exceeding the window
+    pub fn can_send(&self) -> bool {
+        match self.unacked.front() {
+            Some(oldest) => self.next_sequence.wrapping_sub(oldest.sequence) < RELIABLE_WINDOW,
+            None => true,
+        }
+    }
+
+    /// Queue the message, returns the packet to send through the transport
+    pub fn send(&mut self, payload: &[u8], now: u64) -> Result<Vec<u8>, ReliableError> {
+        if !self.can_send() {
+            return Err(ReliableError::WindowFull);
+        }
+        let sequence = self.next_sequence;
+        self.next_sequence = self.next_sequence.wrapping_add(1);
+        self.unacked.push_back(Sent {
+            sequence,
+            payload: payload.to_vec(),
+            sent_at: now,
+            timeout: self.resend_timeout(),
+            resent: false,
+        });
+        Ok(self.packet(DATA, sequence, payload))
+    }
+
+    /// Process the packet received from the transport, its message is delivered by `recv` once the previous ones are
+    pub fn receive(&mut self, packet: &[u8], now: u64) -> Result<(), ReliableError> {
+        let header = Header::read(packet)?;
+        self.acknowledge(header.ack, header.ack_bits, now);
+        if header.kind == ACK {
+            return Ok(());
+        }
+
+        // duplicates of delivered messages are acknowledged again, in case the acknowledgment was lost
+        let distance = header.sequence.wrapping_sub(self.next_delivery) as i16;
+        if distance >= RELIABLE_WINDOW as i16 {
+            return Err(ReliableError::OutOfWindow(header.sequence));
+        }
+        self.track(header.sequence);
+        self.ack_pending = true;
+        if distance < 0 {
+            return Ok(());
+        }
+
+        self.buffered
+            .entry(header.sequence)
+            .or_insert_with(|| packet[RELIABLE_HEADER_SIZE..].to_vec());
+        while let Some(payload) = self.buffered.remove(&self.next_delivery) {
+            self.ready.push_back(payload);
+            self.next_delivery = self.next_delivery.wrapping_add(1);
+        }
+        Ok(())
+    }
+
+    /// Take the next message received, in the order they were sent
+    pub fn recv(&mut self) -> Option<Vec<u8>> {
+        self.ready.pop_front()
+    }
+
+    /// Returns the packets to send through the transport: the ones not acknowledged in time,
+    /// or a packet acknowledging the received ones when nothing else carries the acknowledgment.
+    /// Call it every tick.
+    pub fn update(&mut self, now: u64) -> Vec<Vec<u8>> {
+        let mut packets = Vec::new();
+        let (ack, ack_bits, max_timeout) = (self.ack, self.ack_bits, self.max_timeout);
+        for sent in &mut self.unacked {
+            if now.saturating_sub(sent.sent_at) < sent.timeout {
+                continue;
+            }
+            // the timeout doubles with every resend until the packet gets through
+            sent.sent_at = now;
+            sent.timeout = (sent.timeout * 2).min(max_timeout);
+            sent.resent = true;
+            let header = Header {
+                kind: DATA,
+                sequence: sent.sequence,
+                ack,
+                ack_bits,
+            };
+            packets.push(header.write(&sent.payload));
+        }
+
+        if self.ack_pending && packets.is_empty() {
+            packets.push(self.packet(ACK, self.next_sequence, &[]));
+        }
+        self.ack_pending = false;
+        packets
+    }
+
+    /// Write the packet carrying the latest acknowledgments
+    fn packet(&mut self, kind: u8, sequence: u16, payload: &[u8]) -> Vec<u8> {
+        self.ack_pending = false;
+        let header = Header {
+            kind,
+            sequence,
+            ack: self.ack,
+            ack_bits: self.ack_bits,
+        };
+        header.write(payload)
+    }
+
+    /// Record the sequence as received
+    fn track(&mut self, sequence: u16) {
+        if more_recent(sequence, self.ack) {
+            let shift = sequence.wrapping_sub(self.ack) as u32;
+            self.ack_bits = match shift {
+                1..32 => (self.ack_bits << shift) | (1 << (shift - 1)),
+                32 => 1 << 31,
+                _ => 0,
+            };
+            self.ack = sequence;
+        } else {
+            let distance = self.ack.wrapping_sub(sequence) as u32;
+            if (1..=32).contains(&distance) {
+                self.ack_bits |= 1 << (distance - 1);
+            }
+        }
+    }
+
+    /// Remove the packets acknowledged by the remote peer, measuring the round-trip time
+    fn acknowledge(&mut self, ack: u16, ack_bits: u32, now: u64) {
+        let acked = |sequence: u16| match ack.wrapping_sub(sequence) {
+            0 => true,
+            distance @ 1..=32 => ack_bits & (1 << (distance - 1)) != 0,
+            _ => false,
+        };
+        let mut samples = Vec::new();
+        self.unacked.retain(|sent| {
+            let acked = acked(sent.sequence);
+            if acked && !sent.resent {
+                samples.push(now.saturating_sub(sent.sent_at) as f64);
+            }
+            !acked
+        });
+
+        // smoothed like the retransmission timer of TCP
+        for sample in samples {
+            self.rtt = Some(match self.rtt {
+                Some((rtt, variation)) => (
+                    0.875 * rtt + 0.125 * sample,
+                    0.75 * variation + 0.25 * (rtt - sample).abs(),
+                ),
+                None => (sample, sample / 2.0),
+            });
+        }
+    }
+}
@@ -0,0 +1,240 @@
+//!
+//! Reference resources by their UID or their path, loading them back on the remote side.
+//!
+
+use super::{
+    ConstSize, Deserialize, DeserializeWith, DynSize, Serialize, SerializeWith, decode_varint,
+    encode_varint, reserve,
+    string::{StringTable, read_text, text_size, write_text},
+    varint_size,
+};
+use godot::{
+    builtin::*,
+    classes::{Resource, ResourceLoader, ResourceUid},
+    obj::{Gd, GodotClass, Inherits, Singleton},
+};
+
+/// Tag of a resource stored as its UID
+const TAG_UID: u8 = 0;
+
+/// Tag of a resource stored as its path
+const TAG_PATH: u8 = 1;
+
+/// Tag of a resource stored as the index of its path in the string table
+const TAG_INDEX: u8 = 2;
+
+/// Tag of a resource stored as its path, to be appended to the string table
+const TAG_ENTRY: u8 = 3;
+
+/// Size of the tag identifying how the resource is stored
+const TAG_SIZE: usize = 1;
+
+/// Prefixes of the paths that can be loaded, other paths could reach user data or any file of the system
+const ALLOWED_PREFIXES: [&str; 2] = ["res://", "uid://"];
+
+/// Check the path points inside the project
+fn is_allowed(path: &str) -> bool {
+    ALLOWED_PREFIXES
+        .iter()
+        .any(|prefix| path.starts_with(prefix))
+}
+
+/// Reference to a resource of class `T`, stored as its UID if it has one or as its path otherwise.
+/// If `TABLE` is set, the path is stored in a `StringTable` provided by the context
+/// so that repeated references only cost an index. In this case the packets must be delivered
+/// reliably and in order, since both peers must see the same sequence of insertions.
+pub struct ResourceRef<T: GodotClass = Resource, const TABLE: bool = false>(pub Gd<T>);
+
+impl<T: GodotClass, const TABLE: bool> Clone for ResourceRef<T, TABLE> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T: GodotClass, const TABLE: bool> PartialEq for ResourceRef<T, TABLE> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+/// Location of a resource
+enum Location {
+    Uid(i64),
+    Path(GString),
+}
+
+impl Location {
+    /// Find the location of a resource, fails if it was not loaded from a file of the project
+    fn of<T: Inherits<Resource>>(resource: &Gd<T>) -> Result<Self, ()> {
+        let path = resource.upcast_ref::<Resource>().get_path();
+        if !is_allowed(&path.to_string()) {
+            return Err(());
+        }
+
+        let uid = ResourceLoader::singleton().get_resource_uid(&path);
+        if uid == ResourceUid::INVALID_ID as i64 {
+            Ok(Self::Path(path))
+        } else {
+            Ok(Self::Uid(uid))
+        }
+    }
+
+    /// Size of the location along with its tag
+    fn size(&self) -> usize {
+        match self {
+            Self::Uid(_) => TAG_SIZE + i64::SIZE,
+            Self::Path(path) => TAG_SIZE + path.size(),
+        }
+    }
+
+    /// Write the tag followed by the UID or the path
+    fn write(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
+        match self {
+            Self::Uid(uid) => {
+                buffer.encode_u8(offset, TAG_UID)?;
+                uid.serialize(buffer, offset + TAG_SIZE)
+            }
+            Self::Path(path) => {
+                buffer.encode_u8(offset, TAG_PATH)?;
+                path.serialize(buffer, offset + TAG_SIZE)
+            }
+        }
+    }
+
+    /// Read the UID or the path following the tag
+    fn read(tag: u8, buffer: &PackedByteArray, offset: usize) -> Result<Self, ()> {
+        match tag {
+            TAG_UID => Ok(Self::Uid(i64::deserialize(buffer, offset + TAG_SIZE)?)),
+            TAG_PATH => Ok(Self::Path(GString::deserialize(buffer, offset + TAG_SIZE)?)),
+            _ => Err(()),
+        }
+    }
+
+    /// Text loadable by the resource loader, either a `uid://` or a `res://` path
+    fn to_text(&self) -> GString {
+        match self {
+            Self::Uid(uid) => ResourceUid::singleton().id_to_text(*uid),
+            Self::Path(path) => path.clone(),
+        }
+    }
+}
+
+/// Load the resource and check its class, the path comes from the remote peer and must point inside the project
+fn load<T: Inherits<Resource>>(path: &GString) -> Result<Gd<T>, ()> {
+    if !is_allowed(&path.to_string()) {
+        return Err(());
+    }
+    ResourceLoader::singleton()
+        .load(path)
+        .ok_or(())?
+        .try_cast::<T>()
+        .map_err(|_| ())
+}
+
+// implement without string table
+
+impl<T: Inherits<Resource>> DynSize for ResourceRef<T, false> {
+    /// Size of the reference, zero if the resource was not loaded from a file
+    #[inline]
+    fn size(&self) -> usize {
+        Location::of(&self.0).map_or(0, |location| location.size())
+    }
+}
+impl<T: Inherits<Resource>> Serialize for ResourceRef<T, false> {
+    fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
+        Location::of(&self.0)?.write(buffer, offset)
+    }
+}
+impl<T: Inherits<Resource>> Deserialize for ResourceRef<T, false> {
+    fn deserialize(buffer: &PackedByteArray, offset: usize) -> Result<Self, ()>
+    where
+        Self: Sized,
+    {
+        let location = Location::read(buffer.decode_u8(offset)?, buffer, offset)?;
+        load(&location.to_text()).map(Self)
+    }
+}
+
+crate::impl_contextless![[T: Inherits<Resource>] ResourceRef<T, false>];
+
+// implement with string table
+
+impl<C, T> SerializeWith<C> for ResourceRef<T, true>
+where
+    C: AsMut<StringTable>,
+    T: Inherits<Resource>,
+{
+    fn serialize_with(
+        &self,
+        ctx: &mut C,
+        buffer: &mut PackedByteArray,
+        offset: usize,
+    ) -> Result<usize, ()> {
+        let location = Location::of(&self.0)?;
+        let text = location.to_text().to_string();
+        let table = ctx.as_mut();
+
+        // reference the entry if the peer already knows it
+        if let Some(index) = table.index_of(&text) {
+            reserve(buffer, offset + TAG_SIZE + varint_size(index as u64));
+            buffer.encode_u8(offset, TAG_INDEX)?;
+            return Ok(TAG_SIZE + encode_varint(buffer, offset + TAG_SIZE, index as u64)?);
+        }
+
+        // once the table is full the location is sent inline, like without a table
+        if table.is_full() {
+            let size = location.size();
+            reserve(buffer, offset + size);
+            location.write(buffer, offset)?;
+            return Ok(size);
+        }
+
+        // otherwise send the path and add it on both sides
+        let size = TAG_SIZE + text_size(&text);
+        reserve(buffer, offset + size);
+        buffer.encode_u8(offset, TAG_ENTRY)?;
+        write_text(buffer, offset + TAG_SIZE, &text)?;
+        table.insert(&text).ok_or(())?;
+        Ok(size)
+    }
+}
+impl<C, T> DeserializeWith<C> for ResourceRef<T, true>
+where
+    C: AsMut<StringTable>,
+    T: Inherits<Resource>,
+{
+    fn deserialize_with(
+        ctx: &mut C,
+        buffer: &PackedByteArray,
+        offset: usize,
+    ) -> Result<(Self, usize), ()>
+    where
+        Self: Sized,
+    {
+        let table = ctx.as_mut();
+
+        let (text, size) = match buffer.decode_u8(offset)? {
+            TAG_INDEX => {
+                let (index, read) = decode_varint(buffer, offset + TAG_SIZE)?;
+                let index = u32::try_from(index).map_err(|_| ())?;
+                (table.get(index).ok_or(())?.to_owned(), TAG_SIZE + read)
+            }
+            TAG_ENTRY => {
+                // entries beyond the limit of the table are rejected, the sender sends them inline
+                let text = read_text(buffer, offset + TAG_SIZE)?;
+                if !is_allowed(text) {
+                    return Err(());
+                }
+                table.insert(text).ok_or(())?;
+                (text.to_owned(), TAG_SIZE + text_size(text))
+            }
+            tag => {
+                let location = Location::read(tag, buffer, offset)?;
+                (location.to_text().to_string(), location.size())
+            }
+        };
+
+        let resource = load(&GString::from(text.as_str()))?;
+        Ok((Self(resource), size))
+    }
+}
@@ -0,0 +1,550 @@
+//!
+//! Describe the wire layout of the types, and fingerprint it to detect peers using different packet definitions.
+//!
+
+use super::{
+    Angle, Cell, ConstSize, Flags, Full, Half, Interned, NetRef, NetworkId, Relative, ResourceRef,
+    Rgb, Rgb565, Rgba4444, Rigid2D, SNorm16, Trs, UNorm8, UNorm16, Unit,
+};
+use godot::{
+    builtin::*,
+    classes::InputEvent,
+    global::{JoyAxis, JoyButton, Key, KeyModifierMask, MouseButton, MouseButtonMask},
+    obj::{Gd, GodotClass},
+};
+use std::fmt;
+
+/// Description of the wire layout of a type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Schema {
+    /// Name of the type, or of the wrapper changing its encoding
+    pub name: &'static str,
+
+    /// Size in bytes, unknown for types with a dynamic size
+    pub size: Option<usize>,
+
+    /// How the value is stored
+    pub kind: SchemaKind,
+}
+
+/// Way a value is stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaKind {
+    /// Number or godot type with a dedicated encoding, identified by its name
+    Builtin,
+
+    /// Wrapper changing the encoding of the inner type, identified by its name.
+    /// The parameter holds the constant of the wrapper, such as the size of a `Cell`.
+    /// The inner type of `Rigid2D` is the tuple of its angle and origin, displayed as its two arguments.
+    Wrapper {
+        inner: &'static Schema,
+        param: Option<u32>,
+    },
+
+    /// Bitfield holding the flags followed by the other fields
+    Struct { fields: &'static [SchemaField] },
+
+    /// Tag of the variant on one byte followed by its fields
+    Enum { variants: &'static [SchemaVariant] },
+
+    /// Items stored one after the other
+    Array { item: &'static Schema, len: usize },
+
+    /// Items stored one after the other
+    Tuple { items: &'static [&'static Schema] },
+}
+
+/// Field of a struct or of an enum variant
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SchemaField {
+    /// Name of the field, or its position for tuple structs
+    pub name: &'static str,
+
+    /// Schema of the stored value, flags are described as booleans
+    pub schema: &'static Schema,
+
+    /// Offset in bytes from the start of the value, the tag of an enum included.
+    /// It is unknown after a field with a dynamic size.
+    pub offset: Option<usize>,
+
+    /// Bit holding a flag in the byte at the offset
+    pub bit: Option<u8>,
+}
+
+/// Variant of an enum
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SchemaVariant {
+    /// Name of the variant
+    pub name: &'static str,
+
+    /// Tag identifying the variant
+    pub tag: u8,
+
+    /// Size in bytes, the tag included
+    pub size: Option<usize>,
+
+    /// Fields of the variant
+    pub fields: &'static [SchemaField],
+}
+
+/// Describe the wire layout of a type.
+/// The `Serialize` derive implements this trait.
+pub trait PacketSchema {
+    const SCHEMA: &'static Schema;
+}
+
+/// Hash of the wire format of a type, evaluated at compile time.
+/// It covers the names, types and order of the fields, the wrappers and the enum tags.
+pub trait SchemaHash {
+    const SCHEMA_HASH: u64;
+}
+
+impl<T> SchemaHash for T
+where
+    T: PacketSchema + ?Sized,
+{
+    const SCHEMA_HASH: u64 = T::SCHEMA.hash();
+}
+
+/// Offset basis of the FNV-1a hash
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// Prime of the FNV-1a hash
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Hash a string using FNV-1a
+pub const fn hash_str(text: &str) -> u64 {
+    let bytes = text.as_bytes();
+    let mut hash = FNV_OFFSET;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+        i += 1;
+    }
+    hash
+}
+
+/// Combine a sequence of hashes using FNV-1a, the order of the hashes matters
+pub const fn combine_hashes(hashes: &[u64]) -> u64 {
+    let mut hash = FNV_OFFSET;
+    let mut i = 0;
+    while i < hashes.len() {
+        hash = mix(hash, hashes[i]);
+        i += 1;
+    }
+    hash
+}
+
+/// Feed the bytes of a value to the hash
+const fn mix(mut hash: u64, value: u64) -> u64 {
+    let mut byte = 0;
+    while byte < 8 {
+        hash ^= (value >> (8 * byte)) & 0xFF;
+        hash = hash.wrapping_mul(FNV_PRIME);
+        byte += 1;
+    }
+    hash
+}
+
+/// Feed the names and the schemas of the fields to the hash
+const fn mix_fields(mut hash: u64, fields: &[SchemaField]) -> u64 {
+    let mut i = 0;
+    while i < fields.len() {
+        hash = mix(hash, hash_str(fields[i].name));
+        hash = mix(hash, fields[i].schema.hash());
+        hash = mix(hash, fields[i].bit.is_some() as u64);
+        i += 1;
+    }
+    hash
+}
+
+impl Schema {
+    /// Describe a type with a dedicated encoding
+    pub const fn builtin(name: &'static str, size: Option<usize>) -> Self {
+        Self {
+            name,
+            size,
+            kind: SchemaKind::Builtin,
+        }
+    }
+
+    /// Offset following this value when it is stored at the given offset
+    pub const fn after(&self, offset: Option<usize>) -> Option<usize> {
+        match (offset, self.size) {
+            (Some(offset), Some(size)) => Some(offset + size),
+            _ => None,
+        }
+    }
+
+    /// Hash of the wire format
+    pub const fn hash(&self) -> u64 {
+        let hash = mix(FNV_OFFSET, hash_str(self.name));
+        match self.kind {
+            SchemaKind::Builtin => mix(hash, hash_str("builtin")),
+            SchemaKind::Wrapper { inner, param } => {
+                let hash = mix(mix(hash, hash_str("wrapper")), inner.hash());
+                match param {
+                    Some(param) => mix(hash, param as u64),
+                    None => hash,
+                }
+            }
+            SchemaKind::Struct { fields } => mix_fields(mix(hash, hash_str("struct")), fields),
+            SchemaKind::Enum { variants } => {
+                let mut hash = mix(hash, hash_str("enum"));
+                let mut i = 0;
+                while i < variants.len() {
+                    hash = mix(hash, hash_str(variants[i].name));
+                    hash = mix(hash, variants[i].tag as u64);
+                    hash = mix_fields(hash, variants[i].fields);
+                    i += 1;
+                }
+                hash
+            }
+            SchemaKind::Array { item, len } => {
+                mix(mix(mix(hash, hash_str("array")), item.hash()), len as u64)
+            }
+            SchemaKind::Tuple { items } => {
+                let mut hash = mix(hash, hash_str("tuple"));
+                let mut i = 0;
+                while i < items.len() {
+                    hash = mix(hash, items[i].hash());
+                    i += 1;
+                }
+                hash
+            }
+        }
+    }
+
+    /// Name of the type along with its wrappers and items, such as `Unit<Vector3>`
+    pub(super) fn type_name(&self) -> String {
+        /// Display only the name of the schema
+        struct Name<'a>(&'a Schema);
+        impl fmt::Display for Name<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                self.0.write_name(f)
+            }
+        }
+        Name(self).to_string()
+    }
+
+    /// Bits of the angle described by the schema, angles being built-in types named after their bits
+    pub(super) fn angle_bits(&self) -> Option<u32> {
+        match self.kind {
+            SchemaKind::Builtin => self
+                .name
+                .strip_prefix("Angle<")?
+                .strip_suffix('>')?
+                .parse()
+                .ok(),
+            _ => None,
+        }
+    }
+
+    /// Write the name of the type along with its wrappers and items
+    fn write_name(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            SchemaKind::Wrapper { inner, param } => {
+                write!(f, "{}<", self.name)?;
+                match inner.kind {
+                    SchemaKind::Tuple { items } if self.name == "Rigid2D" => {
+                        Self::write_items(items, f)?
+                    }
+                    _ => inner.write_name(f)?,
+                }
+                if let Some(param) = param {
+                    write!(f, ", {param}")?;
+                }
+                write!(f, ">")
+            }
+            SchemaKind::Array { item, len } => {
+                write!(f, "[")?;
+                item.write_name(f)?;
+                write!(f, "; {len}]")
+            }
+            SchemaKind::Tuple { items } => {
+                write!(f, "(")?;
+                Self::write_items(items, f)?;
+                write!(f, ")")
+            }
+            _ => write!(f, "{}", self.name),
+        }
+    }
+
+    /// Write the names of the items separated by commas
+    fn write_items(items: &[&Schema], f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, item) in items.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            item.write_name(f)?;
+        }
+        Ok(())
+    }
+
+    /// Write the name and the size of the type, followed by its fields on the next lines
+    fn write_tree(&self, f: &mut fmt::Formatter, depth: usize) -> fmt::Result {
+        self.write_name(f)?;
+        match self.size {
+            Some(size) => write!(f, " ({size} bytes)")?,
+            None => write!(f, " (dynamic)")?,
+        }
+
+        match self.kind {
+            SchemaKind::Struct { fields } => write_fields(f, fields, depth + 1),
+            SchemaKind::Enum { variants } => {
+                for variant in variants {
+                    write!(
+                        f,
+                        "\n{:indent$}{} = {}",
+                        "",
+                        variant.name,
+                        variant.tag,
+                        indent = 2 * (depth + 1)
+                    )?;
+                    write_fields(f, variant.fields, depth + 2)?;
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Write each field on its own line with its offset
+fn write_fields(f: &mut fmt::Formatter, fields: &[SchemaField], depth: usize) -> fmt::Result {
+    for field in fields {
+        write!(f, "\n{:indent$}{}", "", field.name, indent = 2 * depth)?;
+        match (field.offset, field.bit) {
+            (Some(offset), Some(bit)) => write!(f, " @{offset}.{bit}: ")?,
+            (Some(offset), None) => write!(f, " @{offset}: ")?,
+            _ => write!(f, ": ")?,
+        }
+        field.schema.write_tree(f, depth)?;
+    }
+    Ok(())
+}
+
+/// Display the layout as a tree, one field per line
+impl fmt::Display for Schema {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_tree(f, 0)
+    }
+}
+
+impl SchemaVariant {
+    /// Size shared by all the variants, if any
+    pub const fn common_size(variants: &[SchemaVariant]) -> Option<usize> {
+        if variants.is_empty() {
+            return None;
+        }
+
+        let mut i = 1;
+        while i < variants.len() {
+            match (variants[0].size, variants[i].size) {
+                (Some(first), Some(size)) if first == size => {}
+                _ => return None,
+            }
+            i += 1;
+        }
+        variants[0].size
+    }
+}
+
+/// Macro to quickly implement the schema.
+macro_rules! impl_schema {
+    // implement for wrappers with a constant size, described by their name and the wrapped type
+    ( wrapper $( $wrap:ident < T $( , const $param:ident : $ptype:ty )? > ),+ ) => {
+        $(
+            impl<T: PacketSchema $( , const $param: $ptype )?> PacketSchema for $wrap<T $( , $param )?>
+            where
+                Self: ConstSize,
+            {
+                const SCHEMA: &'static Schema = &Schema {
+                    name: stringify!($wrap),
+                    size: Some(<Self as ConstSize>::SIZE),
+                    kind: SchemaKind::Wrapper {
+                        inner: T::SCHEMA,
+                        param: None,
+                    },
+                };
+            }
+        )+
+    };
+
+    // implement for types with a dynamic size
+    ( dynamic $( $atype:ty ),+ ) => {
+        $(
+            impl PacketSchema for $atype {
+                const SCHEMA: &'static Schema = &Schema::builtin(stringify!($atype), None);
+            }
+        )+
+    };
+
+    // implement for types with a constant size
+    ( $( $atype:ty ),+ ) => {
+        $(
+            impl PacketSchema for $atype {
+                const SCHEMA: &'static Schema =
+                    &Schema::builtin(stringify!($atype), Some(<$atype as ConstSize>::SIZE));
+            }
+        )+
+    };
+}
+
+// implement for numbers and godot types
+
+impl_schema![u8, u16, u32, u64, i8, i16, i32, i64, f32, f64, bool];
+impl_schema![
+    Vector2, Vector3, Vector4, Vector2i, Vector3i, Vector4i, Quaternion
+];
+impl_schema![
+    Basis,
+    Transform2D,
+    Transform3D,
+    Plane,
+    Color,
+    Rect2,
+    Rect2i,
+    Aabb
+];
+impl_schema![dynamic GString, StringName, NodePath];
+impl_schema![NetworkId];
+impl_schema![
+    Key,
+    MouseButton,
+    JoyButton,
+    JoyAxis,
+    KeyModifierMask,
+    MouseButtonMask
+];
+
+impl PacketSchema for Gd<InputEvent> {
+    const SCHEMA: &'static Schema = &Schema::builtin("InputEvent", None);
+}
+
+impl<T: GodotClass> PacketSchema for NetRef<T> {
+    const SCHEMA: &'static Schema = &Schema::builtin("NetRef", Some(NetworkId::SIZE));
+}
+
+impl<T: GodotClass, const TABLE: bool> PacketSchema for ResourceRef<T, TABLE> {
+    // the table changes the encoding
+    const SCHEMA: &'static Schema = &Schema::builtin(
+        if TABLE {
+            "ResourceRef<TABLE>"
+        } else {
+            "ResourceRef"
+        },
+        None,
+    );
+}
+
+// implement for wrappers, the strict flag does not change the wire format
+
+impl_schema![
+    wrapper
+    Unit<T>,
+    Half<T>,
+    UNorm8<T, const STRICT: bool>,
+    UNorm16<T, const STRICT: bool>,
+    SNorm16<T, const STRICT: bool>,
+    Trs<T>,
+    Full<T>,
+    Rgb<T>,
+    Rgb565<T>,
+    Rgba4444<T>,
+    Relative<T>,
+    Flags<T>
+];
+
+impl<T: PacketSchema, const CELL_SIZE: u32> PacketSchema for Cell<T, CELL_SIZE>
+where
+    Self: ConstSize,
+{
+    const SCHEMA: &'static Schema = &Schema {
+        name: "Cell",
+        size: Some(<Self as ConstSize>::SIZE),
+        kind: SchemaKind::Wrapper {
+            inner: T::SCHEMA,
+            param: Some(CELL_SIZE),
+        },
+    };
+}
+
+impl<const BITS: u32> PacketSchema for Angle<BITS>
+where
+    Self: ConstSize,
+{
+    const SCHEMA: &'static Schema = &Schema::builtin(
+        match BITS {
+            8 => "Angle<8>",
+            12 => "Angle<12>",
+            _ => "Angle<16>",
+        },
+        Some(<Self as ConstSize>::SIZE),
+    );
+}
+
+impl<A: PacketSchema, O: PacketSchema> PacketSchema for Rigid2D<A, O> {
+    const SCHEMA: &'static Schema = &Schema {
+        name: "Rigid2D",
+        size: <(A, O) as PacketSchema>::SCHEMA.size,
+        kind: SchemaKind::Wrapper {
+            inner: <(A, O) as PacketSchema>::SCHEMA,
+            param: None,
+        },
+    };
+}
+
+impl<T: PacketSchema> PacketSchema for Interned<T> {
+    const SCHEMA: &'static Schema = &Schema {
+        name: "Interned",
+        size: None,
+        kind: SchemaKind::Wrapper {
+            inner: T::SCHEMA,
+            param: None,
+        },
+    };
+}
+
+// implement for composite types
+
+impl<T: PacketSchema, const S: usize> PacketSchema for [T; S] {
+    const SCHEMA: &'static Schema = &Schema {
+        name: "array",
+        size: match T::SCHEMA.size {
+            Some(size) => Some(size * S),
+            None => None,
+        },
+        kind: SchemaKind::Array {
+            item: T::SCHEMA,
+            len: S,
+        },
+    };
+}
+
+/// Implement the schema for tuples
+macro_rules! impl_tuple_schema {
+    ( $( $t:ident ),+ ) => {
+        impl<$( $t: PacketSchema ),+> PacketSchema for ( $( $t, )+ ) {
+            const SCHEMA: &'static Schema = &Schema {
+                name: "tuple",
+                size: {
+                    let size = Some(0);
+                    $( let size = $t::SCHEMA.after(size); )+
+                    size
+                },
+                kind: SchemaKind::Tuple {
+                    items: &[$( $t::SCHEMA ),+],
+                },
+            };
+        }
+    };
+}
+
+impl_tuple_schema![T0];
+impl_tuple_schema![T0, T1];
+impl_tuple_schema![T0, T1, T2];
+impl_tuple_schema![T0, T1, T2, T3];
+impl_tuple_schema![T0, T1, T2, T3, T4];
+impl_tuple_schema![T0, T1, T2, T3, T4, T5];
@@ -0,0 +1,165 @@
+//!
+//! Define serialization and deserialization for string types.
+//! Strings are stored as their length in bytes followed by their UTF-8 content.
+//!
+
+use super::{Deserialize, DynSize, Serialize, decode_varint, encode_varint, varint_size};
+use godot::builtin::*;
+use std::collections::HashMap;
+
+/// Write the length of the text followed by its content
+pub(super) fn write_text(
+    buffer: &mut PackedByteArray,
+    offset: usize,
+    text: &str,
+) -> Result<(), ()> {
+    let start = offset + encode_varint(buffer, offset, text.len() as u64)?;
+    buffer
+        .as_mut_slice()
+        .get_mut(start..start + text.len())
+        .ok_or(())?
+        .copy_from_slice(text.as_bytes());
+    Ok(())
+}
+
+/// Read the length of the text followed by its content
+pub(super) fn read_text(buffer: &PackedByteArray, offset: usize) -> Result<&str, ()> {
+    let (len, read) = decode_varint(buffer, offset)?;
+    let start = offset + read;
+    let end = start
+        .checked_add(usize::try_from(len).map_err(|_| ())?)
+        .ok_or(())?;
+    let bytes = buffer.as_slice().get(start..end).ok_or(())?;
+    std::str::from_utf8(bytes).map_err(|_| ())
+}
+
+/// Number of bytes used to store the text
+#[inline]
+pub(super) fn text_size(text: &str) -> usize {
+    varint_size(text.len() as u64) + text.len()
+}
+
+/// Macro to quickly implement traits for network packets.
+macro_rules! impl_packet {
+    ( $string:ty ) => {
+        impl DynSize for $string {
+            #[inline]
+            fn size(&self) -> usize {
+                text_size(&self.to_string())
+            }
+        }
+        impl Serialize for $string {
+            #[inline]
+            fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
+                write_text(buffer, offset, &self.to_string())
+            }
+        }
+        impl Deserialize for $string {
+            #[inline]
+            fn deserialize(buffer: &PackedByteArray, offset: usize) -> Result<Self, ()>
+            where
+                Self: Sized,
+            {
+                read_text(buffer, offset).map(<$string>::from)
+            }
+        }
+    };
+}
+
+// implement for godot strings
+
+impl_packet![GString];
+impl_packet![StringName];
+impl_packet![NodePath];
+
+crate::impl_contextless![[] GString, [] StringName, [] NodePath];
+
+/// Number of strings a table holds by default
+pub const DEFAULT_MAX_STRINGS: u32 = 1 << 16;
+
+/// Table of strings shared by the peers of a session, so that repeated strings cost only an index.
+/// Both peers must see the same sequence of insertions, each direction of a session uses its own table.
+#[derive(Debug, Clone)]
+pub struct StringTable {
+    indices: HashMap<String, u32>,
+    strings: Vec<String>,
+
+    /// Number of strings the table holds, both peers must use the same limit
+    max_strings: u32,
+}
+
+impl Default for StringTable {
+    fn default() -> Self {
+        Self {
+            indices: HashMap::new(),
+            strings: Vec::new(),
+            max_strings: DEFAULT_MAX_STRINGS,
+        }
+    }
+}
+
+impl StringTable {
+    /// Create an empty table holding up to `DEFAULT_MAX_STRINGS` strings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the number of strings the table holds, the strings beyond are not added
+    pub fn max_strings(mut self, max_strings: u32) -> Self {
+        self.max_strings = max_strings;
+        self
+    }
+
+    /// Get the index of the string if it is in the table
+    pub fn index_of(&self, text: &str) -> Option<u32> {
+        self.indices.get(text).copied()
+    }
+
+    /// Get the string stored at the index
+    pub fn get(&self, index: u32) -> Option<&str> {
+        self.strings.get(index as usize).map(String::as_str)
+    }
+
+    /// Add the string to the table if needed, returns its index or `None` once the table is full
+    pub fn insert(&mut self, text: &str) -> Option<u32> {
+        if let Some(index) = self.index_of(text) {
+            return Some(index);
+        }
+        if self.is_full() {
+            return None;
+        }
+
+        let index = self.strings.len() as u32;
+        self.indices.insert(text.to_owned(), index);
+        self.strings.push(text.to_owned());
+        Some(index)
+    }
+
+    /// Check if the table holds `max_strings` strings
+    pub fn is_full(&self) -> bool {
+        self.strings.len() >= self.max_strings as usize
+    }
+
+    /// Number of strings in the table
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Check if the table is empty
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Remove all the strings, to be done by both peers at the same time
+    pub fn clear(&mut self) {
+        self.indices.clear();
+        self.strings.clear();
+    }
+}
+
+/// A table can be used directly as a context
+impl AsMut<StringTable> for StringTable {
+    fn as_mut(&mut self) -> &mut StringTable {
+        self
+    }
+}
@@ -2,8 +2,103 @@
 //! Define encapsulated types
 //!
 
-use super::{ConstSize, Deserialize, Half, Serialize, Unit};
-use godot::{builtin::*, prelude::real_consts::PI};
+use super::{ConstSize, Deserialize, Half, Rigid2D, Serialize, Trs, Unit, Wrapper};
+use godot::{
+    builtin::{math::FloatExt, *},
+    prelude::real_consts::PI,
+};
+
+/// Warn in debug builds when a lossy wrapper drops the scale of a transform.
+#[cfg(debug_assertions)]
+fn warn_dropped_scale(wrapper: &str, has_unit_scale: bool) {
+    if !has_unit_scale {
+        godot::global::godot_warn!(
+            "{} drops the non-unit scale of the transform, use Trs instead to preserve it",
+            wrapper
+        );
+    }
+}
+
+/// Warn in debug builds when a wrapper drops the skew of a transform.
+#[cfg(debug_assertions)]
+fn warn_dropped_skew(wrapper: &str, has_no_skew: bool) {
+    if !has_no_skew {
+        godot::global::godot_warn!(
+            "{} drops the skew of the transform, only rotation and scale are encoded",
+            wrapper
+        );
+    }
+}
+
+/// Check if the axes of the basis are perpendicular, otherwise it holds a skew.
+#[cfg(debug_assertions)]
+fn is_orthogonal(basis: &Basis) -> bool {
+    let [a, b, c] = [basis.col_a(), basis.col_b(), basis.col_c()]
+        .map(|axis| axis.try_normalized().unwrap_or(Vector3::ZERO));
+    a.dot(b).is_zero_approx() && b.dot(c).is_zero_approx() && c.dot(a).is_zero_approx()
+}
+
+/// Extract the rotation of a basis, including a degenerate one with an axis scaled to zero.
+fn rotation_of(basis: &Basis) -> Quaternion {
+    // a negative scale is stored on every axis, remove it to extract the rotation
+    let basis = if basis.determinant() < 0.0 {
+        basis.scaled(-Vector3::ONE)
+    } else {
+        *basis
+    };
+    if !basis.determinant().is_zero_approx() {
+        return basis.get_quaternion();
+    }
+
+    // orthonormalizing a degenerate basis panics, rebuild the missing axes from the remaining ones
+    let axes = [basis.col_a(), basis.col_b(), basis.col_c()];
+    let present = (0..3)
+        .filter(|&index| !axes[index].length_squared().is_zero_approx())
+        .collect::<Vec<_>>();
+    let Some(&first) = present.first() else {
+        return Quaternion::IDENTITY;
+    };
+    let x = axes[first].normalized();
+
+    // the second axis is another one not parallel to the first, or any perpendicular axis
+    let perpendicular = |axis: Vector3| axis - x * x.dot(axis);
+    let (second, y) = present[1..]
+        .iter()
+        .map(|&index| (index, perpendicular(axes[index])))
+        .find(|(_, axis)| !axis.length_squared().is_zero_approx())
+        .unwrap_or_else(|| {
+            let helper = if x.x.abs() < 0.9 {
+                Vector3::RIGHT
+            } else {
+                Vector3::UP
+            };
+            ((first + 1) % 3, perpendicular(helper))
+        });
+    let y = y.normalized();
+
+    // the last axis keeps the basis right-handed
+    let z = if second == (first + 1) % 3 {
+        x.cross(y)
+    } else {
+        y.cross(x)
+    };
+    let mut columns = [Vector3::ZERO; 3];
+    columns[first] = x;
+    columns[second] = y;
+    columns[3 - first - second] = z;
+    let [a, b, c] = columns;
+    Basis::from_cols(a, b, c).get_quaternion()
+}
+
+/// Split a basis into a rotation and a scale.
+fn decompose(basis: &Basis) -> (Quaternion, Vector3) {
+    (rotation_of(basis), basis.get_scale())
+}
+
+/// Rebuild a basis from a rotation and a scale.
+fn compose(rotation: Quaternion, scale: Vector3) -> Basis {
+    Basis::from_quaternion(rotation) * Basis::from_scale(scale)
+}
 
 // implement for basis
 
@@ -43,8 +138,14 @@ impl ConstSize for Unit<Basis> {
 
 impl Serialize for Unit<Basis> {
     fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
+        #[cfg(debug_assertions)]
+        warn_dropped_scale(
+            "Unit<Basis>",
+            (self.0.get_scale() - Vector3::ONE).is_zero_approx(),
+        );
+
         // serialize as quaternion
-        let quat = Unit(self.0.get_quaternion());
+        let quat = Unit(rotation_of(&self.0));
         quat.serialize(buffer, offset)
     }
 }
@@ -93,6 +194,39 @@ impl Deserialize for Half<Basis> {
     }
 }
 
+impl ConstSize for Trs<Basis> {
+    // use quaternion and scale
+    const SIZE: usize = <Half<Quaternion>>::SIZE + <Half<Vector3>>::SIZE;
+}
+
+impl Serialize for Trs<Basis> {
+    fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
+        const QUAT_SIZE: usize = <Half<Quaternion>>::SIZE;
+
+        #[cfg(debug_assertions)]
+        warn_dropped_skew("Trs<Basis>", is_orthogonal(&self.0));
+
+        // encode the basis as a quaternion and a scale
+        let (rotation, scale) = decompose(&self.0);
+        Half(rotation).serialize(buffer, offset)?;
+        Half(scale).serialize(buffer, offset + QUAT_SIZE)
+    }
+}
+
+impl Deserialize for Trs<Basis> {
+    fn deserialize(buffer: &PackedByteArray, offset: usize) -> Result<Self, ()>
+    where
+        Self: Sized,
+    {
+        const QUAT_SIZE: usize = <Half<Quaternion>>::SIZE;
+
+        // decode the basis as a quaternion and a scale
+        let rotation = <Half<Quaternion>>::deserialize(buffer, offset)?.0;
+        let scale = <Half<Vector3>>::deserialize(buffer, offset + QUAT_SIZE)?.0;
+        Ok(Trs(compose(rotation, scale)))
+    }
+}
+
 // implement for transform 2D
 
 impl ConstSize for Transform2D {
@@ -131,6 +265,12 @@ impl Serialize for Unit<Transform2D> {
     fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
         const FACTOR: real = 127.0 / PI;
 
+        #[cfg(debug_assertions)]
+        warn_dropped_scale(
+            "Unit<Transform2D>",
+            (self.0.scale() - Vector2::ONE).is_zero_approx() && self.0.skew().is_zero_approx(),
+        );
+
         // encode the angle as a single byte
         let angle = (self.0.rotation() * FACTOR) as i8;
         buffer.encode_s8(offset, angle)?;
@@ -179,6 +319,86 @@ impl Deserialize for Half<Transform2D> {
     }
 }
 
+impl ConstSize for Trs<Transform2D> {
+    // use angle, scale and position
+    const SIZE: usize = f32::SIZE + <Half<Vector2>>::SIZE + Vector2::SIZE;
+}
+
+impl Serialize for Trs<Transform2D> {
+    #[allow(clippy::unnecessary_cast)]
+    fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
+        const SCALE_OFFSET: usize = f32::SIZE;
+        const ORIGIN_OFFSET: usize = SCALE_OFFSET + <Half<Vector2>>::SIZE;
+
+        #[cfg(debug_assertions)]
+        warn_dropped_skew("Trs<Transform2D>", self.0.skew().is_zero_approx());
+
+        buffer.encode_float(offset, self.0.rotation() as f32)?;
+        Half(self.0.scale()).serialize(buffer, offset + SCALE_OFFSET)?;
+        self.0.origin.serialize(buffer, offset + ORIGIN_OFFSET)
+    }
+}
+
+impl Deserialize for Trs<Transform2D> {
+    fn deserialize(buffer: &PackedByteArray, offset: usize) -> Result<Self, ()>
+    where
+        Self: Sized,
+    {
+        const SCALE_OFFSET: usize = f32::SIZE;
+        const ORIGIN_OFFSET: usize = SCALE_OFFSET + <Half<Vector2>>::SIZE;
+
+        let angle = buffer.decode_float(offset)? as real;
+        let scale = <Half<Vector2>>::deserialize(buffer, offset + SCALE_OFFSET)?.0;
+        let origin = Vector2::deserialize(buffer, offset + ORIGIN_OFFSET)?;
+        Ok(Trs(Transform2D::from_angle_scale_skew_origin(
+            angle, scale, 0.0, origin,
+        )))
+    }
+}
+
+impl<A, O> ConstSize for Rigid2D<A, O>
+where
+    A: ConstSize,
+    O: ConstSize,
+{
+    // use angle and position
+    const SIZE: usize = A::SIZE + O::SIZE;
+}
+
+impl<A, O> Serialize for Rigid2D<A, O>
+where
+    A: Wrapper<real> + Serialize + ConstSize,
+    O: Wrapper<Vector2> + Serialize,
+{
+    // serialize an angle and a position
+    fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
+        #[cfg(debug_assertions)]
+        warn_dropped_scale(
+            "Rigid2D",
+            (self.0.scale() - Vector2::ONE).is_zero_approx() && self.0.skew().is_zero_approx(),
+        );
+
+        A::wrap(self.0.rotation()).serialize(buffer, offset)?;
+        O::wrap(self.0.origin).serialize(buffer, offset + A::SIZE)
+    }
+}
+
+impl<A, O> Deserialize for Rigid2D<A, O>
+where
+    A: Wrapper<real> + Deserialize + ConstSize,
+    O: Wrapper<Vector2> + Deserialize,
+{
+    // deserialize an angle and a position
+    fn deserialize(buffer: &PackedByteArray, offset: usize) -> Result<Self, ()>
+    where
+        Self: Sized,
+    {
+        let angle = A::deserialize(buffer, offset)?.into_inner();
+        let origin = O::deserialize(buffer, offset + A::SIZE)?.into_inner();
+        Ok(Rigid2D::new(Transform2D::from_angle_origin(angle, origin)))
+    }
+}
+
 // implement for transform 3D
 
 impl ConstSize for Transform3D {
@@ -212,8 +432,14 @@ impl Serialize for Unit<Transform3D> {
     fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
         const QUAT_SIZE: usize = <Unit<Quaternion>>::SIZE;
 
+        #[cfg(debug_assertions)]
+        warn_dropped_scale(
+            "Unit<Transform3D>",
+            (self.0.basis.get_scale() - Vector3::ONE).is_zero_approx(),
+        );
+
         // encode the transform as a quaternion and a position
-        Unit(self.0.basis.get_quaternion()).serialize(buffer, offset)?;
+        Unit(rotation_of(&self.0.basis)).serialize(buffer, offset)?;
         self.0.origin.serialize(buffer, offset + QUAT_SIZE)
     }
 }
@@ -256,3 +482,34 @@ impl Deserialize for Half<Transform3D> {
         Ok(Half(Transform3D { basis, origin }))
     }
 }
+
+impl ConstSize for Trs<Transform3D> {
+    // use quaternion, scale and position
+    const SIZE: usize = <Trs<Basis>>::SIZE + Vector3::SIZE;
+}
+
+impl Serialize for Trs<Transform3D> {
+    fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
+        const SIZE: usize = <Trs<Basis>>::SIZE;
+
+        #[cfg(debug_assertions)]
+        warn_dropped_skew("Trs<Transform3D>", is_orthogonal(&self.0.basis));
+
+        Trs(self.0.basis).serialize(buffer, offset)?;
+        self.0.origin.serialize(buffer, offset + SIZE)
+    }
+}
+
+impl Deserialize for Trs<Transform3D> {
+    fn deserialize(buffer: &PackedByteArray, offset: usize) -> Result<Self, ()>
+    where
+        Self: Sized,
+    {
+        const SIZE: usize = <Trs<Basis>>::SIZE;
+        let basis = <Trs<Basis>>::deserialize(buffer, offset)?.0;
+        let origin = Vector3::deserialize(buffer, offset + SIZE)?;
+        Ok(Trs(Transform3D { basis, origin }))
+    }
+}
+
+crate::impl_contextless![[] Basis, [] Transform2D, [] Transform3D];
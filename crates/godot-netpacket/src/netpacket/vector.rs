@@ -3,7 +3,9 @@
 //!
 
 use super::{
-    ConstSize, Deserialize, HALF_SIZE, Half, Serialize, UNIT_SIZE, Unit, from_unit, to_unit,
+    Cell, ConstSize, Deserialize, HALF_SIZE, Half, Relative, SNorm16, Serialize, UNIT_SIZE, UNorm8,
+    UNorm16, Unit, from_snorm16, from_unit, from_unorm8, from_unorm16, to_cell, to_snorm16,
+    to_unit, to_unorm8, to_unorm16,
 };
 use godot::builtin::{math::FloatExt, *};
 
@@ -111,6 +113,11 @@ impl_packet![ Vector3    [ real => f32 ] [3] ; encode_float ; decode_float ];
 impl_packet![ Vector4    [ real => f32 ] [4] ; encode_float ; decode_float ];
 impl_packet![ Quaternion [ real => f32 ] [4] ; encode_float ; decode_float ];
 
+crate::impl_contextless![
+    [] Vector2i, [] Vector3i, [] Vector4i,
+    [] Vector2, [] Vector3, [] Vector4, [] Quaternion,
+];
+
 // macros
 
 macro_rules! impl_serial_field {
@@ -383,3 +390,132 @@ impl Deserialize for Half<Quaternion> {
         }
     }
 }
+
+/// Implement traits for normalized wrappers of vector types
+macro_rules! impl_norm {
+    ( $wrap:ident < $vec:ident { $( $field:ident ),+ } > [ $count:literal ] => $stored:ty ; $to:ident ; $from:ident ; $encode:ident ; $decode:ident ) => {
+        impl<const STRICT: bool> ConstSize for $wrap<$vec, STRICT> {
+            const SIZE: usize = size_of::<$stored>() * $count;
+        }
+        impl<const STRICT: bool> Serialize for $wrap<$vec, STRICT> {
+            #[inline]
+            fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
+                const SIZE: usize = size_of::<$stored>();
+                let nums = [ $( self.0.$field ),+ ];
+                for (index, num) in nums.into_iter().enumerate() {
+                    buffer.$encode(offset + (SIZE * index), $to(num, STRICT)?)?;
+                }
+                Ok(())
+            }
+        }
+        impl<const STRICT: bool> Deserialize for $wrap<$vec, STRICT> {
+            #[inline]
+            fn deserialize(buffer: &PackedByteArray, offset: usize) -> Result<Self, ()>
+            where
+                Self: Sized,
+            {
+                const SIZE: usize = size_of::<$stored>();
+                let mut nums: [real; $count] = [0.0; $count];
+                for (index, num) in nums.iter_mut().enumerate() {
+                    *num = $from(buffer.$decode(offset + (SIZE * index))?);
+                }
+                let [ $( $field ),+ ] = nums;
+                Ok(Self($vec { $( $field ),+ }))
+            }
+        }
+    };
+}
+
+// implement normalized wrappers for vector types
+
+impl_norm![ UNorm8<Vector2 { x, y }>       [2] => u8 ; to_unorm8 ; from_unorm8 ; encode_u8 ; decode_u8 ];
+impl_norm![ UNorm8<Vector3 { x, y, z }>    [3] => u8 ; to_unorm8 ; from_unorm8 ; encode_u8 ; decode_u8 ];
+impl_norm![ UNorm8<Vector4 { x, y, z, w }> [4] => u8 ; to_unorm8 ; from_unorm8 ; encode_u8 ; decode_u8 ];
+
+impl_norm![ UNorm16<Vector2 { x, y }>       [2] => u16 ; to_unorm16 ; from_unorm16 ; encode_u16 ; decode_u16 ];
+impl_norm![ UNorm16<Vector3 { x, y, z }>    [3] => u16 ; to_unorm16 ; from_unorm16 ; encode_u16 ; decode_u16 ];
+impl_norm![ UNorm16<Vector4 { x, y, z, w }> [4] => u16 ; to_unorm16 ; from_unorm16 ; encode_u16 ; decode_u16 ];
+
+impl_norm![ SNorm16<Vector2 { x, y }>          [2] => i16 ; to_snorm16 ; from_snorm16 ; encode_s16 ; decode_s16 ];
+impl_norm![ SNorm16<Vector3 { x, y, z }>       [3] => i16 ; to_snorm16 ; from_snorm16 ; encode_s16 ; decode_s16 ];
+impl_norm![ SNorm16<Vector4 { x, y, z, w }>    [4] => i16 ; to_snorm16 ; from_snorm16 ; encode_s16 ; decode_s16 ];
+impl_norm![ SNorm16<Quaternion { x, y, z, w }> [4] => i16 ; to_snorm16 ; from_snorm16 ; encode_s16 ; decode_s16 ];
+
+/// Implement traits for positions stored as a cell and a local offset
+macro_rules! impl_cell {
+    ( $vec:ident { $( $field:ident ),+ } [ $count:literal ] => $stored:ty ; $encode:ident ; $decode:ident ) => {
+        impl<const CELL_SIZE: u32> ConstSize for Cell<$vec, CELL_SIZE> {
+            const SIZE: usize = size_of::<$stored>() * $count + <UNorm16<$vec>>::SIZE;
+        }
+        impl<const CELL_SIZE: u32> Serialize for Cell<$vec, CELL_SIZE> {
+            fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
+                const { assert!(CELL_SIZE > 0, "the size of a cell cannot be zero") };
+
+                // split the position into a cell and an offset in the range [0.0, 1.0[
+                let position = self.0 / CELL_SIZE as real;
+                let cell = position.floor();
+                let local = position - cell;
+
+                let mut at = offset;
+                $(
+                    buffer.$encode(at, to_cell(cell.$field)?)?;
+                    at += size_of::<$stored>();
+                )+
+                UNorm16::<$vec>(local).serialize(buffer, at)
+            }
+        }
+        impl<const CELL_SIZE: u32> Deserialize for Cell<$vec, CELL_SIZE> {
+            fn deserialize(buffer: &PackedByteArray, offset: usize) -> Result<Self, ()>
+            where
+                Self: Sized,
+            {
+                let mut at = offset;
+                let cell = $vec {
+                    $( $field: {
+                        let axis = buffer.$decode(at)?;
+                        at += size_of::<$stored>();
+                        axis as real
+                    } ),+
+                };
+                let local = <UNorm16<$vec>>::deserialize(buffer, at)?.0;
+                Ok(Cell((cell + local) * CELL_SIZE as real))
+            }
+        }
+    };
+}
+
+// implement cell for vector types
+
+impl_cell![ Vector2 { x, y }    [2] => i32 ; encode_s32 ; decode_s32 ];
+impl_cell![ Vector3 { x, y, z } [3] => i32 ; encode_s32 ; decode_s32 ];
+
+// implement relative positions
+
+impl<T> ConstSize for Relative<T>
+where
+    Half<T>: ConstSize,
+{
+    const SIZE: usize = <Half<T>>::SIZE;
+}
+impl<T> Serialize for Relative<T>
+where
+    T: Copy,
+    Half<T>: Serialize,
+{
+    #[inline]
+    fn serialize(&self, buffer: &mut PackedByteArray, offset: usize) -> Result<(), ()> {
+        Half(self.0).serialize(buffer, offset)
+    }
+}
+impl<T> Deserialize for Relative<T>
+where
+    Half<T>: Deserialize,
+{
+    #[inline]
+    fn deserialize(buffer: &PackedByteArray, offset: usize) -> Result<Self, ()>
+    where
+        Self: Sized,
+    {
+        <Half<T>>::deserialize(buffer, offset).map(|half| Relative(half.0))
+    }
+}